@@ -130,6 +130,19 @@ fn main() {
     // Parse our arguments and split them across `rustc` and `miri`.
     let mut validate = true;
     let mut seed: Option<u64> = None;
+    let mut emit_output = None;
+    let mut cmpxchg_weak_failure_rate = 0.0;
+    let mut strict_provenance = false;
+    let mut allocation_padding = 0;
+    let mut randomize_heap_layout = false;
+    let mut tag_raw_pointers = false;
+    let mut tree_borrows = false;
+    let mut trace = None;
+    let mut event_log = None;
+    let mut print_alloc_ids = vec![];
+    let mut check_abi = false;
+    let mut env_forward = vec![];
+    let mut reproducibility_check = false;
     let mut rustc_args = vec![];
     let mut miri_args = vec![];
     let mut after_dashdash = false;
@@ -146,6 +159,24 @@ fn main() {
                 "-Zmiri-disable-validation" => {
                     validate = false;
                 },
+                "-Zmiri-strict-provenance" => {
+                    strict_provenance = true;
+                },
+                "--tag-raw-pointers" => {
+                    tag_raw_pointers = true;
+                },
+                "-Zmiri-randomize-heap-layout" => {
+                    randomize_heap_layout = true;
+                },
+                "-Zmiri-tree-borrows" => {
+                    tree_borrows = true;
+                },
+                "--check-abi" => {
+                    check_abi = true;
+                },
+                "-Zmiri-reproducibility-check" => {
+                    reproducibility_check = true;
+                },
                 "--" => {
                     after_dashdash = true;
                 }
@@ -163,6 +194,46 @@ fn main() {
                     seed = Some(u64::from_be_bytes(bytes));
 
                 },
+                arg if arg.starts_with("--emit-miri-output=") => {
+                    emit_output = Some(match arg.trim_start_matches("--emit-miri-output=") {
+                        "json" => miri::MemoryDumpFormat::Json,
+                        "binary" => miri::MemoryDumpFormat::Binary,
+                        format => panic!(format!("--emit-miri-output must be `json` or `binary`, was `{}`", format)),
+                    });
+                },
+                arg if arg.starts_with("-Zmiri-trace=") => {
+                    trace = Some(arg.trim_start_matches("-Zmiri-trace=").to_string());
+                },
+                arg if arg.starts_with("-Zmiri-event-log=") => {
+                    event_log = Some(arg.trim_start_matches("-Zmiri-event-log=").to_string());
+                },
+                arg if arg.starts_with("--print-alloc-id=") => {
+                    let id = arg.trim_start_matches("--print-alloc-id=");
+                    print_alloc_ids.push(u64::from_str(id).unwrap_or_else(|_| panic!(format!(
+                        "--print-alloc-id must be an allocation ID, was `{}`",
+                        id,
+                    ))));
+                },
+                arg if arg.starts_with("-Zmiri-env-forward=") => {
+                    env_forward.push(arg.trim_start_matches("-Zmiri-env-forward=").to_owned());
+                },
+                arg if arg.starts_with("-Zmiri-allocation-padding=") => {
+                    let padding = arg.trim_start_matches("-Zmiri-allocation-padding=");
+                    allocation_padding = u32::from_str(padding).unwrap_or_else(|_| panic!(format!(
+                        "-Zmiri-allocation-padding must be a number of bytes, was `{}`",
+                        padding,
+                    )));
+                },
+                arg if arg.starts_with("-Zmiri-compare-exchange-weak-failure-rate=") => {
+                    let rate = arg.trim_start_matches("-Zmiri-compare-exchange-weak-failure-rate=");
+                    cmpxchg_weak_failure_rate = match f64::from_str(rate) {
+                        Ok(rate) if rate >= 0.0 && rate <= 1.0 => rate,
+                        _ => panic!(format!(
+                            "-Zmiri-compare-exchange-weak-failure-rate must be between 0.0 and 1.0, was `{}`",
+                            rate,
+                        )),
+                    };
+                },
                 _ => {
                     rustc_args.push(arg);
                 }
@@ -170,6 +241,13 @@ fn main() {
         }
     }
 
+    if cmpxchg_weak_failure_rate > 0.0 && seed.is_none() {
+        panic!("-Zmiri-compare-exchange-weak-failure-rate requires -Zmiri-seed");
+    }
+    if reproducibility_check && seed.is_none() {
+        panic!("-Zmiri-reproducibility-check requires -Zmiri-seed");
+    }
+
     // Determine sysroot if needed.  Make sure we always call `compile_time_sysroot`
     // as that also does some sanity-checks of the environment we were built in.
     // FIXME: Ideally we'd turn a bad build env into a compile-time error, but
@@ -188,7 +266,24 @@ fn main() {
 
     debug!("rustc arguments: {:?}", rustc_args);
     debug!("miri arguments: {:?}", miri_args);
-    let miri_config = miri::MiriConfig { validate, args: miri_args, seed };
+    let miri_config = miri::MiriConfig {
+        validate,
+        args: miri_args,
+        seed,
+        emit_output,
+        cmpxchg_weak_failure_rate,
+        strict_provenance,
+        allocation_padding,
+        randomize_heap_layout,
+        tag_raw_pointers,
+        tree_borrows,
+        trace,
+        event_log,
+        print_alloc_ids,
+        check_abi,
+        env_forward,
+        reproducibility_check,
+    };
     let result = rustc_driver::report_ices_to_stderr_if_any(move || {
         rustc_driver::run_compiler(&rustc_args, &mut MiriCompilerCalls { miri_config }, None, None)
     }).and_then(|result| result);
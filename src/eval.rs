@@ -1,7 +1,11 @@
+use std::env;
+use std::ffi::OsString;
+
 use rand::rngs::StdRng;
 use rand::SeedableRng;
 
 use syntax::source_map::DUMMY_SP;
+use syntax::symbol::Symbol;
 use rustc::ty::{self, TyCtxt};
 use rustc::ty::layout::{LayoutOf, Size, Align};
 use rustc::hir::def_id::DefId;
@@ -9,18 +13,58 @@ use rustc::mir;
 
 use crate::{
     InterpResult, InterpError, InterpretCx, StackPopCleanup, struct_error,
-    Scalar, Tag, Pointer,
-    MemoryExtra, MiriMemoryKind, Evaluator, TlsEvalContextExt,
+    Scalar, Tag, Pointer, GlobalId, MPlaceTy,
+    MemoryExtra, MiriMemoryKind, Evaluator, TlsEvalContextExt, MemoryDumpFormat, dump_memory,
+    ChromeTrace, EventLog, AllocId, print_allocation, MemorySnapshot,
 };
 
 /// Configuration needed to spawn a Miri instance.
-#[derive(Clone)]
+#[derive(Clone, Default)]
 pub struct MiriConfig {
     pub validate: bool,
     pub args: Vec<String>,
 
     // The seed to use when non-determinism is required (e.g. getrandom())
-    pub seed: Option<u64>
+    pub seed: Option<u64>,
+
+    // Dump the heap in this format when the program exits successfully.
+    pub emit_output: Option<MemoryDumpFormat>,
+
+    // The probability with which `compare_exchange_weak` fails spuriously. Needs a seed.
+    pub cmpxchg_weak_failure_rate: f64,
+
+    // Only allow casting integers to pointers that came from pointer-to-integer casts.
+    pub strict_provenance: bool,
+
+    // The maximum number of bytes of padding to put between the addresses of allocations.
+    pub allocation_padding: u32,
+
+    // Put allocations at random places in 1 to 7 pages of addresses reserved for each of them.
+    pub randomize_heap_layout: bool,
+
+    // Give raw pointers their own tags, so that accesses through them are checked, too.
+    pub tag_raw_pointers: bool,
+
+    // Check with Tree Borrows instead of Stacked Borrows.
+    pub tree_borrows: bool,
+
+    // Write a Chrome trace of all calls to this file.
+    pub trace: Option<String>,
+
+    // Log significant events like allocations and foreign calls to this file.
+    pub event_log: Option<String>,
+
+    // Print the contents of these allocations to stderr when the program exits.
+    pub print_alloc_ids: Vec<u64>,
+
+    // Check calls of foreign functions against their C signatures.
+    pub check_abi: bool,
+
+    // The host env vars to give to the program, or all of them if this contains `*`.
+    pub env_forward: Vec<String>,
+
+    // Run the program a second time with the next seed, and compare the final memory.
+    pub reproducibility_check: bool,
 }
 
 // Used by priroda.
@@ -37,6 +81,34 @@ pub fn create_ecx<'mir, 'tcx: 'mir>(
 
     // FIXME: InterpretCx::new should take an initial MemoryExtra
     ecx.memory_mut().extra = MemoryExtra::with_rng(config.seed.map(StdRng::seed_from_u64));
+    {
+        let mut intptrcast = ecx.memory_mut().extra.intptrcast.borrow_mut();
+        intptrcast.strict_provenance = config.strict_provenance;
+        intptrcast.allocation_padding = config.allocation_padding;
+        intptrcast.randomize_heap_layout = config.randomize_heap_layout;
+    }
+    ecx.memory_mut().extra.stacked_borrows.borrow_mut().tag_raw_pointers = config.tag_raw_pointers;
+    ecx.memory_mut().extra.stacked_borrows.borrow_mut().tree_borrows = config.tree_borrows;
+    ecx.machine.cmpxchg_weak_failure_rate = config.cmpxchg_weak_failure_rate;
+    ecx.machine.check_abi = config.check_abi;
+    if let Some(ref path) = config.trace {
+        let trace = ChromeTrace::create(path).map_err(|err| InterpError::MachineError(
+            format!("failed to create the trace file {}: {}", path, err),
+        ))?;
+        ecx.machine.trace = Some(trace);
+    }
+    if let Some(ref path) = config.event_log {
+        let event_log = EventLog::create(path).map_err(|err| InterpError::MachineError(
+            format!("failed to create the event log {}: {}", path, err),
+        ))?;
+        ecx.machine.event_log = Some(event_log);
+    }
+    if config.emit_output.is_some() {
+        ecx.machine.alloc_sites = Some(Default::default());
+    }
+    if config.reproducibility_check {
+        ecx.memory_mut().extra.allocations = Some(Default::default());
+    }
     
     let main_instance = ty::Instance::mono(ecx.tcx.tcx, main_id);
     let main_mir = ecx.load_mir(main_instance.def)?;
@@ -164,6 +236,85 @@ pub fn create_ecx<'mir, 'tcx: 'mir>(
  
     assert!(args.next().is_none(), "start lang item has more arguments than expected");
 
+    // Initialize the last error (`errno` on Unix, `GetLastError` on Windows).
+    let errno_layout = ecx.layout_of(ecx.tcx.types.u32)?;
+    let errno_place = ecx.allocate(errno_layout, MiriMemoryKind::Static.into());
+    ecx.write_scalar(Scalar::from_u32(0), errno_place.into())?;
+    ecx.machine.last_error = Some(errno_place);
+
+    // Forward the env vars from the host that we were asked to.
+    {
+        let tcx = &{ecx.tcx.tcx};
+        let vars: Vec<(OsString, OsString)> = if config.env_forward.iter().any(|name| name == "*") {
+            env::vars_os().collect()
+        } else {
+            config.env_forward.iter()
+                .filter_map(|name| Some((OsString::from(name), env::var_os(name)?)))
+                .collect()
+        };
+        for (name, value) in vars {
+            let name = name.to_string_lossy().into_owned().into_bytes();
+            // Windows has some odd variables like `=C:`, which `setenv` would not accept.
+            if name.is_empty() || name.contains(&b'=') {
+                continue;
+            }
+            let value = value.to_string_lossy();
+            // `+1` for the null terminator.
+            let value_copy = ecx.memory_mut().allocate(
+                Size::from_bytes((value.len() + 1) as u64),
+                Align::from_bytes(1).unwrap(),
+                MiriMemoryKind::Env.into(),
+            );
+            let alloc = ecx.memory_mut().get_mut(value_copy.alloc_id)?;
+            alloc.write_bytes(tcx, value_copy, value.as_bytes())?;
+            let trailing_zero_ptr = value_copy.offset(Size::from_bytes(value.len() as u64), tcx)?;
+            alloc.write_bytes(tcx, trailing_zero_ptr, &[0])?;
+            ecx.machine.env_vars.insert(name, value_copy);
+        }
+    }
+
+    // Collect the constructors in `.init_array`, like the C runtime does before calling `main`.
+    let mut constructors = Vec::new();
+    for def_id in tcx.body_owners() {
+        if tcx.is_static(def_id).is_none()
+            || tcx.codegen_fn_attrs(def_id).link_section != Some(Symbol::intern(".init_array"))
+        {
+            continue;
+        }
+        let cid = GlobalId { instance: ty::Instance::mono(tcx, def_id), promoted: None };
+        let place = ecx.const_eval_raw(cid)?;
+        // The static is either a single function pointer or an array of them.
+        let fn_ptrs = match place.layout.ty.sty {
+            ty::Array(..) => (0..place.len(&ecx)?)
+                .map(|idx| ecx.mplace_field(place, idx))
+                .collect::<InterpResult<'_, Vec<_>>>()?,
+            _ => vec![place],
+        };
+        for fn_ptr in fn_ptrs {
+            let fn_ptr = ecx.read_scalar(fn_ptr.into())?.to_ptr()?;
+            constructors.push(ecx.memory().get_fn(fn_ptr)?);
+        }
+    }
+    // Push their stack frames on top of the one for `start`. The frame pushed last runs first,
+    // and when a constructor returns, the next one (or `start`) continues.
+    for instance in constructors.into_iter().rev() {
+        let mir = ecx.load_mir(instance.def)?;
+        if mir.arg_count != 0 {
+            return err!(Unimplemented(format!(
+                "`.init_array` constructors with arguments are not supported: {}", instance,
+            )));
+        }
+        let ret_place = MPlaceTy::dangling(ecx.layout_of(tcx.mk_unit())?, &ecx).into();
+        ecx.push_stack_frame(
+            instance,
+            // There is no call site.
+            DUMMY_SP,
+            mir,
+            Some(ret_place),
+            StackPopCleanup::None { cleanup: true },
+        )?;
+    }
+
     Ok(ecx)
 }
 
@@ -172,6 +323,22 @@ pub fn eval_main<'tcx>(
     main_id: DefId,
     config: MiriConfig,
 ) {
+    let emit_output = config.emit_output;
+    let print_alloc_ids = config.print_alloc_ids.clone();
+    // For the reproducibility check, first run the program with the next seed to see what
+    // its memory looks like in the end.
+    let other_run = if config.reproducibility_check {
+        let mut other_config = config.clone();
+        other_config.seed = config.seed.map(|seed| seed.wrapping_add(1));
+        other_config.emit_output = None;
+        other_config.trace = None;
+        other_config.event_log = None;
+        other_config.print_alloc_ids = vec![];
+        run_for_snapshot(tcx, main_id, other_config)
+    } else {
+        None
+    };
+    let program_name = config.args.first().cloned().unwrap_or_default();
     let mut ecx = match create_ecx(tcx, main_id, config) {
         Ok(ecx) => ecx,
         Err(mut err) => {
@@ -182,13 +349,39 @@ pub fn eval_main<'tcx>(
 
     // Perform the main execution.
     let res: InterpResult = (|| {
-        ecx.run()?;
+        ecx.run_recording_errors()?;
         ecx.run_tls_dtors()
     })();
 
+    if let Some(trace) = ecx.machine.trace.take() {
+        if let Err(err) = trace.finish(ecx.machine.fake_clock) {
+            tcx.sess.warn(&format!("failed to write the trace: {}", err));
+        }
+    }
+    if let Some(event_log) = ecx.machine.event_log.take() {
+        if let Err(err) = event_log.finish() {
+            tcx.sess.warn(&format!("failed to write the event log: {}", err));
+        }
+    }
+
+    for id in print_alloc_ids {
+        print_allocation(&ecx, AllocId(id));
+    }
+
     // Process the result.
     match res {
         Ok(()) => {
+            if let Some(other_snapshot) = other_run {
+                for difference in MemorySnapshot::take(&ecx).differences(&other_snapshot) {
+                    tcx.sess.err(&format!("the final memory depends on the seed: {}", difference));
+                }
+            }
+            if let Some(format) = emit_output {
+                let path = format!("{}.miri.{}", program_name, format.extension());
+                if let Err(err) = dump_memory(&ecx, format, &path) {
+                    tcx.sess.err(&format!("failed to write the memory dump to {}: {}", path, err));
+                }
+            }
             let leaks = ecx.memory().leak_report();
             // Disable the leak test on some platforms where we do not
             // correctly implement TLS destructors.
@@ -230,6 +423,17 @@ pub fn eval_main<'tcx>(
                         err.note(&frame_info.to_string());
                     }
                 }
+                // Errors that did not end the evaluation were swallowed somewhere, which can
+                // explain how we got here.
+                for (idx, (kind, frames)) in ecx.machine.error_history.iter().enumerate() {
+                    if Some(idx) == ecx.machine.final_error {
+                        continue;
+                    }
+                    match frames.first() {
+                        Some(frame) => err.note(&format!("earlier error: {}, {}", kind, frame)),
+                        None => err.note(&format!("earlier error: {}", kind)),
+                    };
+                }
                 err.emit();
             } else {
                 ecx.tcx.sess.err(&msg);
@@ -246,3 +450,28 @@ pub fn eval_main<'tcx>(
         }
     }
 }
+
+/// Runs the program to completion and takes a snapshot of the final memory, for the
+/// reproducibility check. Returns `None` if the run fails.
+fn run_for_snapshot<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    main_id: DefId,
+    config: MiriConfig,
+) -> Option<MemorySnapshot> {
+    let seed = config.seed;
+    let res = create_ecx(tcx, main_id, config).and_then(|mut ecx| {
+        ecx.run()?;
+        ecx.run_tls_dtors()?;
+        Ok(MemorySnapshot::take(&ecx))
+    });
+    match res {
+        Ok(snapshot) => Some(snapshot),
+        Err(err) => {
+            tcx.sess.warn(&format!(
+                "skipping the reproducibility check: the run with seed {:#x} failed: {}",
+                seed.unwrap_or_default(), err,
+            ));
+            None
+        }
+    }
+}
@@ -0,0 +1,114 @@
+//! Logging the significant events of the interpreted program for `-Zmiri-event-log`.
+//!
+//! Every event is written as one JSON object per line, with the value of our fake clock, the
+//! kind of event and its details, and the ID of the stack trace at that point. The stack traces
+//! themselves are written once each, in a final line `{"stacks":{"<id>":[<frames>],...}}`.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+
+use crate::*;
+use crate::memory_dump::escape_json;
+
+/// An event worth logging.
+#[derive(Debug)]
+pub enum Event<'a> {
+    /// A heap allocation was created.
+    Alloc { ptr: Pointer<Tag>, size: u64, kind: MiriMemoryKind },
+    /// A heap allocation was freed.
+    Dealloc { ptr: Pointer<Tag> },
+    /// A foreign function was called.
+    ForeignCall { name: &'a str },
+    /// A TLS key was created.
+    TlsCreate { key: u128 },
+    /// A TLS key was deleted.
+    TlsDelete { key: u128 },
+}
+
+impl<'a> Event<'a> {
+    fn to_json(&self) -> String {
+        match *self {
+            Event::Alloc { ptr, size, kind } => format!(
+                "\"event\":\"alloc\",\"alloc\":{},\"size\":{},\"kind\":\"{:?}\"",
+                ptr.alloc_id.0, size, kind,
+            ),
+            Event::Dealloc { ptr } => format!(
+                "\"event\":\"dealloc\",\"alloc\":{},\"offset\":{}",
+                ptr.alloc_id.0, ptr.offset.bytes(),
+            ),
+            Event::ForeignCall { name } => format!(
+                "\"event\":\"foreign_call\",\"name\":\"{}\"",
+                escape_json(name),
+            ),
+            Event::TlsCreate { key } => format!("\"event\":\"tls_create\",\"key\":{}", key),
+            Event::TlsDelete { key } => format!("\"event\":\"tls_delete\",\"key\":{}", key),
+        }
+    }
+}
+
+pub struct EventLog {
+    out: BufWriter<File>,
+    /// The IDs of the stack traces we have seen so far.
+    stack_ids: HashMap<Vec<String>, usize>,
+    /// The first error we got when writing, which `finish` reports.
+    error: Option<io::Error>,
+}
+
+impl EventLog {
+    pub fn create(path: &str) -> io::Result<Self> {
+        Ok(EventLog {
+            out: BufWriter::new(File::create(path)?),
+            stack_ids: HashMap::new(),
+            error: None,
+        })
+    }
+
+    fn write(&mut self, event: &Event<'_>, clock: u64, stack: Vec<String>) {
+        if self.error.is_some() {
+            return;
+        }
+        let next_id = self.stack_ids.len();
+        let stack_id = *self.stack_ids.entry(stack).or_insert(next_id);
+        let line = format!("{{\"ts\":{},{},\"stack\":{}}}\n", clock, event.to_json(), stack_id);
+        if let Err(err) = self.out.write_all(line.as_bytes()) {
+            self.error = Some(err);
+        }
+    }
+
+    /// Writes the stack traces and finishes the file.
+    pub fn finish(mut self) -> io::Result<()> {
+        if let Some(err) = self.error {
+            return Err(err);
+        }
+        let mut stacks: Vec<(Vec<String>, usize)> = self.stack_ids.into_iter().collect();
+        stacks.sort_by_key(|&(_, id)| id);
+        let mut out = String::from("{\"stacks\":{");
+        for (i, (frames, id)) in stacks.into_iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            let frames: Vec<String> = frames.iter()
+                .map(|frame| format!("\"{}\"", escape_json(frame)))
+                .collect();
+            out.push_str(&format!("\"{}\":[{}]", id, frames.join(",")));
+        }
+        out.push_str("}}\n");
+        self.out.write_all(out.as_bytes())?;
+        self.out.flush()
+    }
+}
+
+impl<'mir, 'tcx> EvalContextExt<'mir, 'tcx> for crate::MiriEvalContext<'mir, 'tcx> {}
+pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx> {
+    /// Writes `event` to the event log, if we are keeping one.
+    fn log_event(&mut self, event: Event<'_>) {
+        let this = self.eval_context_mut();
+        if this.machine.event_log.is_none() {
+            return;
+        }
+        let stack = this.generate_stacktrace(None).iter().map(ToString::to_string).collect();
+        let clock = this.machine.fake_clock;
+        this.machine.event_log.as_mut().unwrap().write(&event, clock, stack);
+    }
+}
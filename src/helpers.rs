@@ -2,6 +2,7 @@ use std::mem;
 
 use rustc::ty::{self, layout::{self, Size}};
 use rustc::hir::def_id::{DefId, CRATE_DEF_INDEX};
+use syntax::source_map::Span;
 
 use crate::*;
 
@@ -43,6 +44,150 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
             })
     }
 
+    /// Returns the span of the statement or terminator that is currently being executed.
+    fn current_span(&self) -> Span {
+        let this = self.eval_context_ref();
+        let frame = this.frame();
+        let block = &frame.body.basic_blocks()[frame.block];
+        if frame.stmt < block.statements.len() {
+            block.statements[frame.stmt].source_info.span
+        } else {
+            block.terminator().source_info.span
+        }
+    }
+
+    /// Remembers where the program allocated `ptr`, if we are going to dump the heap at the end.
+    fn record_alloc_site(&mut self, ptr: Pointer<Tag>, kind: MiriMemoryKind) {
+        let this = self.eval_context_mut();
+        if this.machine.alloc_sites.is_some() {
+            let site = this.tcx.sess.source_map().span_to_string(this.current_span());
+            this.machine.alloc_sites.as_mut().unwrap().insert(ptr.alloc_id, (kind, site));
+        }
+        if this.machine.event_log.is_some() {
+            // We just allocated this, so it exists.
+            let size = this.memory().get(ptr.alloc_id).unwrap().bytes.len() as u64;
+            this.log_event(Event::Alloc { ptr, size, kind });
+        }
+    }
+
+    /// Records `res` in the error history if it is an error, and passes it on.
+    fn record_error<T>(&mut self, res: InterpResult<'tcx, T>) -> InterpResult<'tcx, T> {
+        let this = self.eval_context_mut();
+        if let Err(ref err) = res {
+            let frames = this.generate_stacktrace(None);
+            this.machine.error_history.push((err.kind.clone(), frames));
+        }
+        res
+    }
+
+    /// Like `run`, but records the error that ends the evaluation in the error history, and
+    /// remembers which entry it is.
+    fn run_recording_errors(&mut self) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+        loop {
+            let recorded = this.machine.error_history.len();
+            match this.step() {
+                Ok(true) => {}
+                Ok(false) => return Ok(()),
+                Err(err) => {
+                    // An error from one of our hooks was recorded there, and `?` propagated it
+                    // out of the step.
+                    if this.machine.error_history.len() == recorded {
+                        let frames = this.generate_stacktrace(None);
+                        this.machine.error_history.push((err.kind.clone(), frames));
+                    }
+                    this.machine.final_error = Some(this.machine.error_history.len() - 1);
+                    return Err(err);
+                }
+            }
+        }
+    }
+
+    /// Sets the last error variable (`errno` on Unix, `GetLastError` on Windows).
+    fn set_last_error(&mut self, scalar: Scalar<Tag>) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+        let errno_place = this.machine.last_error.unwrap();
+        this.write_scalar(scalar, errno_place.into())
+    }
+
+    /// Gets the last error variable.
+    fn get_last_error(&mut self) -> InterpResult<'tcx, Scalar<Tag>> {
+        let this = self.eval_context_mut();
+        let errno_place = this.machine.last_error.unwrap();
+        this.read_scalar(errno_place.into())?.not_undef()
+    }
+
+    /// Evaluates the `i32` constant `libc::<name>`.
+    fn eval_libc_i32(&mut self, name: &str) -> InterpResult<'tcx, i32> {
+        let this = self.eval_context_mut();
+        this.eval_path_scalar(&["libc", name])?
+            .ok_or_else(|| InterpError::PathNotFound(vec!["libc".to_owned(), name.to_owned()]))?
+            .to_i32()
+    }
+
+    /// Sets `errno` to the value of the given `libc` constant (e.g. `"ERANGE"`).
+    fn set_errno(&mut self, name: &str) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+        let errno = this.eval_libc_i32(name)?;
+        this.set_last_error(Scalar::from_int(errno, Size::from_bits(32)))
+    }
+
+    /// Reads the null-terminated string at `ptr`, looking at no more than `max_len` bytes.
+    /// The terminator is not included in the result.
+    fn read_c_str_bounded(&self, ptr: Scalar<Tag>, max_len: u64) -> InterpResult<'tcx, Vec<u8>> {
+        let this = self.eval_context_ref();
+        let mut bytes = Vec::new();
+        for i in 0..max_len {
+            let ptr = ptr.ptr_offset(Size::from_bytes(i), this)?;
+            match this.memory().read_bytes(ptr, Size::from_bytes(1))?[0] {
+                0 => break,
+                byte => bytes.push(byte),
+            }
+        }
+        Ok(bytes)
+    }
+
+    /// Reads the null-terminated UTF-16 string at `ptr`, without the terminator.
+    fn read_wide_str(&self, ptr: Scalar<Tag>) -> InterpResult<'tcx, Vec<u16>> {
+        let this = self.eval_context_ref();
+        let mut chars = Vec::new();
+        for i in 0.. {
+            let ptr = ptr.ptr_offset(Size::from_bytes(2 * i), this)?;
+            let bytes = this.memory().read_bytes(ptr, Size::from_bytes(2))?;
+            let c = match this.tcx.data_layout.endian {
+                layout::Endian::Little => u16::from_le_bytes([bytes[0], bytes[1]]),
+                layout::Endian::Big => u16::from_be_bytes([bytes[0], bytes[1]]),
+            };
+            if c == 0 {
+                break;
+            }
+            chars.push(c);
+        }
+        Ok(chars)
+    }
+
+    /// Writes `chars` followed by a null terminator as UTF-16 to memory starting at `ptr`.
+    fn write_wide_str(&mut self, ptr: Scalar<Tag>, chars: &[u16]) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+        let endian = this.tcx.data_layout.endian;
+        let bytes: Vec<u8> = chars.iter().chain(std::iter::once(&0)).flat_map(|&c| match endian {
+            layout::Endian::Little => c.to_le_bytes(),
+            layout::Endian::Big => c.to_be_bytes(),
+        }.to_vec()).collect();
+        this.write_raw_bytes(ptr, &bytes)
+    }
+
+    /// Writes `bytes` to memory starting at `ptr`.
+    fn write_raw_bytes(&mut self, ptr: Scalar<Tag>, bytes: &[u8]) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+        let tcx = &{this.tcx.tcx};
+        let size = Size::from_bytes(bytes.len() as u64);
+        match this.memory().check_ptr_access(ptr, size, layout::Align::from_bytes(1).unwrap())? {
+            Some(ptr) => this.memory_mut().get_mut(ptr.alloc_id)?.write_bytes(tcx, ptr, bytes),
+            None => Ok(()), // Nothing to write.
+        }
+    }
+
     /// Visits the memory covered by `place`, sensitive to freezing: the 3rd parameter
     /// will be true if this is frozen, false if this is in an `UnsafeCell`.
     fn visit_freeze_sensitive(
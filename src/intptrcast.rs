@@ -2,12 +2,12 @@ use std::cell::RefCell;
 use std::collections::{HashMap, hash_map::Entry};
 use std::cmp::max;
 
-use rand::Rng;
+use rand::{Rng, RngCore};
 
 use rustc_mir::interpret::{AllocId, Pointer, InterpResult, Memory, AllocCheck};
 use rustc_target::abi::Size;
 
-use crate::{Evaluator, Tag, STACK_ADDR};
+use crate::{Evaluator, Tag, STACK_ADDR, PAGE_SIZE};
 
 pub type MemoryExtra = RefCell<GlobalState>;
 
@@ -24,6 +24,18 @@ pub struct GlobalState {
     /// This is used as a memory address when a new pointer is casted to an integer. It
     /// is always larger than any address that was previously made part of a block.
     pub next_base_addr: u64,
+    /// Whether integers may only be cast to pointers if they came from a pointer-to-integer
+    /// cast, with exactly that value.
+    pub strict_provenance: bool,
+    /// The integers produced by pointer-to-integer casts, and which allocation they point to.
+    /// Only used with `strict_provenance`.
+    pub provenance_map: HashMap<u64, AllocId>,
+    /// The maximum number of extra bytes to leave between the end of an allocation and the
+    /// base address of the next one. Nothing can be accessed through these addresses.
+    pub allocation_padding: u32,
+    /// Whether to reserve between 1 and 7 pages of addresses before each allocation, and put
+    /// the allocation at a random place in them.
+    pub randomize_heap_layout: bool,
 }
 
 impl Default for GlobalState {
@@ -32,6 +44,10 @@ impl Default for GlobalState {
             int_to_ptr_map: Vec::default(),
             base_addr: HashMap::default(),
             next_base_addr: STACK_ADDR,
+            strict_provenance: false,
+            provenance_map: HashMap::default(),
+            allocation_padding: 0,
+            randomize_heap_layout: false,
         }
     }
 }
@@ -42,7 +58,21 @@ impl<'mir, 'tcx> GlobalState {
         memory: &Memory<'mir, 'tcx, Evaluator<'tcx>>,
     ) -> InterpResult<'tcx, Pointer<Tag>> {
         let global_state = memory.extra.intptrcast.borrow();
-        
+
+        if global_state.strict_provenance {
+            return match global_state.provenance_map.get(&int) {
+                Some(&alloc_id) => {
+                    let offset = int - global_state.base_addr[&alloc_id];
+                    // The pointer is untagged because it was created from a cast
+                    Ok(Pointer::new_with_tag(alloc_id, Size::from_bytes(offset), Tag::Untagged))
+                }
+                None => err!(MachineError(format!(
+                    "strict provenance: {:#x} was not the result of a pointer-to-integer cast",
+                    int,
+                ))),
+            };
+        }
+
         match global_state.int_to_ptr_map.binary_search_by_key(&int, |(addr, _)| *addr) {
             Ok(pos) => {
                 let (_, alloc_id) = global_state.int_to_ptr_map[pos];
@@ -84,8 +114,22 @@ impl<'mir, 'tcx> GlobalState {
                 // Leave some space to the previous allocation, to give it some chance to be less aligned.
                 let slack = {
                     let mut rng = memory.extra.rng.as_ref().unwrap().borrow_mut();
+                    // Random padding makes it less likely that the program gets away with
+                    // relying on allocations being adjacent.
+                    let padding = match global_state.allocation_padding {
+                        0 => 0,
+                        max => rng.next_u32() % max,
+                    };
+                    // With `randomize_heap_layout`, the program cannot assume that allocations
+                    // are close to each other, either.
+                    let placement = if global_state.randomize_heap_layout {
+                        let reserved = rng.gen_range(1, 8) * PAGE_SIZE;
+                        rng.gen_range(0, reserved)
+                    } else {
+                        0
+                    };
                     // This means that `(global_state.next_base_addr + slack) % 16` is uniformly distributed.
-                    rng.gen_range(0, 16)
+                    rng.gen_range(0, 16) + u64::from(padding) + placement
                 };
                 // From next_base_addr + slack, round up to adjust for alignment.
                 let base_addr = Self::align_addr(global_state.next_base_addr + slack, align.bytes());
@@ -103,7 +147,11 @@ impl<'mir, 'tcx> GlobalState {
         };
 
         debug_assert_eq!(base_addr % align.bytes(), 0); // sanity check
-        Ok(base_addr + ptr.offset.bytes())
+        let addr = base_addr + ptr.offset.bytes();
+        if global_state.strict_provenance {
+            global_state.provenance_map.insert(addr, ptr.alloc_id);
+        }
+        Ok(addr)
     }
 
     /// Shifts `addr` to make it aligned with `align` by rounding `addr` to the smallest multiple
@@ -0,0 +1,191 @@
+use std::collections::HashMap;
+use std::mem;
+
+use crate::*;
+
+/// The result of a wait that acquired the first of the objects waited on.
+pub const WAIT_OBJECT_0: u32 = 0;
+/// The result of a wait that timed out.
+pub const WAIT_TIMEOUT: u32 = 0x102;
+/// The result of a wait that failed; the last error says why.
+pub const WAIT_FAILED: u32 = 0xFFFF_FFFF;
+/// The timeout that never expires.
+pub const INFINITE: u32 = 0xFFFF_FFFF;
+
+/// A Windows kernel object that can be waited on.
+#[derive(Debug)]
+pub enum KernelObject {
+    /// A mutex, with how often it has been acquired and not yet released.
+    /// There is only one thread, so an acquired mutex is always owned by the current thread.
+    Mutex { lock_count: u32 },
+    /// An event. An auto-reset event is reset by the wait that it ends.
+    Event { manual_reset: bool, signaled: bool },
+    /// A semaphore, with its current and maximum count.
+    Semaphore { count: u32, maximum: u32 },
+}
+
+impl KernelObject {
+    /// Whether a wait on this object succeeds without blocking.
+    fn is_signaled(&self) -> bool {
+        match *self {
+            // The owner may acquire a mutex recursively.
+            KernelObject::Mutex { .. } => true,
+            KernelObject::Event { signaled, .. } => signaled,
+            KernelObject::Semaphore { count, .. } => count > 0,
+        }
+    }
+
+    /// Applies the side effect of a successful wait on this object.
+    fn acquire(&mut self) {
+        match self {
+            KernelObject::Mutex { lock_count } => *lock_count += 1,
+            KernelObject::Event { manual_reset, signaled } => *signaled &= *manual_reset,
+            KernelObject::Semaphore { count, .. } => *count -= 1,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct KernelObjectEntry {
+    object: KernelObject,
+    name: Option<String>,
+    /// The number of open handles. The object is destroyed when the last one is closed.
+    handle_count: u32,
+}
+
+/// The kernel objects created by the program, and the handles referring to them.
+#[derive(Debug)]
+pub struct KernelObjectTable {
+    next_handle: i64,
+    next_id: u64,
+    handles: HashMap<i64, u64>,
+    objects: HashMap<u64, KernelObjectEntry>,
+}
+
+impl Default for KernelObjectTable {
+    fn default() -> Self {
+        KernelObjectTable {
+            // Stay clear of our other fake handles, like the one from `GetProcessHeap`.
+            // Real handles are multiples of 4, too.
+            next_handle: 0x100,
+            next_id: 0,
+            handles: Default::default(),
+            objects: Default::default(),
+        }
+    }
+}
+
+impl KernelObjectTable {
+    fn new_handle(&mut self, id: u64) -> i64 {
+        let handle = self.next_handle;
+        self.next_handle += 4;
+        self.handles.insert(handle, id);
+        self.objects.get_mut(&id).unwrap().handle_count += 1;
+        handle
+    }
+
+    /// Creates a new object and returns a handle to it.
+    pub fn create(&mut self, object: KernelObject, name: Option<String>) -> i64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.objects.insert(id, KernelObjectEntry { object, name, handle_count: 0 });
+        let handle = self.new_handle(id);
+        trace!("New kernel object {} with handle {}", id, handle);
+        handle
+    }
+
+    /// Returns a new handle to the object called `name`, if there is one.
+    pub fn open(&mut self, name: &str) -> Option<i64> {
+        let id = self.objects.iter()
+            .find(|(_, entry)| entry.name.as_ref().map(String::as_str) == Some(name))
+            .map(|(&id, _)| id)?;
+        Some(self.new_handle(id))
+    }
+
+    pub fn get(&self, handle: i64) -> Option<&KernelObject> {
+        let id = self.handles.get(&handle)?;
+        Some(&self.objects[id].object)
+    }
+
+    pub fn get_mut(&mut self, handle: i64) -> Option<&mut KernelObject> {
+        let id = self.handles.get(&handle)?;
+        Some(&mut self.objects.get_mut(id).unwrap().object)
+    }
+
+    /// Closes `handle`, returning `false` if it is not an open handle.
+    pub fn close(&mut self, handle: i64) -> bool {
+        let id = match self.handles.remove(&handle) {
+            Some(id) => id,
+            None => return false,
+        };
+        let entry = self.objects.get_mut(&id).unwrap();
+        entry.handle_count -= 1;
+        if entry.handle_count == 0 {
+            trace!("Destroying kernel object {}", id);
+            self.objects.remove(&id);
+        }
+        true
+    }
+}
+
+impl<'mir, 'tcx> EvalContextExt<'mir, 'tcx> for crate::MiriEvalContext<'mir, 'tcx> {}
+pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx> {
+    /// Creates `object`, or opens the existing object called `name` (a wide string, or null),
+    /// like `CreateMutexW` and friends do. Returns the new handle, or 0 with the last error set.
+    fn create_kernel_object(&mut self, object: KernelObject, name: Scalar<Tag>) -> InterpResult<'tcx, i64> {
+        let this = self.eval_context_mut();
+        let name = if name.is_null_ptr(this) {
+            None
+        } else {
+            Some(String::from_utf16_lossy(&this.read_wide_str(name)?))
+        };
+        if let Some(handle) = name.as_ref().and_then(|name| this.machine.kernel_objects.open(name)) {
+            let existing = this.machine.kernel_objects.get(handle).unwrap();
+            if mem::discriminant(existing) != mem::discriminant(&object) {
+                // The name belongs to a different kind of object.
+                this.machine.kernel_objects.close(handle);
+                this.set_last_error(Scalar::from_u32(6))?; // ERROR_INVALID_HANDLE
+                return Ok(0);
+            }
+            this.set_last_error(Scalar::from_u32(183))?; // ERROR_ALREADY_EXISTS
+            return Ok(handle);
+        }
+        this.set_last_error(Scalar::from_u32(0))?;
+        Ok(this.machine.kernel_objects.create(object, name))
+    }
+
+    /// Waits until one of `handles` (or, with `wait_all`, all of them) is signaled, and returns
+    /// the result of `WaitForMultipleObjects`.
+    fn wait_for_objects(&mut self, handles: &[i64], wait_all: bool, timeout: u32) -> InterpResult<'tcx, u32> {
+        let this = self.eval_context_mut();
+        if handles.iter().any(|&handle| this.machine.kernel_objects.get(handle).is_none()) {
+            this.set_last_error(Scalar::from_u32(6))?; // ERROR_INVALID_HANDLE
+            return Ok(WAIT_FAILED);
+        }
+
+        let table = &mut this.machine.kernel_objects;
+        let is_signaled = |&handle: &i64| table.get(handle).unwrap().is_signaled();
+        let ready = if wait_all {
+            if handles.iter().all(is_signaled) { Some(0) } else { None }
+        } else {
+            handles.iter().position(is_signaled)
+        };
+        if let Some(index) = ready {
+            let acquired = if wait_all { handles } else { &handles[index..=index] };
+            for &handle in acquired {
+                table.get_mut(handle).unwrap().acquire();
+            }
+            return Ok(WAIT_OBJECT_0 + index as u32);
+        }
+
+        // There is no other thread that could ever signal the objects, so we wait until the
+        // timeout expires.
+        if timeout == INFINITE {
+            return err!(MachineError(
+                "deadlock: waiting for kernel objects that can never be signaled".to_string(),
+            ));
+        }
+        this.machine.fake_clock += u64::from(timeout) * 1_000_000;
+        Ok(WAIT_TIMEOUT)
+    }
+}
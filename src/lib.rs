@@ -16,9 +16,17 @@ mod shims;
 mod operator;
 mod helpers;
 mod tls;
+mod stdio;
+mod kernel_objects;
+mod memory_dump;
+mod trace;
+mod event_log;
+mod watchpoints;
+mod reproducibility;
 mod range_map;
 mod mono_hash_map;
 mod stacked_borrows;
+mod tree_borrows;
 mod intptrcast;
 mod machine;
 mod eval;
@@ -28,17 +36,30 @@ pub use rustc_mir::interpret::*;
 // Resolve ambiguity.
 pub use rustc_mir::interpret::{self, AllocMap, PlaceTy};
 
+pub use crate::shims::abi::EvalContextExt as AbiEvalContextExt;
 pub use crate::shims::foreign_items::EvalContextExt as ForeignItemsEvalContextExt;
 pub use crate::shims::intrinsics::EvalContextExt as IntrinsicsEvalContextExt;
+pub use crate::shims::va_list::{VaList, VaArg};
 pub use crate::operator::EvalContextExt as OperatorEvalContextExt;
 pub use crate::tls::{EvalContextExt as TlsEvalContextExt, TlsData};
+pub use crate::stdio::{EvalContextExt as StdioEvalContextExt, StdioData, FileStream, STREAM_EOF, STREAM_ERROR};
+pub use crate::kernel_objects::{
+    EvalContextExt as KernelObjectsEvalContextExt, KernelObject, KernelObjectTable,
+    WAIT_OBJECT_0, WAIT_TIMEOUT, WAIT_FAILED, INFINITE,
+};
+pub use crate::memory_dump::{dump_memory, print_allocation, MemoryDumpFormat};
+pub use crate::trace::ChromeTrace;
+pub use crate::event_log::{EvalContextExt as EventLogEvalContextExt, Event, EventLog};
+pub use crate::reproducibility::MemorySnapshot;
+pub use crate::watchpoints::{EvalContextExt as WatchpointsEvalContextExt, WATCH_READ, WATCH_WRITE};
 pub use crate::range_map::RangeMap;
 pub use crate::helpers::{EvalContextExt as HelpersEvalContextExt};
 pub use crate::mono_hash_map::MonoHashMap;
 pub use crate::stacked_borrows::{EvalContextExt as StackedBorEvalContextExt, Tag, Permission, Stack, Stacks, Item};
+pub use crate::tree_borrows::{Tree, TreePermission};
 pub use crate::machine::{
-    PAGE_SIZE, STACK_ADDR, NUM_CPUS,
-    MemoryExtra, AllocExtra, MiriMemoryKind, Evaluator, MiriEvalContext, MiriEvalContextExt,
+    PAGE_SIZE, STACK_ADDR, NUM_CPUS, FE_TONEAREST, MACH_TASK_SELF, MACH_THREAD_SELF,
+    RwLockState, MemoryExtra, AllocExtra, MiriMemoryKind, Evaluator, MiriEvalContext, MiriEvalContextExt,
 };
 pub use crate::eval::{eval_main, create_ecx, MiriConfig};
 
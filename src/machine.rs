@@ -1,6 +1,6 @@
 use std::rc::Rc;
 use std::borrow::Cow;
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::cell::RefCell;
 
 use rand::rngs::StdRng;
@@ -8,8 +8,8 @@ use rand::rngs::StdRng;
 use syntax::attr;
 use syntax::symbol::sym;
 use rustc::hir::def_id::DefId;
-use rustc::ty::{self, layout::{Size, LayoutOf}, query::TyCtxtAt};
-use rustc::mir;
+use rustc::ty::{self, layout::{self, Align, Size, LayoutOf}, query::TyCtxtAt};
+use rustc::mir::{self, interpret::FrameInfo};
 
 use crate::*;
 
@@ -17,6 +17,22 @@ use crate::*;
 pub const PAGE_SIZE: u64 = 4*1024; // FIXME: adjust to target architecture
 pub const STACK_ADDR: u64 = 16*PAGE_SIZE; // not really about the "stack", but where we start assigning integer addresses to allocations
 pub const NUM_CPUS: u64 = 1;
+/// The fake Mach port names of the current task and thread on macOS.
+pub const MACH_TASK_SELF: u32 = 1;
+pub const MACH_THREAD_SELF: u32 = 2;
+
+/// The value of `FE_TONEAREST`, which is the same on all targets we support.
+pub const FE_TONEAREST: i32 = 0;
+
+/// The state of a `pthread_rwlock_t`. There is only one thread, so we do not need to know
+/// who holds the lock.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct RwLockState {
+    /// The number of read locks currently held.
+    pub readers: u32,
+    /// Whether the write lock is currently held.
+    pub writer: bool,
+}
 
 /// Extra memory kinds
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
@@ -25,6 +41,8 @@ pub enum MiriMemoryKind {
     Rust,
     /// `malloc` memory.
     C,
+    /// `mmap` memory.
+    Mmap,
     /// Part of env var emulation.
     Env,
     /// Statics.
@@ -42,6 +60,11 @@ impl Into<MemoryKind<MiriMemoryKind>> for MiriMemoryKind {
 #[derive(Debug, Clone)]
 pub struct AllocExtra {
     pub stacked_borrows: stacked_borrows::AllocExtra,
+    pub watchpoints: watchpoints::MemoryExtra,
+    /// The kind of memory, for `--print-alloc-id`.
+    pub kind: MemoryKind<MiriMemoryKind>,
+    /// Whether the memory was mapped with `PROT_NONE`, so that it cannot be accessed.
+    pub inaccessible: bool,
 }
 
 /// Extra global memory data
@@ -49,6 +72,10 @@ pub struct AllocExtra {
 pub struct MemoryExtra {
     pub stacked_borrows: stacked_borrows::MemoryExtra,
     pub intptrcast: intptrcast::MemoryExtra,
+    pub watchpoints: watchpoints::MemoryExtra,
+    /// All allocations that were ever created, if we are going to compare the final memory
+    /// of two runs.
+    pub(crate) allocations: Option<Rc<RefCell<BTreeSet<AllocId>>>>,
     /// The random number generator to use if Miri is running in non-deterministic mode and to
     /// enable intptrcast
     pub(crate) rng: Option<RefCell<StdRng>>
@@ -59,6 +86,8 @@ impl MemoryExtra {
         MemoryExtra {
             stacked_borrows: Default::default(),
             intptrcast: Default::default(),
+            watchpoints: Default::default(),
+            allocations: None,
             rng: rng.map(RefCell::new),
         }
     }
@@ -67,7 +96,8 @@ impl MemoryExtra {
 /// The machine itself.
 pub struct Evaluator<'tcx> {
     /// Environment variables set by `setenv`.
-    /// Miri does not expose env vars from the host to the emulated program.
+    /// Miri does not expose env vars from the host to the emulated program, except for the
+    /// ones forwarded with `-Zmiri-env-forward`.
     pub(crate) env_vars: HashMap<Vec<u8>, Pointer<Tag>>,
 
     /// Program arguments (`Option` because we can only initialize them after creating the ecx).
@@ -77,12 +107,94 @@ pub struct Evaluator<'tcx> {
     pub(crate) argv: Option<Pointer<Tag>>,
     pub(crate) cmd_line: Option<Pointer<Tag>>,
 
-    /// Last OS error.
-    pub(crate) last_error: u32,
+    /// The string returned by `gnu_get_libc_version`, allocated on the first call.
+    pub(crate) libc_version: Option<Pointer<Tag>>,
+
+    /// Last OS error location in memory. It is a 32-bit integer: `errno` on Unix,
+    /// `GetLastError` on Windows.
+    pub(crate) last_error: Option<MPlaceTy<'tcx, Tag>>,
+
+    /// The floating-point rounding mode set by `fesetround`. This is only tracked so that
+    /// `fegetround` is consistent; Miri always computes with the host's default rounding.
+    pub(crate) fenv: i32,
+
+    /// The states of the pseudo-random number generators behind `rand` and `drand48`.
+    pub(crate) c_rand_state: u64,
+    pub(crate) drand48_state: u64,
 
     /// TLS state.
     pub(crate) tls: TlsData<'tcx>,
 
+    /// The live allocations made by `__rust_alloc`, `__rust_alloc_zeroed` and `__rust_realloc`,
+    /// the only ones that `__rust_dealloc` and `__rust_realloc` may free.
+    pub(crate) rust_alloc_ids: HashSet<AllocId>,
+
+    /// The functions registered with `at_quick_exit` that did not run yet.
+    pub(crate) at_quick_exit_handlers: Vec<ty::Instance<'tcx>>,
+
+    /// The handlers pushed with `pthread_cleanup_push` and not popped yet, with their argument.
+    /// `pthread_exit` runs them before it ends the process.
+    pub(crate) cleanup_handlers: Vec<(ty::Instance<'tcx>, Scalar<Tag>)>,
+
+    /// Whether the thread set its cancelability state to `PTHREAD_CANCEL_DISABLE`.
+    pub(crate) cancel_disabled: bool,
+    /// Whether the thread set its cancelability type to `PTHREAD_CANCEL_ASYNCHRONOUS`.
+    pub(crate) cancel_asynchronous: bool,
+    /// Whether `pthread_cancel` was called for the thread, and it was not cancelled yet.
+    pub(crate) cancel_pending: bool,
+
+    /// The function set with `__miri_set_abort_handler`, which `abort` calls instead of
+    /// stopping the program.
+    pub(crate) abort_handler: Option<ty::Instance<'tcx>>,
+
+    /// For the running call of `__libc_start_main`, the number of functions it called so far
+    /// (out of `init`, `main`, `fini` and `rtld_fini`), and where `main` puts its exit code.
+    pub(crate) libc_start_main: Option<(u8, MPlaceTy<'tcx, Tag>)>,
+
+    /// C `FILE` streams.
+    pub(crate) stdio: StdioData,
+
+    /// Windows mutexes and other kernel objects.
+    pub(crate) kernel_objects: KernelObjectTable,
+
+    /// The state of the `pthread_rwlock_t`s that are or were locked, by their address.
+    pub(crate) rwlocks: HashMap<Pointer, RwLockState>,
+
+    /// Directories created with `CreateDirectoryW`, as lowercase absolute paths.
+    /// Miri does not touch the host file system.
+    pub(crate) virtual_dirs: HashSet<String>,
+
+    /// A deterministic clock in nanoseconds that advances by one with every executed
+    /// terminator. It has nothing to do with the host's time.
+    pub(crate) fake_clock: u64,
+
+    /// The trace of the program's calls, if we are recording one.
+    pub(crate) trace: Option<ChromeTrace>,
+
+    /// The log of significant events, if we are keeping one.
+    pub(crate) event_log: Option<EventLog>,
+
+    /// The probability with which `compare_exchange_weak` fails spuriously.
+    pub(crate) cmpxchg_weak_failure_rate: f64,
+
+    /// Whether to check calls of foreign functions against their C signatures.
+    pub(crate) check_abi: bool,
+
+    /// Where the program made each of its heap allocations, if we are going to dump the heap
+    /// at the end.
+    pub(crate) alloc_sites: Option<HashMap<AllocId, (MiriMemoryKind, String)>>,
+
+    /// The allocations that the program filled with random bytes.
+    pub(crate) random_allocs: HashSet<AllocId>,
+
+    /// Every error that came out of one of our machine hooks or out of a step of the evaluation,
+    /// with the stack at that point. The final error is reported anyway; this shows where
+    /// earlier ones were swallowed.
+    pub(crate) error_history: Vec<(InterpError<'tcx, u64>, Vec<FrameInfo<'tcx>>)>,
+
+    /// The index in `error_history` of the error that ended the evaluation.
+    pub(crate) final_error: Option<usize>,
+
     /// Whether to enforce the validity invariant.
     pub(crate) validate: bool,
 }
@@ -94,8 +206,34 @@ impl<'tcx> Evaluator<'tcx> {
             argc: None,
             argv: None,
             cmd_line: None,
-            last_error: 0,
+            libc_version: None,
+            last_error: None,
+            fenv: FE_TONEAREST,
+            // The states after `srand(1)` and before any call to `srand48`, respectively.
+            c_rand_state: 1,
+            drand48_state: 0x1234_ABCD_330E,
             tls: TlsData::default(),
+            rust_alloc_ids: HashSet::default(),
+            at_quick_exit_handlers: Vec::new(),
+            cleanup_handlers: Vec::new(),
+            cancel_disabled: false,
+            cancel_asynchronous: false,
+            cancel_pending: false,
+            abort_handler: None,
+            libc_start_main: None,
+            stdio: StdioData::default(),
+            kernel_objects: KernelObjectTable::default(),
+            rwlocks: HashMap::default(),
+            virtual_dirs: HashSet::default(),
+            fake_clock: 0,
+            trace: None,
+            event_log: None,
+            cmpxchg_weak_failure_rate: 0.0,
+            check_abi: false,
+            alloc_sites: None,
+            random_allocs: HashSet::default(),
+            error_history: Vec::new(),
+            final_error: None,
             validate,
         }
     }
@@ -147,7 +285,8 @@ impl<'mir, 'tcx> Machine<'mir, 'tcx> for Evaluator<'tcx> {
         dest: Option<PlaceTy<'tcx, Tag>>,
         ret: Option<mir::BasicBlock>,
     ) -> InterpResult<'tcx, Option<&'mir mir::Body<'tcx>>> {
-        ecx.find_fn(instance, args, dest, ret)
+        let res = ecx.find_fn(instance, args, dest, ret);
+        ecx.record_error(res)
     }
 
     #[inline(always)]
@@ -157,7 +296,8 @@ impl<'mir, 'tcx> Machine<'mir, 'tcx> for Evaluator<'tcx> {
         args: &[OpTy<'tcx, Tag>],
         dest: PlaceTy<'tcx, Tag>,
     ) -> InterpResult<'tcx> {
-        ecx.call_intrinsic(instance, args, dest)
+        let res = ecx.call_intrinsic(instance, args, dest);
+        ecx.record_error(res)
     }
 
     #[inline(always)]
@@ -229,6 +369,14 @@ impl<'mir, 'tcx> Machine<'mir, 'tcx> for Evaluator<'tcx> {
                 let data = vec![0; size.bytes() as usize];
                 Allocation::from_bytes(&data, tcx.data_layout.pointer_align.abi)
             }
+            "mach_task_self_" => {
+                // The Mach port of the current task, a `u32`. See `task_self_trap`.
+                let data = match tcx.data_layout.endian {
+                    layout::Endian::Little => MACH_TASK_SELF.to_le_bytes(),
+                    layout::Endian::Big => MACH_TASK_SELF.to_be_bytes(),
+                };
+                Allocation::from_bytes(&data, Align::from_bytes(4).unwrap())
+            }
             _ => return err!(Unimplemented(
                     format!("can't access foreign static: {}", link_name),
                 )),
@@ -237,9 +385,17 @@ impl<'mir, 'tcx> Machine<'mir, 'tcx> for Evaluator<'tcx> {
     }
 
     #[inline(always)]
-    fn before_terminator(_ecx: &mut InterpretCx<'mir, 'tcx, Self>) -> InterpResult<'tcx>
+    fn before_terminator(ecx: &mut InterpretCx<'mir, 'tcx, Self>) -> InterpResult<'tcx>
     {
-        // We are not interested in detecting loops.
+        // We are not interested in detecting loops, but we use this to drive our clock.
+        ecx.machine.fake_clock += 1;
+        ecx.report_watchpoint_hits();
+        if let Some(mut trace) = ecx.machine.trace.take() {
+            if let Err(err) = trace.sync(ecx.stack(), ecx.machine.fake_clock) {
+                return err!(MachineError(format!("failed to write the trace: {}", err)));
+            }
+            ecx.machine.trace = Some(trace);
+        }
         Ok(())
     }
 
@@ -251,7 +407,7 @@ impl<'mir, 'tcx> Machine<'mir, 'tcx> for Evaluator<'tcx> {
     ) -> (Cow<'b, Allocation<Self::PointerTag, Self::AllocExtra>>, Self::PointerTag) {
         let kind = kind.expect("we set our STATIC_KIND so this cannot be None");
         let alloc = alloc.into_owned();
-        let (stacks, base_tag) = Stacks::new_allocation(
+        let (stacks, base_tag) = stacked_borrows::AllocExtra::new_allocation(
             id,
             Size::from_bytes(alloc.bytes.len() as u64),
             Rc::clone(&memory.extra.stacked_borrows),
@@ -261,6 +417,9 @@ impl<'mir, 'tcx> Machine<'mir, 'tcx> for Evaluator<'tcx> {
             assert!(alloc.relocations.is_empty(), "Only statics can come initialized with inner pointers");
             // Now we can rely on the inner pointers being static, too.
         }
+        if let Some(ref allocations) = memory.extra.allocations {
+            allocations.borrow_mut().insert(id);
+        }
         let mut memory_extra = memory.extra.stacked_borrows.borrow_mut();
         let alloc: Allocation<Tag, Self::AllocExtra> = Allocation {
             bytes: alloc.bytes,
@@ -276,6 +435,9 @@ impl<'mir, 'tcx> Machine<'mir, 'tcx> for Evaluator<'tcx> {
             mutability: alloc.mutability,
             extra: AllocExtra {
                 stacked_borrows: stacks,
+                watchpoints: Rc::clone(&memory.extra.watchpoints),
+                kind,
+                inaccessible: false,
             },
         };
         (Cow::Owned(alloc), base_tag)
@@ -303,7 +465,8 @@ impl<'mir, 'tcx> Machine<'mir, 'tcx> for Evaluator<'tcx> {
             // uninitialized data.
              Ok(())
         } else {
-            ecx.retag(kind, place)
+            let res = ecx.retag(kind, place);
+            ecx.record_error(res)
         }
     }
 
@@ -354,6 +517,10 @@ impl AllocationExtra<Tag> for AllocExtra {
         ptr: Pointer<Tag>,
         size: Size,
     ) -> InterpResult<'tcx> {
+        if alloc.extra.inaccessible {
+            return err!(MachineError("read from memory mapped with PROT_NONE".to_string()));
+        }
+        alloc.extra.watchpoints.borrow_mut().access(ptr, size, /*write:*/ false);
         alloc.extra.stacked_borrows.memory_read(ptr, size)
     }
 
@@ -363,6 +530,10 @@ impl AllocationExtra<Tag> for AllocExtra {
         ptr: Pointer<Tag>,
         size: Size,
     ) -> InterpResult<'tcx> {
+        if alloc.extra.inaccessible {
+            return err!(MachineError("write to memory mapped with PROT_NONE".to_string()));
+        }
+        alloc.extra.watchpoints.borrow_mut().access(ptr, size, /*write:*/ true);
         alloc.extra.stacked_borrows.memory_written(ptr, size)
     }
 
@@ -382,7 +553,8 @@ impl MayLeak for MiriMemoryKind {
         use self::MiriMemoryKind::*;
         match self {
             Rust | C => false,
-            Env | Static => true,
+            // Mappings stay until the process exits, unmapping them is optional.
+            Mmap | Env | Static => true,
         }
     }
 }
@@ -0,0 +1,208 @@
+//! Serializing the program's heap after it finished, for `--emit-miri-output`, and printing
+//! single allocations for `--print-alloc-id`.
+
+use std::fmt::Write;
+use std::fs;
+use std::io;
+
+use rustc::ty::layout::Size;
+
+use crate::*;
+
+/// The format of the memory dump.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum MemoryDumpFormat {
+    /// A JSON object, with uninitialized bytes as `null`.
+    Json,
+    /// A compact little-endian format, described at `write_binary`.
+    Binary,
+}
+
+impl MemoryDumpFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            MemoryDumpFormat::Json => "json",
+            MemoryDumpFormat::Binary => "bin",
+        }
+    }
+}
+
+/// A live heap allocation, as it ends up in the dump.
+struct DumpedAllocation {
+    id: AllocId,
+    kind: MiriMemoryKind,
+    site: String,
+    align: u64,
+    /// The bytes, with `None` for uninitialized ones.
+    bytes: Vec<Option<u8>>,
+    /// The pointers stored in the allocation: offset, target allocation, and tag.
+    relocations: Vec<(u64, AllocId, Tag)>,
+}
+
+/// Writes all live `Rust` and `C` allocations to `path`.
+pub fn dump_memory<'mir, 'tcx>(
+    ecx: &MiriEvalContext<'mir, 'tcx>,
+    format: MemoryDumpFormat,
+    path: &str,
+) -> io::Result<()> {
+    let sites = match ecx.machine.alloc_sites {
+        Some(ref sites) => sites,
+        None => return Ok(()),
+    };
+    let mut ids: Vec<AllocId> = sites.keys().copied().collect();
+    ids.sort();
+    let allocations: Vec<DumpedAllocation> = ids.into_iter().filter_map(|id| {
+        // Deallocated memory is gone.
+        let alloc = ecx.memory().get(id).ok()?;
+        let (kind, ref site) = sites[&id];
+        let bytes = (0..alloc.bytes.len() as u64)
+            .map(|offset| {
+                let offset = Size::from_bytes(offset);
+                if alloc.undef_mask.get(offset) {
+                    Some(alloc.bytes[offset.bytes() as usize])
+                } else {
+                    None
+                }
+            })
+            .collect();
+        let relocations = alloc.relocations.iter()
+            .map(|&(offset, (tag, target))| (offset.bytes(), target, tag))
+            .collect();
+        Some(DumpedAllocation {
+            id,
+            kind,
+            site: site.clone(),
+            align: alloc.align.bytes(),
+            bytes,
+            relocations,
+        })
+    }).collect();
+
+    let contents = match format {
+        MemoryDumpFormat::Json => write_json(&allocations).into_bytes(),
+        MemoryDumpFormat::Binary => write_binary(&allocations),
+    };
+    fs::write(path, contents)
+}
+
+/// Hexdumps the allocation `id` to stderr, with its kind, size, alignment, and the tags of the
+/// pointers stored in it.
+pub fn print_allocation<'mir, 'tcx>(ecx: &MiriEvalContext<'mir, 'tcx>, id: AllocId) {
+    let alloc = match ecx.memory().get(id) {
+        Ok(alloc) => alloc,
+        Err(_) => {
+            eprintln!("alloc{}: no such allocation (it might have been deallocated)", id.0);
+            return;
+        }
+    };
+    eprintln!(
+        "alloc{} ({:?}, size {}, align {}):",
+        id.0, alloc.extra.kind, alloc.bytes.len(), alloc.align.bytes(),
+    );
+    for (line, chunk) in alloc.bytes.chunks(16).enumerate() {
+        let mut out = format!("  {:#06x} ", line * 16);
+        for (i, &byte) in chunk.iter().enumerate() {
+            let offset = Size::from_bytes((line * 16 + i) as u64);
+            if alloc.undef_mask.get(offset) {
+                write!(out, " {:02x}", byte).unwrap();
+            } else {
+                out.push_str(" __");
+            }
+        }
+        eprintln!("{}", out);
+    }
+    for &(offset, (tag, target)) in alloc.relocations.iter() {
+        eprintln!("  pointer at {:#x} to alloc{} with tag {:?}", offset.bytes(), target.0, tag);
+    }
+}
+
+fn write_json(allocations: &[DumpedAllocation]) -> String {
+    let mut out = String::from("{\"allocations\":[");
+    for (i, alloc) in allocations.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        write!(
+            out,
+            "{{\"id\":{},\"kind\":\"{:?}\",\"site\":\"{}\",\"align\":{},\"bytes\":[",
+            alloc.id.0, alloc.kind, escape_json(&alloc.site), alloc.align,
+        ).unwrap();
+        for (j, byte) in alloc.bytes.iter().enumerate() {
+            if j > 0 {
+                out.push(',');
+            }
+            match byte {
+                Some(byte) => write!(out, "{}", byte).unwrap(),
+                None => out.push_str("null"),
+            }
+        }
+        out.push_str("],\"relocations\":[");
+        for (j, &(offset, target, tag)) in alloc.relocations.iter().enumerate() {
+            if j > 0 {
+                out.push(',');
+            }
+            let tag = match tag {
+                Tag::Tagged(id) => id.get().to_string(),
+                Tag::Untagged => "null".to_string(),
+            };
+            write!(out, "{{\"offset\":{},\"alloc\":{},\"tag\":{}}}", offset, target.0, tag).unwrap();
+        }
+        out.push_str("]}");
+    }
+    out.push_str("]}\n");
+    out
+}
+
+pub(crate) fn escape_json(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if (c as u32) < 0x20 => write!(out, "\\u{:04x}", c as u32).unwrap(),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// The binary format is little-endian throughout: the magic `MIRIMEM\0`, the number of
+/// allocations as a `u64`, and then for each allocation
+/// * its id (`u64`), kind (`u8`, 0 for `Rust` and 1 for `C`), and the length of its
+///   allocation site (`u32`) followed by the site in UTF-8,
+/// * its alignment and size (`u64` each), followed by the bytes (uninitialized ones are 0),
+/// * the initialization mask, one bit per byte starting at the least significant bit,
+/// * the number of relocations (`u64`), followed by the offset, target allocation and
+///   tag (`u64` each, tag 0 means untagged) of each relocation.
+fn write_binary(allocations: &[DumpedAllocation]) -> Vec<u8> {
+    let mut out = b"MIRIMEM\0".to_vec();
+    out.extend_from_slice(&(allocations.len() as u64).to_le_bytes());
+    for alloc in allocations {
+        out.extend_from_slice(&alloc.id.0.to_le_bytes());
+        out.push(match alloc.kind {
+            MiriMemoryKind::C => 1,
+            _ => 0,
+        });
+        out.extend_from_slice(&(alloc.site.len() as u32).to_le_bytes());
+        out.extend_from_slice(alloc.site.as_bytes());
+        out.extend_from_slice(&alloc.align.to_le_bytes());
+        out.extend_from_slice(&(alloc.bytes.len() as u64).to_le_bytes());
+        out.extend(alloc.bytes.iter().map(|byte| byte.unwrap_or(0)));
+        for chunk in alloc.bytes.chunks(8) {
+            let mask = chunk.iter().enumerate()
+                .fold(0u8, |mask, (i, byte)| mask | ((byte.is_some() as u8) << i));
+            out.push(mask);
+        }
+        out.extend_from_slice(&(alloc.relocations.len() as u64).to_le_bytes());
+        for &(offset, target, tag) in &alloc.relocations {
+            let tag = match tag {
+                Tag::Tagged(id) => id.get(),
+                Tag::Untagged => 0,
+            };
+            out.extend_from_slice(&offset.to_le_bytes());
+            out.extend_from_slice(&target.0.to_le_bytes());
+            out.extend_from_slice(&tag.to_le_bytes());
+        }
+    }
+    out
+}
@@ -0,0 +1,74 @@
+//! Comparing the final memory of two runs with different seeds, for
+//! `-Zmiri-reproducibility-check`.
+
+use std::collections::BTreeMap;
+
+use rustc::ty::layout::Size;
+
+use crate::*;
+
+/// The contents of an allocation at the end of a run.
+#[derive(PartialEq, Eq, Debug)]
+struct AllocSnapshot {
+    kind: MemoryKind<MiriMemoryKind>,
+    /// The bytes, with `None` for uninitialized ones.
+    bytes: Vec<Option<u8>>,
+    /// The offsets of the pointers stored in the allocation, and where they point. Their tags
+    /// do not matter.
+    relocations: Vec<(u64, AllocId)>,
+}
+
+/// The memory at the end of a run.
+pub struct MemorySnapshot(BTreeMap<AllocId, AllocSnapshot>);
+
+impl MemorySnapshot {
+    /// Records the contents of all allocations that are still live, except for those that
+    /// the program filled with random bytes.
+    pub fn take<'mir, 'tcx>(ecx: &MiriEvalContext<'mir, 'tcx>) -> Self {
+        let ids = match ecx.memory().extra.allocations {
+            Some(ref ids) => ids.borrow(),
+            None => return MemorySnapshot(BTreeMap::new()),
+        };
+        let allocs = ids.iter()
+            .filter(|id| !ecx.machine.random_allocs.contains(id))
+            .filter_map(|&id| {
+                // Deallocated memory is gone.
+                let alloc = ecx.memory().get(id).ok()?;
+                let bytes = (0..alloc.bytes.len() as u64)
+                    .map(|offset| {
+                        let offset = Size::from_bytes(offset);
+                        if alloc.undef_mask.get(offset) {
+                            Some(alloc.bytes[offset.bytes() as usize])
+                        } else {
+                            None
+                        }
+                    })
+                    .collect();
+                let relocations = alloc.relocations.iter()
+                    .map(|&(offset, (_, target))| (offset.bytes(), target))
+                    .collect();
+                Some((id, AllocSnapshot { kind: alloc.extra.kind, bytes, relocations }))
+            })
+            .collect();
+        MemorySnapshot(allocs)
+    }
+
+    /// Returns a description of every allocation that differs between the two snapshots.
+    pub fn differences(&self, other: &MemorySnapshot) -> Vec<String> {
+        let mut differences = Vec::new();
+        for (id, alloc) in &self.0 {
+            match other.0.get(id) {
+                None => differences.push(format!("alloc{} ({:?}) only exists in one run", id.0, alloc.kind)),
+                Some(other) if other != alloc =>
+                    differences.push(format!("alloc{} ({:?}) has different contents", id.0, alloc.kind)),
+                Some(_) => {}
+            }
+        }
+        for (id, alloc) in &other.0 {
+            if !self.0.contains_key(id) {
+                differences.push(format!("alloc{} ({:?}) only exists in one run", id.0, alloc.kind));
+            }
+        }
+        differences
+    }
+}
@@ -0,0 +1,90 @@
+//! Checking calls of foreign functions against their C signatures, for `--check-abi`.
+
+use rustc::ty::layout::{Align, Size};
+
+use crate::*;
+
+/// The C types that show up in the signatures we check.
+#[derive(Copy, Clone, Debug)]
+enum CType {
+    Int,
+    SizeT,
+    Ptr,
+    Double,
+}
+
+impl CType {
+    fn name(self) -> &'static str {
+        match self {
+            CType::Int => "int",
+            CType::SizeT => "size_t",
+            CType::Ptr => "pointer",
+            CType::Double => "double",
+        }
+    }
+}
+
+/// The argument types of the foreign functions whose signature we know. These are
+/// functions we emulate, so every entry needs a match arm in `emulate_foreign_item`;
+/// `tests/run-pass/check_abi.rs` calls each of them.
+/// Variadic functions are not listed, since we cannot check their arguments.
+fn signature(link_name: &str) -> Option<&'static [CType]> {
+    use self::CType::*;
+    Some(match link_name {
+        "abort" => &[],
+        "exit" | "_exit" | "isatty" | "sysconf" => &[Int],
+        "malloc" => &[SizeT],
+        "calloc" => &[SizeT, SizeT],
+        "realloc" => &[Ptr, SizeT],
+        "free" | "strlen" | "getenv" | "unsetenv" => &[Ptr],
+        "posix_memalign" => &[Ptr, SizeT, SizeT],
+        "memcpy" | "memmove" | "memcmp" => &[Ptr, Ptr, SizeT],
+        "memset" | "memchr" | "memrchr" => &[Ptr, Int, SizeT],
+        "setenv" => &[Ptr, Ptr, Int],
+        "write" => &[Int, Ptr, SizeT],
+        "pthread_key_create" => &[Ptr, Ptr],
+        "pthread_mutex_lock" | "pthread_mutex_unlock" | "pthread_mutex_destroy" |
+        "pthread_rwlock_rdlock" | "pthread_rwlock_tryrdlock" | "pthread_rwlock_wrlock" |
+        "pthread_rwlock_trywrlock" | "pthread_rwlock_unlock" | "pthread_rwlock_destroy" => &[Ptr],
+        "pthread_mutex_init" | "pthread_rwlock_init" => &[Ptr, Ptr],
+        "sqrt" | "cbrt" | "exp" | "log" | "sin" | "cos" | "tan" | "floor" | "ceil" => &[Double],
+        "pow" | "hypot" | "atan2" | "fmod" => &[Double, Double],
+        _ => return None,
+    })
+}
+
+impl<'mir, 'tcx> EvalContextExt<'mir, 'tcx> for crate::MiriEvalContext<'mir, 'tcx> {}
+pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx> {
+    /// Checks that the number, sizes and alignments of `args` match the C signature of
+    /// `link_name`, if we know it.
+    fn check_foreign_abi(&self, link_name: &str, args: &[OpTy<'tcx, Tag>]) -> InterpResult<'tcx> {
+        let this = self.eval_context_ref();
+        let signature = match signature(link_name) {
+            Some(signature) => signature,
+            None => return Ok(()),
+        };
+        if args.len() != signature.len() {
+            return err!(AbiViolation(format!(
+                "calling `{}` with {} arguments, but it takes {}",
+                link_name, args.len(), signature.len(),
+            )));
+        }
+        let dl = &this.tcx.data_layout;
+        for (idx, (arg, &ty)) in args.iter().zip(signature).enumerate() {
+            let (size, align): (Size, Align) = match ty {
+                CType::Int => (Size::from_bytes(4), dl.i32_align.abi),
+                CType::SizeT | CType::Ptr => (dl.pointer_size, dl.pointer_align.abi),
+                CType::Double => (Size::from_bytes(8), dl.f64_align.abi),
+            };
+            if arg.layout.size != size || arg.layout.align.abi != align {
+                return err!(AbiViolation(format!(
+                    "argument {} of `{}` should have type `{}` (size {}, alignment {}), \
+                     but has size {} and alignment {}",
+                    idx + 1, link_name, ty.name(), size.bytes(), align.bytes(),
+                    arg.layout.size.bytes(), arg.layout.align.abi.bytes(),
+                )));
+            }
+        }
+        Ok(())
+    }
+}
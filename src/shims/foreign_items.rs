@@ -1,11 +1,14 @@
+use std::convert::TryFrom;
+
 use rustc::ty;
-use rustc::ty::layout::{Align, LayoutOf, Size};
+use rustc::ty::layout::{self, Align, LayoutOf, Size};
 use rustc::hir::def_id::DefId;
 use rustc::mir;
 use syntax::attr;
-use syntax::symbol::sym;
+use syntax::symbol::{sym, Symbol};
 
-use rand::RngCore;
+use rand::{RngCore, SeedableRng};
+use rand::rngs::StdRng;
 
 use crate::*;
 
@@ -38,8 +41,36 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
             return Ok(None);
         }
 
+        // Functions marked `#[miri::skip]` are not interpreted, they just return zeros.
+        if this.is_skipped(instance.def_id()) {
+            this.tcx.sess.span_warn(
+                this.current_span(),
+                &format!("skipping the body of `{}` because it is marked `#[miri::skip]`", instance),
+            );
+            let dest = match dest {
+                Some(dest) => dest,
+                None => return err!(Unimplemented(
+                    format!("can't skip diverging function: {}", instance),
+                )),
+            };
+            if !dest.layout.is_zst() {
+                let mplace = this.force_allocation(dest)?;
+                let size = dest.layout.size;
+                if let Some(ptr) = this.memory().check_ptr_access(mplace.ptr, size, mplace.align)? {
+                    this.memory_mut().get_mut(ptr.alloc_id)?.write_repeat(&{this.tcx.tcx}, ptr, 0, size)?;
+                }
+            }
+            this.goto_block(ret)?;
+            return Ok(None);
+        }
+
         // Try to see if we can do something about foreign items.
         if this.tcx.is_foreign_item(instance.def_id()) {
+            // Compiler builtins that ended up as calls are handled separately.
+            if this.emulate_builtin(instance.def_id(), args, dest)? {
+                this.goto_block(ret)?;
+                return Ok(None);
+            }
             // An external function that we cannot find MIR for, but we can still run enough
             // of them to make miri viable.
             this.emulate_foreign_item(instance.def_id(), args, dest, ret)?;
@@ -51,6 +82,104 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
         Ok(Some(this.load_mir(instance.def)?))
     }
 
+    /// Returns the name the foreign item `def_id` is linked under.
+    fn link_name(&self, def_id: DefId) -> Symbol {
+        let this = self.eval_context_ref();
+        let attrs = this.tcx.get_attrs(def_id);
+        match attr::first_attr_value_str_by_name(&attrs, sym::link_name) {
+            Some(name) => name,
+            None => this.tcx.item_name(def_id),
+        }
+    }
+
+    /// Returns whether the function `def_id` is marked `#[miri::skip]`.
+    fn is_skipped(&self, def_id: DefId) -> bool {
+        let this = self.eval_context_ref();
+        this.tcx.get_attrs(def_id).iter().any(|attr| {
+            let path: Vec<_> = attr.path.segments.iter().map(|seg| seg.ident.as_str()).collect();
+            path.len() == 2 && path[0] == "miri" && path[1] == "skip"
+        })
+    }
+
+    /// Emulates the GCC builtin `def_id` if it is one we support, returning whether we did.
+    /// These are usually expanded by the compiler but may show up as calls, e.g. when used
+    /// through a function pointer.
+    fn emulate_builtin(
+        &mut self,
+        def_id: DefId,
+        args: &[OpTy<'tcx, Tag>],
+        dest: Option<PlaceTy<'tcx, Tag>>,
+    ) -> InterpResult<'tcx, bool> {
+        let this = self.eval_context_mut();
+        let link_name = this.link_name(def_id).as_str();
+        let link_name = link_name.get();
+        let dest = match dest {
+            Some(dest) => dest,
+            None => return Ok(false),
+        };
+        match link_name {
+            "__builtin_frame_address" | "__builtin_return_address" => {
+                let level = this.read_scalar(args[0])?.to_u32()?;
+                if level > 0 {
+                    // We do not support walking up the stack.
+                    this.write_null(dest)?;
+                } else {
+                    let call_id = this.frame().extra;
+                    let addr = fake_frame_address(this, call_id, link_name == "__builtin_return_address");
+                    this.write_scalar(addr, dest)?;
+                }
+            }
+            // Optimization hints, which just return their first argument.
+            "__builtin_expect" | "__builtin_expect_with_probability" | "__builtin_unpredictable" => {
+                this.copy_op(args[0], dest)?;
+            }
+            "__builtin_clz" | "__builtin_clzl" | "__builtin_clzll" |
+            "__builtin_ctz" | "__builtin_ctzl" | "__builtin_ctzll" |
+            "__builtin_popcount" | "__builtin_popcountl" | "__builtin_popcountll" |
+            "__builtin_parity" | "__builtin_parityl" | "__builtin_parityll" => {
+                // The suffix determines the argument type, which we can just take from the layout.
+                let size = args[0].layout.size;
+                let val = this.read_scalar(args[0])?.to_bits(size)?;
+                let op = link_name.trim_start_matches("__builtin_").trim_end_matches('l');
+                if val == 0 && (op == "clz" || op == "ctz") {
+                    // These are `ctlz_nonzero` and `cttz_nonzero`, so report the UB like those do.
+                    let intrinsic = if op == "clz" { "ctlz_nonzero" } else { "cttz_nonzero" };
+                    return err!(Intrinsic(format!("{} called on 0", intrinsic)));
+                }
+                let result = match op {
+                    "clz" => val.leading_zeros() - (128 - size.bits() as u32),
+                    "ctz" => val.trailing_zeros(),
+                    "popcount" => val.count_ones(),
+                    "parity" => val.count_ones() % 2,
+                    _ => bug!(),
+                };
+                this.write_scalar(Scalar::from_uint(result, dest.layout.size), dest)?;
+            }
+            "__builtin_bswap16" | "__builtin_bswap32" | "__builtin_bswap64" => {
+                let size = args[0].layout.size;
+                let val = this.read_scalar(args[0])?.to_bits(size)?;
+                // Swap all 16 bytes, then move the interesting ones back down.
+                let result = val.swap_bytes() >> (128 - size.bits());
+                this.write_scalar(Scalar::from_uint(result, dest.layout.size), dest)?;
+            }
+            // `llvm.x86.rdtsc` is what `core::arch`'s `_rdtsc` links to.
+            "__rdtsc" | "__builtin_ia32_rdtsc" | "llvm.x86.rdtsc" => {
+                // We simulate a 1GHz time stamp counter on top of our fake clock. This is
+                // monotonic and reproducible, but says nothing about how long the program
+                // would take to run on real hardware.
+                let tsc = this.machine.fake_clock;
+                this.write_scalar(Scalar::from_u64(tsc), dest)?;
+            }
+            "__atomic_thread_fence" | "__atomic_signal_fence" | "atomic_thread_fence" | "atomic_signal_fence" => {
+                // With only one thread, there is nothing to synchronize with.
+                // We still make sure the ordering argument is initialized.
+                this.read_scalar(args[0])?.not_undef()?;
+            }
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
     fn malloc(
         &mut self,
         size: u64,
@@ -63,6 +192,7 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
         } else {
             let align = this.tcx.data_layout.pointer_align.abi;
             let ptr = this.memory_mut().allocate(Size::from_bytes(size), align, MiriMemoryKind::C.into());
+            this.record_alloc_site(ptr, MiriMemoryKind::C);
             if zero_init {
                 // We just allocated this, the access cannot fail
                 this.memory_mut()
@@ -79,8 +209,10 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
     ) -> InterpResult<'tcx> {
         let this = self.eval_context_mut();
         if !ptr.is_null_ptr(this) {
+            let ptr = ptr.to_ptr()?;
+            this.log_event(Event::Dealloc { ptr });
             this.memory_mut().deallocate(
-                ptr.to_ptr()?,
+                ptr,
                 None,
                 MiriMemoryKind::C.into(),
             )?;
@@ -104,10 +236,12 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
                     align,
                     MiriMemoryKind::C.into()
                 );
+                this.record_alloc_site(new_ptr, MiriMemoryKind::C);
                 Ok(Scalar::Ptr(new_ptr))
             }
         } else {
             let old_ptr = old_ptr.to_ptr()?;
+            this.log_event(Event::Dealloc { ptr: old_ptr });
             let memory = this.memory_mut();
             let old_size = Size::from_bytes(memory.get(old_ptr.alloc_id)?.bytes.len() as u64);
             if new_size == 0 {
@@ -126,11 +260,56 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
                     align,
                     MiriMemoryKind::C.into(),
                 )?;
+                this.record_alloc_site(new_ptr, MiriMemoryKind::C);
                 Ok(Scalar::Ptr(new_ptr))
             }
         }
     }
 
+    /// Checks that `__rust_dealloc` or `__rust_realloc` may free `ptr`, and forgets about it.
+    /// Memory of another kind and dangling pointers are reported by the deallocation itself;
+    /// this catches Rust memory that did not come from the Rust allocator shims.
+    fn check_rust_dealloc(&mut self, ptr: Pointer<Tag>) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+        if this.machine.rust_alloc_ids.remove(&ptr.alloc_id) {
+            return Ok(());
+        }
+        if let Ok(alloc) = this.memory().get(ptr.alloc_id) {
+            if alloc.extra.kind == MemoryKind::Machine(MiriMemoryKind::Rust) {
+                return err!(DeallocatedWrongMemoryKind(
+                    "untracked Rust".to_string(),
+                    format!("{:?}", alloc.extra.kind),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Pops the most recently pushed `pthread_cleanup_push` handler and pushes a frame that
+    /// runs it, returning to the current call. Returns whether there was a handler left.
+    fn run_cleanup_handler(&mut self) -> InterpResult<'tcx, bool> {
+        let this = self.eval_context_mut();
+        let (routine, arg) = match this.machine.cleanup_handlers.pop() {
+            Some(handler) => handler,
+            None => return Ok(false),
+        };
+        let mir = this.load_mir(routine.def)?;
+        let ret_place = MPlaceTy::dangling(this.layout_of(this.tcx.mk_unit())?, this).into();
+        this.push_stack_frame(
+            routine,
+            mir.span,
+            mir,
+            Some(ret_place),
+            StackPopCleanup::None { cleanup: true },
+        )?;
+        let arg_local = this.frame().body.args_iter().next().ok_or_else(
+            || InterpError::AbiViolation("cleanup handler does not take enough arguments.".to_owned()),
+        )?;
+        let arg_dest = this.eval_place(&mir::Place::Base(mir::PlaceBase::Local(arg_local)))?;
+        this.write_scalar(arg, arg_dest)?;
+        Ok(true)
+    }
+
     /// Emulates calling a foreign item, failing if the item is not supported.
     /// This function will handle `goto_block` if needed.
     fn emulate_foreign_item(
@@ -141,25 +320,109 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
         ret: Option<mir::BasicBlock>,
     ) -> InterpResult<'tcx> {
         let this = self.eval_context_mut();
-        let attrs = this.tcx.get_attrs(def_id);
-        let link_name = match attr::first_attr_value_str_by_name(&attrs, sym::link_name) {
-            Some(name) => name.as_str(),
-            None => this.tcx.item_name(def_id).as_str(),
-        };
+        let link_name = this.link_name(def_id).as_str();
         // Strip linker suffixes (seen on 32-bit macOS).
         let link_name = link_name.get().trim_end_matches("$UNIX2003");
         let tcx = &{this.tcx.tcx};
+        if this.machine.check_abi {
+            this.check_foreign_abi(link_name, args)?;
+        }
+        this.log_event(Event::ForeignCall { name: link_name });
 
         // First: functions that diverge.
         match link_name {
             "__rust_start_panic" | "panic_impl" => {
                 return err!(MachineError("the evaluated program panicked".to_string()));
             }
-            "exit" | "ExitProcess" => {
+            "exit" | "_exit" | "_Exit" | "ExitProcess" => {
                 // it's really u32 for ExitProcess, but we have to put it into the `Exit` error variant anyway
                 let code = this.read_scalar(args[0])?.to_i32()?;
                 return err!(Exit(code));
             }
+            "quick_exit" => {
+                // The handlers run in reverse order of registration. We run one at a time: its
+                // frame returns to the call of `quick_exit`, which is then executed again.
+                if let Some(handler) = this.machine.at_quick_exit_handlers.pop() {
+                    let mir = this.load_mir(handler.def)?;
+                    let ret_place = MPlaceTy::dangling(this.layout_of(this.tcx.mk_unit())?, this).into();
+                    this.push_stack_frame(
+                        handler,
+                        mir.span,
+                        mir,
+                        Some(ret_place),
+                        StackPopCleanup::None { cleanup: true },
+                    )?;
+                    return Ok(());
+                }
+                let code = this.read_scalar(args[0])?.to_i32()?;
+                return err!(Exit(code));
+            }
+            "pthread_exit" => {
+                // The cleanup handlers run one at a time, most recently pushed first: each
+                // returns to this call, which is then executed again. There is only the main
+                // thread, so once they are done, the process ends.
+                if this.run_cleanup_handler()? {
+                    return Ok(());
+                }
+                return err!(Exit(0));
+            }
+            "abort" => {
+                // The handler runs only once: if it returns, its frame returns to the call of
+                // `abort`, which then really aborts.
+                if let Some(handler) = this.machine.abort_handler.take() {
+                    let mir = this.load_mir(handler.def)?;
+                    let ret_place = MPlaceTy::dangling(this.layout_of(this.tcx.mk_unit())?, this).into();
+                    this.push_stack_frame(
+                        handler,
+                        mir.span,
+                        mir,
+                        Some(ret_place),
+                        StackPopCleanup::None { cleanup: true },
+                    )?;
+                    return Ok(());
+                }
+                return err!(MachineError("abort called".to_string()));
+            }
+            "objc_msgSend" | "objc_msgSend_stret" | "objc_msgSend_fpret" | "objc_msgSendSuper" |
+            "objc_getClass" | "objc_lookUpClass" | "sel_registerName" => {
+                // There is no flag that forwards foreign calls to the host, so there is nothing
+                // to point the user to.
+                return err!(MachineError(
+                    "Objective-C runtime calls are not supported in miri".to_string(),
+                ));
+            }
+            // The C++ runtime puts these into the vtable slots of pure virtual and deleted
+            // functions, so calling them means the program called such a function.
+            "__cxa_pure_virtual" => {
+                return err!(MachineError("pure virtual function called".to_string()));
+            }
+            "__cxa_deleted_virtual" => {
+                return err!(MachineError("deleted virtual function called".to_string()));
+            }
+            "_Unwind_RaiseException" | "_Unwind_Resume" => {
+                return err!(MachineError("unwind not supported in miri".to_string()));
+            }
+            "__assert_fail" | "__assert_rtn" => {
+                // glibc uses `(expr, file, line, func)`, macOS uses `(func, file, line, expr)`.
+                let (expr, func) = if link_name == "__assert_fail" { (0, 3) } else { (3, 0) };
+                let read_str = |arg: OpTy<'tcx, Tag>| -> InterpResult<'tcx, String> {
+                    let ptr = this.read_scalar(arg)?.not_undef()?;
+                    // The function name may be missing.
+                    if ptr.is_null_ptr(this) {
+                        return Ok("???".to_string());
+                    }
+                    let ptr = ptr.to_ptr()?;
+                    let s = this.memory().get(ptr.alloc_id)?.read_c_str(tcx, ptr)?;
+                    Ok(String::from_utf8_lossy(s).into_owned())
+                };
+                let expr = read_str(args[expr])?;
+                let file = read_str(args[1])?;
+                let func = read_str(args[func])?;
+                let line = this.read_scalar(args[2])?.to_u32()?;
+                return err!(MachineError(format!(
+                    "assertion failed: {} at {}:{} in {}", expr, file, line, func,
+                )));
+            }
             _ => if dest.is_none() {
                 return err!(Unimplemented(
                     format!("can't call diverging foreign function: {}", link_name),
@@ -171,6 +434,91 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
         let dest = dest.expect("we already checked for a dest");
         let ret = ret.expect("dest is `Some` but ret is `None`");
         match link_name {
+            // Miri's own functions for debugging the interpreted program.
+            "__miri_watch" => {
+                let ptr = this.read_scalar(args[0])?.not_undef()?;
+                let size = this.read_scalar(args[1])?.to_usize(this)?;
+                let mode = this.read_scalar(args[2])?.to_u32()?;
+                if mode == 0 || mode > u32::from(WATCH_READ | WATCH_WRITE) {
+                    return err!(MachineError(format!("__miri_watch: invalid mode {}", mode)));
+                }
+                let ptr = this.force_ptr(ptr)?;
+                this.watch_address(ptr, Size::from_bytes(size), mode as u8);
+            }
+            "__miri_print_backtrace" => {
+                let span = this.current_span();
+                let mut diag = this.tcx.sess.struct_span_warn(span, "backtrace requested by `__miri_print_backtrace`");
+                let frames = this.generate_stacktrace(None);
+                // Like for errors, we point at the call site if it is in the local crate.
+                for idx in 0..frames.len() {
+                    let call_site_is_local = frames.get(idx+1).map_or(false,
+                        |caller_info| caller_info.instance.def_id().is_local());
+                    if call_site_is_local {
+                        diag.span_note(frames[idx].call_site, &frames[idx].to_string());
+                    } else {
+                        diag.note(&frames[idx].to_string());
+                    }
+                }
+                diag.emit();
+            }
+            "__miri_poison_memory" => {
+                let ptr = this.read_scalar(args[0])?.not_undef()?;
+                let size = Size::from_bytes(this.read_scalar(args[1])?.to_usize(this)?);
+                if let Some(ptr) = this.memory().check_ptr_access(ptr, size, Align::from_bytes(1).unwrap())? {
+                    // Go through a regular write first, so that this counts as a write for
+                    // Stacked Borrows and removes any pointers stored there.
+                    let alloc = this.memory_mut().get_mut(ptr.alloc_id)?;
+                    alloc.write_repeat(tcx, ptr, 0, size)?;
+                    alloc.mark_definedness(ptr, size, false);
+                }
+            }
+            "__miri_get_alloc_id" => {
+                let ptr = this.read_scalar(args[0])?.not_undef()?;
+                let ptr = this.force_ptr(ptr)?;
+                this.write_scalar(Scalar::from_uint(ptr.alloc_id.0, dest.layout.size), dest)?;
+            }
+            "__miri_set_rng_seed" => {
+                let seed = this.read_scalar(args[0])?.to_u64()?;
+                // Creating the RNG here would also turn on intptrcast, so we only reseed it.
+                match &this.memory().extra.rng {
+                    Some(rng) => *rng.borrow_mut() = StdRng::seed_from_u64(seed),
+                    None => return err!(Unimplemented(
+                        "`__miri_set_rng_seed` requires `-Zmiri-seed`".to_owned(),
+                    )),
+                }
+            }
+
+            "__miri_disable_stacked_borrows" => {
+                let mut stacked_borrows = this.memory().extra.stacked_borrows.borrow_mut();
+                stacked_borrows.suppressed_violations.get_or_insert_with(Vec::new);
+            }
+            "__miri_enable_stacked_borrows" => {
+                let violations = this.memory().extra.stacked_borrows.borrow_mut()
+                    .suppressed_violations.take()
+                    .unwrap_or_default();
+                let span = this.current_span();
+                for violation in violations {
+                    this.tcx.sess.span_warn(
+                        span,
+                        &format!("Stacked Borrows violation while the checks were disabled: {}", violation),
+                    );
+                }
+            }
+            "__miri_set_abort_handler" => {
+                let handler = this.read_scalar(args[0])?.not_undef()?;
+                this.machine.abort_handler = if handler.is_null_ptr(this) {
+                    None
+                } else {
+                    let handler = this.memory().get_fn(handler.to_ptr()?)?;
+                    if this.load_mir(handler.def)?.arg_count != 0 {
+                        return err!(AbiViolation(
+                            "the abort handler must not take arguments".to_string(),
+                        ));
+                    }
+                    Some(handler)
+                };
+            }
+
             "malloc" => {
                 let size = this.read_scalar(args[0])?.to_usize(this)?;
                 let res = this.malloc(size, /*zero_init:*/ false);
@@ -205,6 +553,7 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
                         Align::from_bytes(align).unwrap(),
                         MiriMemoryKind::C.into()
                     );
+                    this.record_alloc_site(ptr, MiriMemoryKind::C);
                     this.write_scalar(Scalar::Ptr(ptr), ret.into())?;
                 }
                 this.write_null(dest)?;
@@ -235,6 +584,8 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
                         Align::from_bytes(align).unwrap(),
                         MiriMemoryKind::Rust.into()
                     );
+                this.record_alloc_site(ptr, MiriMemoryKind::Rust);
+                this.machine.rust_alloc_ids.insert(ptr.alloc_id);
                 this.write_scalar(Scalar::Ptr(ptr), dest)?;
             }
             "__rust_alloc_zeroed" => {
@@ -252,6 +603,8 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
                         Align::from_bytes(align).unwrap(),
                         MiriMemoryKind::Rust.into()
                     );
+                this.record_alloc_site(ptr, MiriMemoryKind::Rust);
+                this.machine.rust_alloc_ids.insert(ptr.alloc_id);
                 this.memory_mut()
                     .get_mut(ptr.alloc_id)?
                     .write_repeat(tcx, ptr, 0, Size::from_bytes(size))?;
@@ -267,6 +620,8 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
                 if !align.is_power_of_two() {
                     return err!(HeapAllocNonPowerOfTwoAlignment(align));
                 }
+                this.check_rust_dealloc(ptr)?;
+                this.log_event(Event::Dealloc { ptr });
                 this.memory_mut().deallocate(
                     ptr,
                     Some((Size::from_bytes(old_size), Align::from_bytes(align).unwrap())),
@@ -284,6 +639,8 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
                 if !align.is_power_of_two() {
                     return err!(HeapAllocNonPowerOfTwoAlignment(align));
                 }
+                this.check_rust_dealloc(ptr)?;
+                this.log_event(Event::Dealloc { ptr });
                 let new_ptr = this.memory_mut().reallocate(
                     ptr,
                     Size::from_bytes(old_size),
@@ -292,9 +649,68 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
                     Align::from_bytes(align).unwrap(),
                     MiriMemoryKind::Rust.into(),
                 )?;
+                this.record_alloc_site(new_ptr, MiriMemoryKind::Rust);
+                this.machine.rust_alloc_ids.insert(new_ptr.alloc_id);
                 this.write_scalar(Scalar::Ptr(new_ptr), dest)?;
             }
 
+            "__cxa_demangle" => {
+                let mangled = this.read_scalar(args[0])?.not_undef()?;
+                let buf = this.read_scalar(args[1])?.not_undef()?;
+                let length_ptr = this.read_scalar(args[2])?.not_undef()?;
+                let status_ptr = this.read_scalar(args[3])?.not_undef()?;
+                // We only know Rust's mangling, which looks like C++'s for most Rust symbols.
+                let demangled = if mangled.is_null_ptr(this)
+                    || (!buf.is_null_ptr(this) && length_ptr.is_null_ptr(this))
+                {
+                    Err(-3) // invalid argument
+                } else {
+                    let mangled = mangled.to_ptr()?;
+                    let mangled = this.memory().get(mangled.alloc_id)?.read_c_str(tcx, mangled)?;
+                    std::str::from_utf8(mangled).ok()
+                        .and_then(|mangled| rustc_demangle::try_demangle(mangled).ok())
+                        .map(|demangled| format!("{:#}", demangled))
+                        .ok_or(-2) // invalid mangled name
+                };
+                let (status, res) = match demangled {
+                    Ok(demangled) => {
+                        let mut bytes = demangled.into_bytes();
+                        bytes.push(0);
+                        let len = bytes.len() as u64;
+                        let capacity = if buf.is_null_ptr(this) {
+                            0
+                        } else {
+                            this.read_scalar(this.deref_operand(args[2])?.into())?.to_usize(this)?
+                        };
+                        // Like the C++ runtime, we reuse the buffer if it is large enough, and
+                        // otherwise allocate a new one and report its size.
+                        let res = if capacity >= len {
+                            buf
+                        } else {
+                            let res = if buf.is_null_ptr(this) {
+                                this.malloc(len, /*zero_init:*/ false)
+                            } else {
+                                this.realloc(buf, len)?
+                            };
+                            if !length_ptr.is_null_ptr(this) {
+                                let length_place = this.deref_operand(args[2])?;
+                                this.write_scalar(Scalar::from_uint(len, length_place.layout.size), length_place.into())?;
+                            }
+                            res
+                        };
+                        let ptr = res.to_ptr()?;
+                        this.memory_mut().get_mut(ptr.alloc_id)?.write_bytes(tcx, ptr, &bytes)?;
+                        (0, res)
+                    }
+                    Err(status) => (status, Scalar::from_int(0, dest.layout.size)),
+                };
+                if !status_ptr.is_null_ptr(this) {
+                    let status_place = this.deref_operand(args[3])?;
+                    this.write_scalar(Scalar::from_int(status, status_place.layout.size), status_place.into())?;
+                }
+                this.write_scalar(res, dest)?;
+            }
+
             "syscall" => {
                 let sys_getrandom = this.eval_path_scalar(&["libc", "SYS_getrandom"])?
                     .expect("Failed to get libc::SYS_getrandom")
@@ -302,14 +718,15 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
 
                 // `libc::syscall(NR_GETRANDOM, buf.as_mut_ptr(), buf.len(), GRND_NONBLOCK)`
                 // is called if a `HashMap` is created the regular way (e.g. HashMap<K, V>).
+                let mut va_args = VaList::new(link_name, args, 1);
                 match this.read_scalar(args[0])?.to_usize(this)? {
                     id if id == sys_getrandom => {
-                        let ptr = this.read_scalar(args[1])?.not_undef()?;
-                        let len = this.read_scalar(args[2])?.to_usize(this)?;
+                        let ptr = va_args.read_scalar(this)?;
+                        let len = va_args.read_scalar(this)?.to_usize(this)?;
 
                         // The only supported flags are GRND_RANDOM and GRND_NONBLOCK,
                         // neither of which have any effect on our current PRNG
-                        let _flags = this.read_scalar(args[3])?.to_i32()?;
+                        let _flags = va_args.read_int(this)?;
 
                         gen_random(this, len as usize, ptr)?;
                         this.write_scalar(Scalar::from_uint(len, dest.layout.size), dest)?;
@@ -321,6 +738,26 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
                     }
                 }
             }
+            "getrandom" => {
+                let ptr = this.read_scalar(args[0])?.not_undef()?;
+                let len = this.read_scalar(args[1])?.to_usize(this)?;
+                // The flags (`GRND_RANDOM` and `GRND_NONBLOCK`) have no effect on our PRNG.
+                let _flags = this.read_scalar(args[2])?.to_u32()?;
+                gen_random(this, len as usize, ptr)?;
+                this.write_scalar(Scalar::from_uint(len, dest.layout.size), dest)?;
+            }
+            "getentropy" => {
+                let ptr = this.read_scalar(args[0])?.not_undef()?;
+                let len = this.read_scalar(args[1])?.to_usize(this)?;
+                // `getentropy` refuses to produce more than 256 bytes at once.
+                if len > 256 {
+                    this.set_errno("EIO")?;
+                    this.write_scalar(Scalar::from_int(-1, dest.layout.size), dest)?;
+                } else {
+                    gen_random(this, len as usize, ptr)?;
+                    this.write_null(dest)?;
+                }
+            }
 
             "dlsym" => {
                 let _handle = this.read_scalar(args[0])?;
@@ -435,6 +872,89 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
                 }
             }
 
+            "rawmemchr" => {
+                let ptr = this.read_scalar(args[0])?.not_undef()?;
+                let val = this.read_scalar(args[1])?.to_i32()? as u8;
+                // There is no length limit, so we go on until `val` shows up or we read out of
+                // bounds.
+                let mut idx = 0;
+                let new_ptr = loop {
+                    let new_ptr = ptr.ptr_offset(Size::from_bytes(idx), this)?;
+                    if this.memory().read_bytes(new_ptr, Size::from_bytes(1))?[0] == val {
+                        break new_ptr;
+                    }
+                    idx += 1;
+                };
+                this.write_scalar(new_ptr, dest)?;
+            }
+
+            "memmem" => {
+                let haystack = this.read_scalar(args[0])?.not_undef()?;
+                let haystack_len = this.read_scalar(args[1])?.to_usize(this)?;
+                let needle = this.read_scalar(args[2])?.not_undef()?;
+                let needle_len = this.read_scalar(args[3])?.to_usize(this)?;
+                let idx = if needle_len == 0 {
+                    // The empty needle is found at the start of the haystack.
+                    Some(0)
+                } else {
+                    let haystack_bytes = this.memory().read_bytes(haystack, Size::from_bytes(haystack_len))?;
+                    let needle_bytes = this.memory().read_bytes(needle, Size::from_bytes(needle_len))?;
+                    haystack_bytes.windows(needle_bytes.len()).position(|window| window == needle_bytes)
+                };
+                if let Some(idx) = idx {
+                    let new_ptr = haystack.ptr_offset(Size::from_bytes(idx as u64), this)?;
+                    this.write_scalar(new_ptr, dest)?;
+                } else {
+                    this.write_null(dest)?;
+                }
+            }
+
+            // The C library's pseudo-random numbers. Like on a real system, they are
+            // deterministic unless the program picks a seed that is not.
+            "srand" => {
+                let seed = this.read_scalar(args[0])?.to_u32()?;
+                this.machine.c_rand_state = u64::from(seed);
+            }
+            "rand" | "rand_r" => {
+                let rand_max = this.eval_libc_i32("RAND_MAX")? as u64;
+                let step = |state: u64| {
+                    state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407)
+                };
+                let state = if link_name == "rand" {
+                    this.machine.c_rand_state = step(this.machine.c_rand_state);
+                    this.machine.c_rand_state
+                } else {
+                    // `rand_r` keeps its state in an `unsigned int` provided by the caller.
+                    let seed_place = this.deref_operand(args[0])?;
+                    let state = step(u64::from(this.read_scalar(seed_place.into())?.to_u32()?));
+                    this.write_scalar(Scalar::from_u32(state as u32), seed_place.into())?;
+                    state
+                };
+                let random = (state >> 33) % (rand_max + 1);
+                this.write_scalar(Scalar::from_uint(random, dest.layout.size), dest)?;
+            }
+            "srand48" => {
+                let seed = this.read_scalar(args[0])?.not_undef()?;
+                let seed = this.force_bits(seed, args[0].layout.size)? as u64;
+                this.machine.drand48_state = (seed & 0xFFFF_FFFF) << 16 | 0x330E;
+            }
+            "drand48" | "lrand48" | "mrand48" => {
+                // The linear congruential generator prescribed by POSIX.
+                let state = this.machine.drand48_state.wrapping_mul(0x5_DEEC_E66D).wrapping_add(0xB);
+                let state = state & ((1 << 48) - 1);
+                this.machine.drand48_state = state;
+                match link_name {
+                    "drand48" => {
+                        let f = state as f64 / (1u64 << 48) as f64;
+                        this.write_scalar(Scalar::from_u64(f.to_bits()), dest)?;
+                    }
+                    // A non-negative number below 2^31.
+                    "lrand48" => this.write_scalar(Scalar::from_uint(state >> 17, dest.layout.size), dest)?,
+                    // A signed number in [-2^31, 2^31).
+                    _ => this.write_scalar(Scalar::from_int((state >> 16) as u32 as i32, dest.layout.size), dest)?,
+                }
+            }
+
             "getenv" => {
                 let result = {
                     let name_ptr = this.read_scalar(args[0])?.to_ptr()?;
@@ -555,108 +1075,1155 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
                 )?;
             }
 
-            "strlen" => {
-                let ptr = this.read_scalar(args[0])?.to_ptr()?;
-                let n = this.memory().get(ptr.alloc_id)?.read_c_str(tcx, ptr)?.len();
-                this.write_scalar(Scalar::from_uint(n as u64, dest.layout.size), dest)?;
+            // C `FILE` streams. We only support streams on the standard file descriptors.
+            "fdopen" => {
+                let fd = this.read_scalar(args[0])?.to_i32()?;
+                if fd == 0 || fd == 1 || fd == 2 {
+                    let stream = this.open_stream(fd);
+                    this.write_scalar(stream, dest)?;
+                } else {
+                    this.set_errno("EBADF")?;
+                    this.write_null(dest)?;
+                }
             }
-
-            // math functions
-
-            "cbrtf" | "coshf" | "sinhf" |"tanf" => {
-                // FIXME: Using host floats.
-                let f = f32::from_bits(this.read_scalar(args[0])?.to_u32()?);
-                let f = match link_name {
-                    "cbrtf" => f.cbrt(),
-                    "coshf" => f.cosh(),
-                    "sinhf" => f.sinh(),
-                    "tanf" => f.tan(),
-                    _ => bug!(),
+            "fileno" => {
+                let stream = this.read_scalar(args[0])?.not_undef()?;
+                let fd = match this.get_stream_mut(stream)? {
+                    Some(stream) => stream.fd,
+                    None => {
+                        this.set_errno("EBADF")?;
+                        -1
+                    }
                 };
-                this.write_scalar(Scalar::from_u32(f.to_bits()), dest)?;
+                this.write_scalar(Scalar::from_int(fd, dest.layout.size), dest)?;
             }
-            // underscore case for windows
-            "_hypotf" | "hypotf" | "atan2f" => {
-                // FIXME: Using host floats.
-                let f1 = f32::from_bits(this.read_scalar(args[0])?.to_u32()?);
-                let f2 = f32::from_bits(this.read_scalar(args[1])?.to_u32()?);
-                let n = match link_name {
-                    "_hypotf" | "hypotf" => f1.hypot(f2),
-                    "atan2f" => f1.atan2(f2),
+            "fclose" => {
+                let stream = this.read_scalar(args[0])?.not_undef()?;
+                if !this.close_stream(stream)? {
+                    return err!(MachineError(format!("{} called on a stream that is not open", link_name)));
+                }
+                // Return success (`0`).
+                this.write_null(dest)?;
+            }
+            "feof" | "ferror" | "clearerr" => {
+                let stream = this.read_scalar(args[0])?.not_undef()?;
+                let stream = this.expect_stream_mut(stream, link_name)?;
+                let flags = stream.flags;
+                match link_name {
+                    "feof" => {
+                        let eof = (flags & STREAM_EOF) != 0;
+                        this.write_scalar(Scalar::from_int(eof as i32, dest.layout.size), dest)?;
+                    }
+                    "ferror" => {
+                        let error = (flags & STREAM_ERROR) != 0;
+                        this.write_scalar(Scalar::from_int(error as i32, dest.layout.size), dest)?;
+                    }
+                    "clearerr" => {
+                        stream.flags &= !(STREAM_EOF | STREAM_ERROR);
+                    }
                     _ => bug!(),
+                }
+            }
+            "rewind" => {
+                let stream = this.read_scalar(args[0])?.not_undef()?;
+                // The standard file descriptors have no position to reset, so all that
+                // remains to be done is clearing the indicators.
+                this.expect_stream_mut(stream, link_name)?.flags &= !(STREAM_EOF | STREAM_ERROR);
+            }
+            "setvbuf" => {
+                let stream = this.read_scalar(args[0])?.not_undef()?;
+                // We ignore the buffer, as we write through to the host immediately.
+                let mode = this.read_scalar(args[2])?.to_i32()?;
+                let valid_mode = ["_IOFBF", "_IOLBF", "_IONBF"].iter()
+                    .map(|name| this.eval_libc_i32(name))
+                    .collect::<InterpResult<'tcx, Vec<i32>>>()?
+                    .contains(&mode);
+                let result = match this.get_stream_mut(stream)? {
+                    Some(stream) if valid_mode => {
+                        stream.buffering = Some(mode);
+                        0
+                    }
+                    Some(_) => {
+                        this.set_errno("EINVAL")?;
+                        -1
+                    }
+                    None => {
+                        this.set_errno("EBADF")?;
+                        -1
+                    }
                 };
-                this.write_scalar(Scalar::from_u32(n.to_bits()), dest)?;
+                this.write_scalar(Scalar::from_int(result, dest.layout.size), dest)?;
             }
-
-            "cbrt" | "cosh" | "sinh" | "tan" => {
-                // FIXME: Using host floats.
-                let f = f64::from_bits(this.read_scalar(args[0])?.to_u64()?);
-                let f = match link_name {
-                    "cbrt" => f.cbrt(),
-                    "cosh" => f.cosh(),
-                    "sinh" => f.sinh(),
-                    "tan" => f.tan(),
-                    _ => bug!(),
+            "setbuf" | "setbuffer" | "setlinebuf" => {
+                let stream = this.read_scalar(args[0])?.not_undef()?;
+                let mode = if link_name == "setlinebuf" {
+                    this.eval_libc_i32("_IOLBF")?
+                } else {
+                    // Passing a null buffer makes the stream unbuffered.
+                    let buf = this.read_scalar(args[1])?.not_undef()?;
+                    this.eval_libc_i32(if buf.is_null_ptr(this) { "_IONBF" } else { "_IOFBF" })?
                 };
-                this.write_scalar(Scalar::from_u64(f.to_bits()), dest)?;
+                this.expect_stream_mut(stream, link_name)?.buffering = Some(mode);
             }
-            // underscore case for windows
-            "_hypot" | "hypot" | "atan2" => {
-                // FIXME: Using host floats.
-                let f1 = f64::from_bits(this.read_scalar(args[0])?.to_u64()?);
-                let f2 = f64::from_bits(this.read_scalar(args[1])?.to_u64()?);
-                let n = match link_name {
-                    "_hypot" | "hypot" => f1.hypot(f2),
-                    "atan2" => f1.atan2(f2),
-                    _ => bug!(),
+            "fflush" => {
+                use std::io::{self, Write};
+
+                let stream = this.read_scalar(args[0])?.not_undef()?;
+                // We write through to the host immediately, so only the host's buffers need flushing.
+                let fds: Option<Vec<i32>> = if stream.is_null_ptr(this) {
+                    // Flush all open streams.
+                    Some(this.machine.stdio.streams.values().map(|stream| stream.fd).collect())
+                } else {
+                    this.get_stream_mut(stream)?.map(|stream| vec![stream.fd])
                 };
-                this.write_scalar(Scalar::from_u64(n.to_bits()), dest)?;
+                let result = match fds {
+                    Some(fds) => {
+                        let flushed = fds.into_iter().all(|fd| match fd {
+                            1 => io::stdout().flush().is_ok(),
+                            2 => io::stderr().flush().is_ok(),
+                            _ => true,
+                        });
+                        if flushed { 0 } else { -1 }
+                    }
+                    None => {
+                        this.set_errno("EBADF")?;
+                        -1
+                    }
+                };
+                this.write_scalar(Scalar::from_int(result, dest.layout.size), dest)?;
             }
-
-            // Some things needed for `sys::thread` initialization to go through.
-            "signal" | "sigaction" | "sigaltstack" => {
-                this.write_scalar(Scalar::from_int(0, dest.layout.size), dest)?;
+            "flockfile" | "ftrylockfile" => {
+                let stream = this.read_scalar(args[0])?.not_undef()?;
+                // There is only one thread, so the lock is always available.
+                this.expect_stream_mut(stream, link_name)?.lock_count += 1;
+                if link_name == "ftrylockfile" {
+                    this.write_null(dest)?;
+                }
             }
-
-            "sysconf" => {
-                let name = this.read_scalar(args[0])?.to_i32()?;
-
-                trace!("sysconf() called with name {}", name);
-                // TODO: Cache the sysconf integers via Miri's global cache.
-                let paths = &[
-                    (&["libc", "_SC_PAGESIZE"], Scalar::from_int(PAGE_SIZE, dest.layout.size)),
-                    (&["libc", "_SC_GETPW_R_SIZE_MAX"], Scalar::from_int(-1, dest.layout.size)),
-                    (&["libc", "_SC_NPROCESSORS_ONLN"], Scalar::from_int(NUM_CPUS, dest.layout.size)),
-                ];
-                let mut result = None;
-                for &(path, path_value) in paths {
-                    if let Some(val) = this.eval_path_scalar(path)? {
-                        let val = val.to_i32()?;
-                        if val == name {
-                            result = Some(path_value);
-                            break;
-                        }
-
-                    }
+            "funlockfile" => {
+                let stream = this.read_scalar(args[0])?.not_undef()?;
+                let stream = this.expect_stream_mut(stream, link_name)?;
+                if stream.lock_count == 0 {
+                    return err!(MachineError(format!("{} called on a stream that is not locked", link_name)));
                 }
-                if let Some(result) = result {
-                    this.write_scalar(result, dest)?;
+                stream.lock_count -= 1;
+            }
+            // The `_unlocked` variants behave like the locked ones, but expect the caller to hold
+            // the stream lock.
+            "putc" | "fputc" | "putc_unlocked" | "fputc_unlocked" => {
+                let c = this.read_scalar(args[0])?.to_i32()?;
+                let stream = this.read_scalar(args[1])?.not_undef()?;
+                if link_name.ends_with("_unlocked") {
+                    this.check_stream_locked(stream, link_name)?;
+                }
+                // `c` is written as an `unsigned char`, and that is also what we return.
+                let c = c as u8;
+                let result = if this.write_to_stream(stream, &[c], link_name)? {
+                    i32::from(c)
                 } else {
-                    return err!(Unimplemented(
-                        format!("Unimplemented sysconf name: {}", name),
-                    ));
+                    this.eval_libc_i32("EOF")?
+                };
+                this.write_scalar(Scalar::from_int(result, dest.layout.size), dest)?;
+            }
+            "fputs" | "fputs_unlocked" => {
+                let s = this.read_scalar(args[0])?.to_ptr()?;
+                let stream = this.read_scalar(args[1])?.not_undef()?;
+                if link_name.ends_with("_unlocked") {
+                    this.check_stream_locked(stream, link_name)?;
                 }
+                let s = this.memory().get(s.alloc_id)?.read_c_str(tcx, s)?.to_owned();
+                let result = if this.write_to_stream(stream, &s, link_name)? {
+                    0
+                } else {
+                    this.eval_libc_i32("EOF")?
+                };
+                this.write_scalar(Scalar::from_int(result, dest.layout.size), dest)?;
             }
-
-            "sched_getaffinity" => {
-                // Return an error; `num_cpus` then falls back to `sysconf`.
-                this.write_scalar(Scalar::from_int(-1, dest.layout.size), dest)?;
+            "fwrite" | "fwrite_unlocked" => {
+                let buf = this.read_scalar(args[0])?.not_undef()?;
+                let size = this.read_scalar(args[1])?.to_usize(this)?;
+                let nmemb = this.read_scalar(args[2])?.to_usize(this)?;
+                let stream = this.read_scalar(args[3])?.not_undef()?;
+                if link_name.ends_with("_unlocked") {
+                    this.check_stream_locked(stream, link_name)?;
+                }
+                let len = size.checked_mul(nmemb).ok_or_else(|| InterpError::Overflow(mir::BinOp::Mul))?;
+                let bytes = this.memory().read_bytes(buf, Size::from_bytes(len))?.to_owned();
+                let result = if this.write_to_stream(stream, &bytes, link_name)? {
+                    nmemb
+                } else {
+                    0
+                };
+                this.write_scalar(Scalar::from_uint(result, dest.layout.size), dest)?;
             }
-
-            "isatty" => {
+            "getc" | "fgetc" | "getc_unlocked" | "fgetc_unlocked" => {
+                let stream = this.read_scalar(args[0])?.not_undef()?;
+                if link_name.ends_with("_unlocked") {
+                    this.check_stream_locked(stream, link_name)?;
+                }
+                this.read_from_stream(stream, link_name)?;
+                let eof = this.eval_libc_i32("EOF")?;
+                this.write_scalar(Scalar::from_int(eof, dest.layout.size), dest)?;
+            }
+            "fread" | "fread_unlocked" => {
+                let stream = this.read_scalar(args[3])?.not_undef()?;
+                if link_name.ends_with("_unlocked") {
+                    this.check_stream_locked(stream, link_name)?;
+                }
+                this.read_from_stream(stream, link_name)?;
+                // No elements were read.
                 this.write_null(dest)?;
             }
-
+            "fgets" | "fgets_unlocked" => {
+                let stream = this.read_scalar(args[2])?.not_undef()?;
+                if link_name.ends_with("_unlocked") {
+                    this.check_stream_locked(stream, link_name)?;
+                }
+                this.read_from_stream(stream, link_name)?;
+                // End-of-file was hit before any characters were read, so we return a null pointer.
+                this.write_null(dest)?;
+            }
+            // The `_chk` variants are emitted by `_FORTIFY_SOURCE`. Their extra `flag` argument
+            // only controls checks that Miri performs anyway, so we ignore it.
+            "printf" | "__printf_chk" => {
+                let fmt = if link_name == "printf" { 0 } else { 1 };
+                let bytes = this.format_printf(this.read_scalar(args[fmt])?.not_undef()?, VaList::new(link_name, args, fmt + 1))?;
+                let result = match crate::stdio::write_to_host(1, &bytes) {
+                    Ok(()) => bytes.len() as i64,
+                    Err(_) => -1,
+                };
+                this.write_scalar(Scalar::from_int(result, dest.layout.size), dest)?;
+            }
+            "fprintf" | "__fprintf_chk" => {
+                let stream = this.read_scalar(args[0])?.not_undef()?;
+                let fmt = if link_name == "fprintf" { 1 } else { 2 };
+                let bytes = this.format_printf(this.read_scalar(args[fmt])?.not_undef()?, VaList::new(link_name, args, fmt + 1))?;
+                let result = if this.write_to_stream(stream, &bytes, link_name)? {
+                    bytes.len() as i64
+                } else {
+                    -1
+                };
+                this.write_scalar(Scalar::from_int(result, dest.layout.size), dest)?;
+            }
+            "sprintf" | "__sprintf_chk" => {
+                let buf = this.read_scalar(args[0])?.not_undef()?;
+                let fmt = if link_name == "sprintf" { 1 } else { 3 };
+                let mut bytes = this.format_printf(this.read_scalar(args[fmt])?.not_undef()?, VaList::new(link_name, args, fmt + 1))?;
+                let len = bytes.len();
+                bytes.push(0);
+                if link_name == "__sprintf_chk" {
+                    // The size of the destination buffer as far as the compiler could tell.
+                    let buf_size = this.read_scalar(args[2])?.to_usize(this)?;
+                    if bytes.len() as u64 > buf_size {
+                        this.tcx.sess.warn(&format!(
+                            "{} writes {} bytes to a buffer of size {}", link_name, bytes.len(), buf_size,
+                        ));
+                    }
+                }
+                this.write_raw_bytes(buf, &bytes)?;
+                this.write_scalar(Scalar::from_int(len as i64, dest.layout.size), dest)?;
+            }
+            "snprintf" | "__snprintf_chk" => {
+                let buf = this.read_scalar(args[0])?.not_undef()?;
+                let max_len = this.read_scalar(args[1])?.to_usize(this)?;
+                let fmt = if link_name == "snprintf" { 2 } else { 4 };
+                if link_name == "__snprintf_chk" {
+                    // The size of the destination buffer as far as the compiler could tell.
+                    let buf_size = this.read_scalar(args[3])?.to_usize(this)?;
+                    if max_len > buf_size {
+                        this.tcx.sess.warn(&format!(
+                            "{} called with a maximum length of {} for a buffer of size {}",
+                            link_name, max_len, buf_size,
+                        ));
+                    }
+                }
+                let mut bytes = this.format_printf(this.read_scalar(args[fmt])?.not_undef()?, VaList::new(link_name, args, fmt + 1))?;
+                let len = bytes.len();
+                if max_len > 0 {
+                    // The output is truncated to make room for the null terminator.
+                    bytes.truncate(max_len as usize - 1);
+                    bytes.push(0);
+                    this.write_raw_bytes(buf, &bytes)?;
+                }
+                // We return the length the output would have had without truncation.
+                this.write_scalar(Scalar::from_int(len as i64, dest.layout.size), dest)?;
+            }
+
+            // We never unwind, so there is no unwinding context to inspect or modify.
+            "_Unwind_GetIP" | "_Unwind_GetGR" | "_Unwind_GetCFA" | "_Unwind_GetLanguageSpecificData" |
+            "_Unwind_GetRegionStart" => {
+                this.write_null(dest)?;
+            }
+            "_Unwind_SetIP" | "_Unwind_SetGR" => {
+                // Nothing to do.
+            }
+            "__gcc_personality_v0" | "__gxx_personality_v0" => {
+                // No frame handles anything: return `_URC_CONTINUE_UNWIND`.
+                this.write_scalar(Scalar::from_int(8, dest.layout.size), dest)?;
+            }
+
+            "__errno_location" | "__error" => {
+                let errno_place = this.machine.last_error.unwrap();
+                this.write_scalar(errno_place.ptr, dest)?;
+            }
+
+            "strlen" => {
+                let ptr = this.read_scalar(args[0])?.to_ptr()?;
+                let n = this.memory().get(ptr.alloc_id)?.read_c_str(tcx, ptr)?.len();
+                this.write_scalar(Scalar::from_uint(n as u64, dest.layout.size), dest)?;
+            }
+
+            "strspn" | "strcspn" | "strpbrk" => {
+                let s = this.read_scalar(args[0])?.to_ptr()?;
+                let set = this.read_scalar(args[1])?.to_ptr()?;
+                let set = this.memory().get(set.alloc_id)?.read_c_str(tcx, set)?.to_owned();
+                let s_bytes = this.memory().get(s.alloc_id)?.read_c_str(tcx, s)?;
+                // `strspn` skips bytes in `set`, the others skip bytes not in `set`.
+                let skip_members = link_name == "strspn";
+                let n = s_bytes.iter().position(|c| set.contains(c) != skip_members).unwrap_or(s_bytes.len());
+                if link_name == "strpbrk" {
+                    if n < s_bytes.len() {
+                        let new_ptr = s.offset(Size::from_bytes(n as u64), this)?;
+                        this.write_scalar(Scalar::Ptr(new_ptr), dest)?;
+                    } else {
+                        this.write_null(dest)?;
+                    }
+                } else {
+                    this.write_scalar(Scalar::from_uint(n as u64, dest.layout.size), dest)?;
+                }
+            }
+
+            // Character classification in the C locale, where only ASCII characters are special.
+            "isalpha" | "isdigit" | "isalnum" | "isspace" | "ispunct" | "iscntrl" |
+            "isupper" | "islower" | "isxdigit" | "isprint" | "isgraph" | "isblank" => {
+                let c = this.read_scalar(args[0])?.to_i32()?;
+                let result = u8::try_from(c).map_or(false, |c| is_ascii_class(&link_name[2..], c));
+                this.write_scalar(Scalar::from_int(result as i32, dest.layout.size), dest)?;
+            }
+            "toupper" | "tolower" => {
+                let c = this.read_scalar(args[0])?.to_i32()?;
+                // Anything but ASCII letters, including `EOF`, is returned unchanged.
+                let result = match u8::try_from(c) {
+                    Ok(c) if link_name == "toupper" => i32::from(c.to_ascii_uppercase()),
+                    Ok(c) => i32::from(c.to_ascii_lowercase()),
+                    Err(_) => c,
+                };
+                this.write_scalar(Scalar::from_int(result, dest.layout.size), dest)?;
+            }
+            // The same for wide characters. `wint_t` is 32 bits wide on Unix but 16 bits on Windows.
+            "iswalpha" | "iswdigit" | "iswalnum" | "iswspace" | "iswpunct" | "iswcntrl" |
+            "iswupper" | "iswlower" | "iswxdigit" | "iswprint" | "iswgraph" | "iswblank" => {
+                let c = this.read_scalar(args[0])?.to_bits(args[0].layout.size)?;
+                let result = u8::try_from(c).map_or(false, |c| is_ascii_class(&link_name[3..], c));
+                this.write_scalar(Scalar::from_int(result as i32, dest.layout.size), dest)?;
+            }
+            "towupper" | "towlower" => {
+                let c = this.read_scalar(args[0])?.to_bits(args[0].layout.size)?;
+                // Anything but ASCII letters, including `WEOF`, is returned unchanged.
+                let result = match u8::try_from(c) {
+                    Ok(c) if link_name == "towupper" => u128::from(c.to_ascii_uppercase()),
+                    Ok(c) => u128::from(c.to_ascii_lowercase()),
+                    Err(_) => c,
+                };
+                this.write_scalar(Scalar::from_uint(result, dest.layout.size), dest)?;
+            }
+
+            "strstr" => {
+                let haystack = this.read_scalar(args[0])?.to_ptr()?;
+                let needle = this.read_scalar(args[1])?.to_ptr()?;
+                let needle = this.memory().get(needle.alloc_id)?.read_c_str(tcx, needle)?.to_owned();
+                let haystack_bytes = this.memory().get(haystack.alloc_id)?.read_c_str(tcx, haystack)?;
+                let idx = if needle.is_empty() {
+                    // The empty needle is found at the start of the haystack.
+                    Some(0)
+                } else {
+                    haystack_bytes.windows(needle.len()).position(|window| window == &needle[..])
+                };
+                if let Some(idx) = idx {
+                    let new_ptr = haystack.offset(Size::from_bytes(idx as u64), this)?;
+                    this.write_scalar(Scalar::Ptr(new_ptr), dest)?;
+                } else {
+                    this.write_null(dest)?;
+                }
+            }
+
+            // The `_chk` variants are emitted by `_FORTIFY_SOURCE` and take the size of the
+            // destination buffer as far as the compiler could tell as their last argument.
+            "strcpy" | "__strcpy_chk" | "stpcpy" => {
+                let dest_ptr = this.read_scalar(args[0])?.not_undef()?;
+                let src = this.read_scalar(args[1])?.to_ptr()?;
+                let mut bytes = this.memory().get(src.alloc_id)?.read_c_str(tcx, src)?.to_owned();
+                let len = bytes.len() as u64;
+                bytes.push(0);
+                if link_name == "__strcpy_chk" {
+                    let dest_len = this.read_scalar(args[2])?.to_usize(this)?;
+                    check_fortify_len(link_name, bytes.len() as u64, dest_len)?;
+                }
+                this.write_raw_bytes(dest_ptr, &bytes)?;
+                if link_name == "stpcpy" {
+                    // Return a pointer to the null terminator.
+                    let end = dest_ptr.ptr_offset(Size::from_bytes(len), this)?;
+                    this.write_scalar(end, dest)?;
+                } else {
+                    this.write_scalar(dest_ptr, dest)?;
+                }
+            }
+            "strncpy" | "__strncpy_chk" | "stpncpy" => {
+                let dest_ptr = this.read_scalar(args[0])?.not_undef()?;
+                let src = this.read_scalar(args[1])?.not_undef()?;
+                let n = this.read_scalar(args[2])?.to_usize(this)?;
+                if link_name == "__strncpy_chk" {
+                    let dest_len = this.read_scalar(args[3])?.to_usize(this)?;
+                    check_fortify_len(link_name, n, dest_len)?;
+                }
+                // `src` need not be null-terminated if it is at least `n` bytes long.
+                let mut bytes = this.read_c_str_bounded(src, n)?;
+                let len = bytes.len() as u64;
+                // The rest of the destination is filled with zeros.
+                bytes.resize(n as usize, 0);
+                this.write_raw_bytes(dest_ptr, &bytes)?;
+                if link_name == "stpncpy" {
+                    // Return a pointer to the first null byte written, or past the end if
+                    // there is none.
+                    let end = dest_ptr.ptr_offset(Size::from_bytes(len), this)?;
+                    this.write_scalar(end, dest)?;
+                } else {
+                    this.write_scalar(dest_ptr, dest)?;
+                }
+            }
+            "strcat" | "__strcat_chk" => {
+                let dest_ptr = this.read_scalar(args[0])?.to_ptr()?;
+                let src = this.read_scalar(args[1])?.to_ptr()?;
+                let dest_str_len = this.memory().get(dest_ptr.alloc_id)?.read_c_str(tcx, dest_ptr)?.len() as u64;
+                let mut bytes = this.memory().get(src.alloc_id)?.read_c_str(tcx, src)?.to_owned();
+                bytes.push(0);
+                if link_name == "__strcat_chk" {
+                    let dest_len = this.read_scalar(args[2])?.to_usize(this)?;
+                    check_fortify_len(link_name, dest_str_len + bytes.len() as u64, dest_len)?;
+                }
+                let end = dest_ptr.offset(Size::from_bytes(dest_str_len), this)?;
+                this.write_raw_bytes(end.into(), &bytes)?;
+                this.write_scalar(Scalar::Ptr(dest_ptr), dest)?;
+            }
+            "memcpy" | "__memcpy_chk" | "mempcpy" | "__mempcpy_chk" | "memmove" | "__memmove_chk" => {
+                let dest_ptr = this.read_scalar(args[0])?.not_undef()?;
+                let src = this.read_scalar(args[1])?.not_undef()?;
+                let n = this.read_scalar(args[2])?.to_usize(this)?;
+                if link_name.ends_with("_chk") {
+                    let dest_len = this.read_scalar(args[3])?.to_usize(this)?;
+                    check_fortify_len(link_name, n, dest_len)?;
+                }
+                let nonoverlapping = !link_name.contains("memmove");
+                if nonoverlapping && n > 0 {
+                    if let (Scalar::Ptr(src), Scalar::Ptr(dest)) = (src, dest_ptr) {
+                        let n = Size::from_bytes(n);
+                        if src.alloc_id == dest.alloc_id &&
+                            src.offset < dest.offset + n && dest.offset < src.offset + n
+                        {
+                            return err!(MachineError(
+                                format!("{} called with overlapping buffers", link_name),
+                            ));
+                        }
+                    }
+                }
+                let align = Align::from_bytes(1).unwrap();
+                this.memory_mut().copy(src, align, dest_ptr, align, Size::from_bytes(n), nonoverlapping)?;
+                if link_name.contains("mempcpy") {
+                    // Return a pointer to the end of the copied bytes.
+                    let end = dest_ptr.ptr_offset(Size::from_bytes(n), this)?;
+                    this.write_scalar(end, dest)?;
+                } else {
+                    this.write_scalar(dest_ptr, dest)?;
+                }
+            }
+            "memset" | "__memset_chk" => {
+                let dest_ptr = this.read_scalar(args[0])?.not_undef()?;
+                let val = this.read_scalar(args[1])?.to_i32()? as u8;
+                let n = Size::from_bytes(this.read_scalar(args[2])?.to_usize(this)?);
+                if link_name == "__memset_chk" {
+                    let dest_len = this.read_scalar(args[3])?.to_usize(this)?;
+                    check_fortify_len(link_name, n.bytes(), dest_len)?;
+                }
+                let align = Align::from_bytes(1).unwrap();
+                if let Some(ptr) = this.memory().check_ptr_access(dest_ptr, n, align)? {
+                    this.memory_mut().get_mut(ptr.alloc_id)?.write_repeat(tcx, ptr, val, n)?;
+                }
+                this.write_scalar(dest_ptr, dest)?;
+            }
+
+            // math functions
+
+            "abs" | "labs" | "llabs" | "imaxabs" => {
+                let n = this.read_scalar(args[0])?.to_bits(args[0].layout.size)?;
+                let n = this.sign_extend(n, args[0].layout) as i128;
+                // Like in C, the absolute value of the minimum value overflows back to it.
+                let abs = this.truncate(n.wrapping_abs() as u128, dest.layout);
+                this.write_scalar(Scalar::from_uint(abs, dest.layout.size), dest)?;
+            }
+            "div" | "ldiv" | "lldiv" | "imaxdiv" => {
+                let numer = this.read_scalar(args[0])?.to_bits(args[0].layout.size)?;
+                let numer = this.sign_extend(numer, args[0].layout) as i128;
+                let denom = this.read_scalar(args[1])?.to_bits(args[1].layout.size)?;
+                let denom = this.sign_extend(denom, args[1].layout) as i128;
+                // Both division by zero and the quotient of the minimum value and `-1`, which
+                // does not fit into the type, are reported as an overflowing division.
+                let min = -(1i128 << (args[0].layout.size.bits() - 1));
+                if denom == 0 || (numer == min && denom == -1) {
+                    return err!(Overflow(mir::BinOp::Div));
+                }
+                // The result is a struct with the fields `quot` and `rem`, in that order.
+                let quot_place = this.place_field(dest, 0)?;
+                let rem_place = this.place_field(dest, 1)?;
+                let quot = this.truncate((numer / denom) as u128, quot_place.layout);
+                let rem = this.truncate((numer % denom) as u128, rem_place.layout);
+                this.write_scalar(Scalar::from_uint(quot, quot_place.layout.size), quot_place)?;
+                this.write_scalar(Scalar::from_uint(rem, rem_place.layout.size), rem_place)?;
+            }
+            "fabsf" => {
+                let bits = this.read_scalar(args[0])?.to_u32()?;
+                this.write_scalar(Scalar::from_u32(bits & !(1 << 31)), dest)?;
+            }
+            "fabs" => {
+                let bits = this.read_scalar(args[0])?.to_u64()?;
+                this.write_scalar(Scalar::from_u64(bits & !(1 << 63)), dest)?;
+            }
+            "isnan" | "__isnan" | "isnanf" | "__isnanf" |
+            "isinf" | "__isinf" | "isinff" | "__isinff" |
+            "finite" | "__finite" | "finitef" | "__finitef" |
+            "fpclassify" | "__fpclassify" | "__fpclassifyf" => {
+                let f = match args[0].layout.size.bytes() {
+                    4 => f32::from_bits(this.read_scalar(args[0])?.to_u32()?) as f64,
+                    8 => f64::from_bits(this.read_scalar(args[0])?.to_u64()?),
+                    _ => return err!(Unimplemented(
+                        format!("{} is not supported for `long double` arguments", link_name),
+                    )),
+                };
+                let res = match link_name.trim_start_matches("__") {
+                    "isnan" | "isnanf" => f.is_nan() as i32,
+                    "isinf" | "isinff" => if f.is_infinite() { f.signum() as i32 } else { 0 },
+                    "finite" | "finitef" => f.is_finite() as i32,
+                    "fpclassify" | "fpclassifyf" => {
+                        use std::num::FpCategory::*;
+                        // The `FP_*` constants are macros, so they are not in the `libc` crate.
+                        let macos = this.tcx.sess.target.target.target_os.to_lowercase() == "macos";
+                        match (f.classify(), macos) {
+                            (Nan, false) => 0,
+                            (Infinite, false) => 1,
+                            (Zero, false) => 2,
+                            (Subnormal, false) => 3,
+                            (Normal, false) => 4,
+                            (Nan, true) => 1,
+                            (Infinite, true) => 2,
+                            (Zero, true) => 3,
+                            (Normal, true) => 4,
+                            (Subnormal, true) => 5,
+                        }
+                    }
+                    _ => bug!(),
+                };
+                this.write_scalar(Scalar::from_int(res, dest.layout.size), dest)?;
+            }
+            "copysignf" | "fdimf" => {
+                let f1 = f32::from_bits(this.read_scalar(args[0])?.to_u32()?);
+                let f2 = f32::from_bits(this.read_scalar(args[1])?.to_u32()?);
+                let n = match link_name {
+                    "copysignf" => f32::from_bits((f1.to_bits() & !(1 << 31)) | (f2.to_bits() & (1 << 31))),
+                    // NaN operands make the comparison fail, so they propagate.
+                    "fdimf" => if f1 <= f2 { 0.0 } else { f1 - f2 },
+                    _ => bug!(),
+                };
+                this.write_scalar(Scalar::from_u32(n.to_bits()), dest)?;
+            }
+            "copysign" | "fdim" => {
+                let f1 = f64::from_bits(this.read_scalar(args[0])?.to_u64()?);
+                let f2 = f64::from_bits(this.read_scalar(args[1])?.to_u64()?);
+                let n = match link_name {
+                    "copysign" => f64::from_bits((f1.to_bits() & !(1 << 63)) | (f2.to_bits() & (1 << 63))),
+                    // NaN operands make the comparison fail, so they propagate.
+                    "fdim" => if f1 <= f2 { 0.0 } else { f1 - f2 },
+                    _ => bug!(),
+                };
+                this.write_scalar(Scalar::from_u64(n.to_bits()), dest)?;
+            }
+            "floorf" | "ceilf" | "roundf" | "truncf" | "rintf" | "nearbyintf" => {
+                let f = f32::from_bits(this.read_scalar(args[0])?.to_u32()?);
+                let f = match link_name {
+                    "floorf" => f.floor(),
+                    "ceilf" => f.ceil(),
+                    "roundf" => f.round(),
+                    "truncf" => f.trunc(),
+                    // Every integer that is nearest to an `f32` is an `f32` itself.
+                    "rintf" | "nearbyintf" => round_ties_even(f as f64) as f32,
+                    _ => bug!(),
+                };
+                this.write_scalar(Scalar::from_u32(f.to_bits()), dest)?;
+            }
+            "floor" | "ceil" | "round" | "trunc" | "rint" | "nearbyint" => {
+                let f = f64::from_bits(this.read_scalar(args[0])?.to_u64()?);
+                let f = match link_name {
+                    "floor" => f.floor(),
+                    "ceil" => f.ceil(),
+                    "round" => f.round(),
+                    "trunc" => f.trunc(),
+                    "rint" | "nearbyint" => round_ties_even(f),
+                    _ => bug!(),
+                };
+                this.write_scalar(Scalar::from_u64(f.to_bits()), dest)?;
+            }
+            "lrint" | "llrint" | "lround" | "llround" |
+            "lrintf" | "llrintf" | "lroundf" | "llroundf" => {
+                // `f32` converts to `f64` exactly.
+                let f = if link_name.ends_with('f') {
+                    f32::from_bits(this.read_scalar(args[0])?.to_u32()?) as f64
+                } else {
+                    f64::from_bits(this.read_scalar(args[0])?.to_u64()?)
+                };
+                let f = if link_name.contains("rint") { round_ties_even(f) } else { f.round() };
+                // Like on x86, NaN and results that do not fit give the minimum value.
+                let min = -(1i128 << (dest.layout.size.bits() - 1));
+                let n = if f >= min as f64 && f < -(min as f64) { f as i128 } else { min };
+                this.write_scalar(Scalar::from_int(n, dest.layout.size), dest)?;
+            }
+            "fminf" | "fmaxf" => {
+                let f1 = f32::from_bits(this.read_scalar(args[0])?.to_u32()?);
+                let f2 = f32::from_bits(this.read_scalar(args[1])?.to_u32()?);
+                // A NaN operand is treated as missing data, so the other operand is returned.
+                let n = if f1.is_nan() {
+                    f2
+                } else if f2.is_nan() {
+                    f1
+                } else if (link_name == "fminf") == (f1 < f2) {
+                    f1
+                } else {
+                    f2
+                };
+                this.write_scalar(Scalar::from_u32(n.to_bits()), dest)?;
+            }
+            "fmin" | "fmax" => {
+                let f1 = f64::from_bits(this.read_scalar(args[0])?.to_u64()?);
+                let f2 = f64::from_bits(this.read_scalar(args[1])?.to_u64()?);
+                // A NaN operand is treated as missing data, so the other operand is returned.
+                let n = if f1.is_nan() {
+                    f2
+                } else if f2.is_nan() {
+                    f1
+                } else if (link_name == "fmin") == (f1 < f2) {
+                    f1
+                } else {
+                    f2
+                };
+                this.write_scalar(Scalar::from_u64(n.to_bits()), dest)?;
+            }
+
+            "cbrtf" | "coshf" | "sinhf" |"tanf" |
+            "sqrtf" | "expf" | "logf" | "log2f" | "log10f" | "log1pf" | "sinf" | "cosf" |
+            "j0f" | "j1f" | "y0f" | "y1f" | "erff" | "erfcf" => {
+                // FIXME: Using host floats.
+                let f = f32::from_bits(this.read_scalar(args[0])?.to_u32()?);
+                let f = match link_name {
+                    "cbrtf" => f.cbrt(),
+                    "coshf" => f.cosh(),
+                    "sinhf" => f.sinh(),
+                    "tanf" => f.tan(),
+                    "sqrtf" => f.sqrt(),
+                    "expf" => f.exp(),
+                    "logf" => f.ln(),
+                    "log2f" => f.log2(),
+                    "log10f" => f.log10(),
+                    "log1pf" => log1p(f as f64) as f32,
+                    "sinf" => f.sin(),
+                    "cosf" => f.cos(),
+                    // `libm` follows POSIX for the poles and domain errors of `y0`/`y1`.
+                    "j0f" => libm::j0f(f),
+                    "j1f" => libm::j1f(f),
+                    "y0f" => libm::y0f(f),
+                    "y1f" => libm::y1f(f),
+                    // These saturate to `1`/`-1` (resp. `0`/`2`) for large inputs.
+                    "erff" => libm::erff(f),
+                    "erfcf" => libm::erfcf(f),
+                    _ => bug!(),
+                };
+                this.write_scalar(Scalar::from_u32(f.to_bits()), dest)?;
+            }
+            // underscore case for windows
+            "_hypotf" | "hypotf" | "atan2f" | "fmodf" | "remainderf" | "dremf" | "powf" => {
+                // FIXME: Using host floats.
+                let f1 = f32::from_bits(this.read_scalar(args[0])?.to_u32()?);
+                let f2 = f32::from_bits(this.read_scalar(args[1])?.to_u32()?);
+                let n = match link_name {
+                    // In `f64`, neither the squares nor their sum can overflow.
+                    "_hypotf" | "hypotf" => hypot(f1 as f64, f2 as f64) as f32,
+                    "atan2f" => f1.atan2(f2),
+                    "powf" => f1.powf(f2),
+                    // Rust's `%` truncates just like `fmod`, including returning NaN for
+                    // a zero divisor or an infinite dividend.
+                    "fmodf" => f1 % f2,
+                    // `remainder` rounds the quotient to nearest instead.
+                    "remainderf" | "dremf" => libm::remainderf(f1, f2),
+                    _ => bug!(),
+                };
+                this.write_scalar(Scalar::from_u32(n.to_bits()), dest)?;
+            }
+            "nextafterf" | "nexttowardf" => {
+                let f1 = f32::from_bits(this.read_scalar(args[0])?.to_u32()?);
+                let f2 = this.read_next_toward_target(link_name, args[1])?;
+                let n = if f1.is_nan() || f2.is_nan() {
+                    std::f32::NAN
+                } else if f1 as f64 == f2 {
+                    f2 as f32
+                } else {
+                    next_after_f32(f1, f2 > f1 as f64)
+                };
+                this.write_scalar(Scalar::from_u32(n.to_bits()), dest)?;
+            }
+            "jnf" | "ynf" => {
+                // FIXME: Using host floats.
+                let n = this.read_scalar(args[0])?.to_i32()?;
+                let f = f32::from_bits(this.read_scalar(args[1])?.to_u32()?);
+                let f = match link_name {
+                    "jnf" => libm::jnf(n, f),
+                    "ynf" => libm::ynf(n, f),
+                    _ => bug!(),
+                };
+                this.write_scalar(Scalar::from_u32(f.to_bits()), dest)?;
+            }
+
+            "cbrt" | "cosh" | "sinh" | "tan" |
+            "sqrt" | "exp" | "log" | "log2" | "log10" | "log1p" | "sin" | "cos" |
+            "j0" | "j1" | "y0" | "y1" | "erf" | "erfc" => {
+                // FIXME: Using host floats.
+                let f = f64::from_bits(this.read_scalar(args[0])?.to_u64()?);
+                let f = match link_name {
+                    "cbrt" => f.cbrt(),
+                    "cosh" => f.cosh(),
+                    "sinh" => f.sinh(),
+                    "tan" => f.tan(),
+                    "sqrt" => f.sqrt(),
+                    "exp" => f.exp(),
+                    "log" => f.ln(),
+                    "log2" => f.log2(),
+                    "log10" => f.log10(),
+                    "log1p" => log1p(f),
+                    "sin" => f.sin(),
+                    "cos" => f.cos(),
+                    // `libm` follows POSIX for the poles and domain errors of `y0`/`y1`.
+                    "j0" => libm::j0(f),
+                    "j1" => libm::j1(f),
+                    "y0" => libm::y0(f),
+                    "y1" => libm::y1(f),
+                    // These saturate to `1`/`-1` (resp. `0`/`2`) for large inputs.
+                    "erf" => libm::erf(f),
+                    "erfc" => libm::erfc(f),
+                    _ => bug!(),
+                };
+                this.write_scalar(Scalar::from_u64(f.to_bits()), dest)?;
+            }
+            // underscore case for windows
+            "_hypot" | "hypot" | "atan2" | "fmod" | "remainder" | "drem" | "pow" => {
+                // FIXME: Using host floats.
+                let f1 = f64::from_bits(this.read_scalar(args[0])?.to_u64()?);
+                let f2 = f64::from_bits(this.read_scalar(args[1])?.to_u64()?);
+                let n = match link_name {
+                    "_hypot" | "hypot" => hypot(f1, f2),
+                    "atan2" => f1.atan2(f2),
+                    "pow" => f1.powf(f2),
+                    // Rust's `%` truncates just like `fmod`, including returning NaN for
+                    // a zero divisor or an infinite dividend.
+                    "fmod" => f1 % f2,
+                    // `remainder` rounds the quotient to nearest instead.
+                    "remainder" | "drem" => libm::remainder(f1, f2),
+                    _ => bug!(),
+                };
+                this.write_scalar(Scalar::from_u64(n.to_bits()), dest)?;
+            }
+            "nanf" | "nan" | "nanl" => {
+                let tagp = this.read_scalar(args[0])?.to_ptr()?;
+                let tag = this.memory().get(tagp.alloc_id)?.read_c_str(tcx, tagp)?;
+                // An empty or non-numeric tag gives the canonical quiet NaN.
+                let payload = parse_nan_payload(tag).unwrap_or(0);
+                match dest.layout.size.bytes() {
+                    4 => {
+                        let bits = 0x7fc0_0000 | (payload as u32 & 0x003f_ffff);
+                        this.write_scalar(Scalar::from_u32(bits), dest)?;
+                    }
+                    8 => {
+                        let bits = 0x7ff8_0000_0000_0000 | (payload & 0x0007_ffff_ffff_ffff);
+                        this.write_scalar(Scalar::from_u64(bits), dest)?;
+                    }
+                    _ => return err!(Unimplemented(
+                        format!("{} is not supported for `long double` results", link_name),
+                    )),
+                }
+            }
+            "nextafter" | "nexttoward" => {
+                let f1 = f64::from_bits(this.read_scalar(args[0])?.to_u64()?);
+                let f2 = this.read_next_toward_target(link_name, args[1])?;
+                let n = if f1.is_nan() || f2.is_nan() {
+                    std::f64::NAN
+                } else if f1 == f2 {
+                    f2
+                } else {
+                    next_after_f64(f1, f2 > f1)
+                };
+                this.write_scalar(Scalar::from_u64(n.to_bits()), dest)?;
+            }
+            "jn" | "yn" => {
+                // FIXME: Using host floats.
+                let n = this.read_scalar(args[0])?.to_i32()?;
+                let f = f64::from_bits(this.read_scalar(args[1])?.to_u64()?);
+                let f = match link_name {
+                    "jn" => libm::jn(n, f),
+                    "yn" => libm::yn(n, f),
+                    _ => bug!(),
+                };
+                this.write_scalar(Scalar::from_u64(f.to_bits()), dest)?;
+            }
+
+            "sincosf" | "__sincosf" => {
+                // FIXME: Using host floats.
+                let f = f32::from_bits(this.read_scalar(args[0])?.to_u32()?);
+                let sin_place = this.deref_operand(args[1])?;
+                let cos_place = this.deref_operand(args[2])?;
+                let (sin, cos) = f.sin_cos();
+                this.write_scalar(Scalar::from_u32(sin.to_bits()), sin_place.into())?;
+                this.write_scalar(Scalar::from_u32(cos.to_bits()), cos_place.into())?;
+            }
+            "sincos" | "__sincos" => {
+                // FIXME: Using host floats.
+                let f = f64::from_bits(this.read_scalar(args[0])?.to_u64()?);
+                let sin_place = this.deref_operand(args[1])?;
+                let cos_place = this.deref_operand(args[2])?;
+                let (sin, cos) = f.sin_cos();
+                this.write_scalar(Scalar::from_u64(sin.to_bits()), sin_place.into())?;
+                this.write_scalar(Scalar::from_u64(cos.to_bits()), cos_place.into())?;
+            }
+            "asinf" | "acosf" | "atanf" => {
+                // FIXME: Using host floats.
+                let f = f32::from_bits(this.read_scalar(args[0])?.to_u32()?);
+                if link_name != "atanf" && f.abs() > 1.0 {
+                    // The result is NaN, which the host gives us as well.
+                    this.set_errno("EDOM")?;
+                }
+                let f = match link_name {
+                    "asinf" => f.asin(),
+                    "acosf" => f.acos(),
+                    "atanf" => f.atan(),
+                    _ => bug!(),
+                };
+                this.write_scalar(Scalar::from_u32(f.to_bits()), dest)?;
+            }
+            "asin" | "acos" | "atan" => {
+                // FIXME: Using host floats.
+                let f = f64::from_bits(this.read_scalar(args[0])?.to_u64()?);
+                if link_name != "atan" && f.abs() > 1.0 {
+                    // The result is NaN, which the host gives us as well.
+                    this.set_errno("EDOM")?;
+                }
+                let f = match link_name {
+                    "asin" => f.asin(),
+                    "acos" => f.acos(),
+                    "atan" => f.atan(),
+                    _ => bug!(),
+                };
+                this.write_scalar(Scalar::from_u64(f.to_bits()), dest)?;
+            }
+
+            "frexpf" => {
+                // FIXME: Using host floats.
+                let f = f32::from_bits(this.read_scalar(args[0])?.to_u32()?);
+                let exp_place = this.deref_operand(args[1])?;
+                let (mantissa, exp) = libm::frexpf(f);
+                this.write_scalar(Scalar::from_int(exp, exp_place.layout.size), exp_place.into())?;
+                this.write_scalar(Scalar::from_u32(mantissa.to_bits()), dest)?;
+            }
+            "frexp" => {
+                // FIXME: Using host floats.
+                let f = f64::from_bits(this.read_scalar(args[0])?.to_u64()?);
+                let exp_place = this.deref_operand(args[1])?;
+                let (mantissa, exp) = libm::frexp(f);
+                this.write_scalar(Scalar::from_int(exp, exp_place.layout.size), exp_place.into())?;
+                this.write_scalar(Scalar::from_u64(mantissa.to_bits()), dest)?;
+            }
+            "ldexpf" => {
+                // FIXME: Using host floats.
+                let f = f32::from_bits(this.read_scalar(args[0])?.to_u32()?);
+                let exp = this.read_scalar(args[1])?.to_i32()?;
+                this.write_scalar(Scalar::from_u32(libm::ldexpf(f, exp).to_bits()), dest)?;
+            }
+            "ldexp" => {
+                // FIXME: Using host floats.
+                let f = f64::from_bits(this.read_scalar(args[0])?.to_u64()?);
+                let exp = this.read_scalar(args[1])?.to_i32()?;
+                this.write_scalar(Scalar::from_u64(libm::ldexp(f, exp).to_bits()), dest)?;
+            }
+            "modff" => {
+                // FIXME: Using host floats.
+                let f = f32::from_bits(this.read_scalar(args[0])?.to_u32()?);
+                let int_place = this.deref_operand(args[1])?;
+                let (frac, int) = libm::modff(f);
+                this.write_scalar(Scalar::from_u32(int.to_bits()), int_place.into())?;
+                this.write_scalar(Scalar::from_u32(frac.to_bits()), dest)?;
+            }
+            "modf" => {
+                // FIXME: Using host floats.
+                let f = f64::from_bits(this.read_scalar(args[0])?.to_u64()?);
+                let int_place = this.deref_operand(args[1])?;
+                let (frac, int) = libm::modf(f);
+                this.write_scalar(Scalar::from_u64(int.to_bits()), int_place.into())?;
+                this.write_scalar(Scalar::from_u64(frac.to_bits()), dest)?;
+            }
+            "scalbnf" | "scalblnf" => {
+                // FIXME: Using host floats.
+                let f = f32::from_bits(this.read_scalar(args[0])?.to_u32()?);
+                // `scalbln` takes a `long`, but clamping that to `i32` does not change the result.
+                let exp = this.read_scalar(args[1])?.to_bits(args[1].layout.size)?;
+                let exp = clamp_to_i32(this.sign_extend(exp, args[1].layout) as i128);
+                this.write_scalar(Scalar::from_u32(libm::scalbnf(f, exp).to_bits()), dest)?;
+            }
+            "scalbn" | "scalbln" => {
+                // FIXME: Using host floats.
+                let f = f64::from_bits(this.read_scalar(args[0])?.to_u64()?);
+                // `scalbln` takes a `long`, but clamping that to `i32` does not change the result.
+                let exp = this.read_scalar(args[1])?.to_bits(args[1].layout.size)?;
+                let exp = clamp_to_i32(this.sign_extend(exp, args[1].layout) as i128);
+                this.write_scalar(Scalar::from_u64(libm::scalbn(f, exp).to_bits()), dest)?;
+            }
+            "logbf" | "significandf" => {
+                // FIXME: Using host floats.
+                let f = f32::from_bits(this.read_scalar(args[0])?.to_u32()?);
+                let f = if f == 0.0 || !f.is_finite() {
+                    // `logb` has a pole at zero and returns `|x|` for infinities and NaN.
+                    // `significand` just returns these special values as they are.
+                    match link_name {
+                        "logbf" if f == 0.0 => std::f32::NEG_INFINITY,
+                        "logbf" => f.abs(),
+                        _ => f,
+                    }
+                } else {
+                    let exp = libm::ilogbf(f);
+                    match link_name {
+                        "logbf" => exp as f32,
+                        "significandf" => libm::scalbnf(f, -exp),
+                        _ => bug!(),
+                    }
+                };
+                this.write_scalar(Scalar::from_u32(f.to_bits()), dest)?;
+            }
+            "logb" | "significand" => {
+                // FIXME: Using host floats.
+                let f = f64::from_bits(this.read_scalar(args[0])?.to_u64()?);
+                let f = if f == 0.0 || !f.is_finite() {
+                    // `logb` has a pole at zero and returns `|x|` for infinities and NaN.
+                    // `significand` just returns these special values as they are.
+                    match link_name {
+                        "logb" if f == 0.0 => std::f64::NEG_INFINITY,
+                        "logb" => f.abs(),
+                        _ => f,
+                    }
+                } else {
+                    let exp = libm::ilogb(f);
+                    match link_name {
+                        "logb" => exp as f64,
+                        "significand" => libm::scalbn(f, -exp),
+                        _ => bug!(),
+                    }
+                };
+                this.write_scalar(Scalar::from_u64(f.to_bits()), dest)?;
+            }
+            "ilogbf" => {
+                // FIXME: Using host floats.
+                let f = f32::from_bits(this.read_scalar(args[0])?.to_u32()?);
+                // `libm` returns `FP_ILOGB0`/`FP_ILOGBNAN` (`i32::min_value()`) and `i32::max_value()`
+                // for the special values, just like glibc does.
+                this.write_scalar(Scalar::from_int(libm::ilogbf(f), dest.layout.size), dest)?;
+            }
+            "ilogb" => {
+                // FIXME: Using host floats.
+                let f = f64::from_bits(this.read_scalar(args[0])?.to_u64()?);
+                this.write_scalar(Scalar::from_int(libm::ilogb(f), dest.layout.size), dest)?;
+            }
+            "tgammaf" | "lgammaf" | "lgammaf_r" => {
+                // FIXME: Using host floats.
+                let f = f32::from_bits(this.read_scalar(args[0])?.to_u32()?);
+                // The non-positive integers are the poles of the gamma function.
+                let is_pole = f.is_finite() && f <= 0.0 && f == f.trunc();
+                let res = if link_name == "tgammaf" {
+                    if is_pole {
+                        // Zero is a pole error, the negative integers are a domain error.
+                        this.set_errno(if f == 0.0 { "ERANGE" } else { "EDOM" })?;
+                    }
+                    libm::tgammaf(f)
+                } else {
+                    if is_pole {
+                        this.set_errno("ERANGE")?;
+                    }
+                    let (res, sign) = libm::lgammaf_r(f);
+                    if link_name == "lgammaf_r" {
+                        let sign_place = this.deref_operand(args[1])?;
+                        this.write_scalar(Scalar::from_int(sign, sign_place.layout.size), sign_place.into())?;
+                    }
+                    res
+                };
+                this.write_scalar(Scalar::from_u32(res.to_bits()), dest)?;
+            }
+            "tgamma" | "lgamma" | "lgamma_r" => {
+                // FIXME: Using host floats.
+                let f = f64::from_bits(this.read_scalar(args[0])?.to_u64()?);
+                // The non-positive integers are the poles of the gamma function.
+                let is_pole = f.is_finite() && f <= 0.0 && f == f.trunc();
+                let res = if link_name == "tgamma" {
+                    if is_pole {
+                        // Zero is a pole error, the negative integers are a domain error.
+                        this.set_errno(if f == 0.0 { "ERANGE" } else { "EDOM" })?;
+                    }
+                    libm::tgamma(f)
+                } else {
+                    if is_pole {
+                        this.set_errno("ERANGE")?;
+                    }
+                    let (res, sign) = libm::lgamma_r(f);
+                    if link_name == "lgamma_r" {
+                        let sign_place = this.deref_operand(args[1])?;
+                        this.write_scalar(Scalar::from_int(sign, sign_place.layout.size), sign_place.into())?;
+                    }
+                    res
+                };
+                this.write_scalar(Scalar::from_u64(res.to_bits()), dest)?;
+            }
+
+            // Floating-point environment.
+            "fegetround" => {
+                this.write_scalar(Scalar::from_int(this.machine.fenv, dest.layout.size), dest)?;
+            }
+            "fesetround" => {
+                let round = this.read_scalar(args[0])?.to_i32()?;
+                this.set_rounding_mode(round);
+                // Return success (`0`).
+                this.write_null(dest)?;
+            }
+            "fegetenv" => {
+                // We only save the rounding mode, in the first 4 bytes of the `fenv_t`.
+                let env_ptr = this.read_scalar(args[0])?.not_undef()?;
+                let int_size = Size::from_bits(32);
+                let env_ptr = this.memory().check_ptr_access(env_ptr, int_size, Align::from_bytes(4).unwrap())?
+                    .expect("cannot be a ZST");
+                let round = Scalar::from_int(this.machine.fenv, int_size);
+                this.memory_mut().get_mut(env_ptr.alloc_id)?
+                    .write_scalar(tcx, env_ptr, round.into(), int_size)?;
+                // Return success (`0`).
+                this.write_null(dest)?;
+            }
+            "fesetenv" => {
+                let env_ptr = this.read_scalar(args[0])?.not_undef()?;
+                let round = if let Scalar::Ptr(_) = env_ptr {
+                    let int_size = Size::from_bits(32);
+                    let env_ptr = this.memory().check_ptr_access(env_ptr, int_size, Align::from_bytes(4).unwrap())?
+                        .expect("cannot be a ZST");
+                    this.memory().get(env_ptr.alloc_id)?
+                        .read_scalar(tcx, env_ptr, int_size)?
+                        .to_i32()?
+                } else {
+                    // An integer like `FE_DFL_ENV` (`(fenv_t *) -1` on glibc): the default environment.
+                    FE_TONEAREST
+                };
+                this.set_rounding_mode(round);
+                // Return success (`0`).
+                this.write_null(dest)?;
+            }
+
+            // Some things needed for `sys::thread` initialization to go through.
+            "signal" | "sigaction" | "sigaltstack" => {
+                this.write_scalar(Scalar::from_int(0, dest.layout.size), dest)?;
+            }
+
+            "sysconf" => {
+                let name = this.read_scalar(args[0])?.to_i32()?;
+
+                trace!("sysconf() called with name {}", name);
+                // TODO: Cache the sysconf integers via Miri's global cache.
+                let paths = &[
+                    (&["libc", "_SC_PAGESIZE"], Scalar::from_int(PAGE_SIZE, dest.layout.size)),
+                    (&["libc", "_SC_GETPW_R_SIZE_MAX"], Scalar::from_int(-1, dest.layout.size)),
+                    (&["libc", "_SC_NPROCESSORS_ONLN"], Scalar::from_int(NUM_CPUS, dest.layout.size)),
+                ];
+                let mut result = None;
+                for &(path, path_value) in paths {
+                    if let Some(val) = this.eval_path_scalar(path)? {
+                        let val = val.to_i32()?;
+                        if val == name {
+                            result = Some(path_value);
+                            break;
+                        }
+
+                    }
+                }
+                if let Some(result) = result {
+                    this.write_scalar(result, dest)?;
+                } else {
+                    return err!(Unimplemented(
+                        format!("Unimplemented sysconf name: {}", name),
+                    ));
+                }
+            }
+
+            "confstr" => {
+                let name = this.read_scalar(args[0])?.to_i32()?;
+                let buf = this.read_scalar(args[1])?.not_undef()?;
+                let len = this.read_scalar(args[2])?.to_usize(this)?;
+
+                // We pretend to be a recent glibc, like `gnu_get_libc_version`.
+                let paths: &[(&[&str], &[u8])] = &[
+                    (&["libc", "_CS_PATH"], b"/bin:/usr/bin"),
+                    (&["libc", "_CS_GNU_LIBC_VERSION"], b"glibc 2.31"),
+                    (&["libc", "_CS_GNU_LIBPTHREAD_VERSION"], b"NPTL 2.31"),
+                ];
+                let mut result = None;
+                for &(path, path_value) in paths {
+                    if let Some(val) = this.eval_path_scalar(path)? {
+                        if val.to_i32()? == name {
+                            result = Some(path_value);
+                            break;
+                        }
+                    }
+                }
+                let value = match result {
+                    Some(value) => value,
+                    None => return err!(Unimplemented(
+                        format!("Unimplemented confstr name: {}", name),
+                    )),
+                };
+                // Copy as much as fits, always with a null terminator.
+                if len > 0 && !buf.is_null_ptr(this) {
+                    let buf = buf.to_ptr()?;
+                    let mut bytes = value[..value.len().min(len as usize - 1)].to_vec();
+                    bytes.push(0);
+                    this.memory_mut().get_mut(buf.alloc_id)?.write_bytes(tcx, buf, &bytes)?;
+                }
+                // The size of the buffer that fits the entire value.
+                this.write_scalar(Scalar::from_uint(value.len() as u64 + 1, dest.layout.size), dest)?;
+            }
+            "gnu_get_libc_version" => {
+                // The string is static in glibc, so every call returns the same pointer.
+                let ptr = match this.machine.libc_version {
+                    Some(ptr) => ptr,
+                    None => {
+                        let ptr = this.memory_mut().allocate_static_bytes(b"2.31\0", MiriMemoryKind::Static.into());
+                        this.machine.libc_version = Some(ptr);
+                        ptr
+                    }
+                };
+                this.write_scalar(Scalar::Ptr(ptr), dest)?;
+            }
+
+            "sched_getaffinity" => {
+                // Return an error; `num_cpus` then falls back to `sysconf`.
+                this.write_scalar(Scalar::from_int(-1, dest.layout.size), dest)?;
+            }
+
+            "isatty" => {
+                this.write_null(dest)?;
+            }
+
             // Hook pthread calls that go to the thread-local storage memory subsystem.
             "pthread_key_create" => {
                 let key_ptr = this.read_scalar(args[0])?.not_undef()?;
@@ -682,6 +2249,7 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
 
                 // Create key and write it into the memory where `key_ptr` wants it.
                 let key = this.machine.tls.create_tls_key(dtor, tcx) as u128;
+                this.log_event(Event::TlsCreate { key });
                 if key_layout.size.bits() < 128 && key >= (1u128 << key_layout.size.bits() as u128) {
                     return err!(OutOfTls);
                 }
@@ -701,6 +2269,7 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
             "pthread_key_delete" => {
                 let key = this.read_scalar(args[0])?.to_bits(args[0].layout.size)?;
                 this.machine.tls.delete_tls_key(key)?;
+                this.log_event(Event::TlsDelete { key });
                 // Return success (0)
                 this.write_null(dest)?;
             }
@@ -732,20 +2301,172 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
                 // Return success (`0`).
                 this.write_null(dest)?;
             }
-            "pthread_get_stackaddr_np" => {
-                // Just any address.
-                let stack_addr = Scalar::from_uint(STACK_ADDR, dest.layout.size);
-                this.write_scalar(stack_addr, dest)?;
+            "pthread_get_stackaddr_np" => {
+                // Just any address.
+                let stack_addr = Scalar::from_uint(STACK_ADDR, dest.layout.size);
+                this.write_scalar(stack_addr, dest)?;
+            }
+
+            // Stub out calls for condvar, mutex and rwlock, to just return `0`.
+            "pthread_mutexattr_init" | "pthread_mutexattr_settype" | "pthread_mutex_init" |
+            "pthread_mutexattr_destroy" | "pthread_mutex_lock" | "pthread_mutex_unlock" |
+            "pthread_mutex_destroy" | "pthread_condattr_init" |
+            "pthread_condattr_setclock" | "pthread_cond_init" | "pthread_condattr_destroy" |
+            "pthread_cond_destroy" => {
+                this.write_null(dest)?;
+            }
+
+            // Reader-writer locks. With only one thread, a lock that would block can never be
+            // acquired, so the blocking functions report a deadlock.
+            "pthread_rwlock_init" | "pthread_rwlock_destroy" | "pthread_rwlock_rdlock" |
+            "pthread_rwlock_tryrdlock" | "pthread_rwlock_wrlock" | "pthread_rwlock_trywrlock" |
+            "pthread_rwlock_unlock" => {
+                let lock = this.read_scalar(args[0])?.not_undef()?;
+                let lock = this.force_ptr(lock)?.erase_tag();
+                let state = this.machine.rwlocks.get(&lock).copied().unwrap_or_default();
+                let blocked = if link_name.ends_with("rdlock") {
+                    state.writer
+                } else {
+                    state.writer || state.readers > 0
+                };
+                let result = match link_name {
+                    "pthread_rwlock_init" => {
+                        this.machine.rwlocks.remove(&lock);
+                        None
+                    }
+                    "pthread_rwlock_destroy" => {
+                        if blocked {
+                            Some("EBUSY")
+                        } else {
+                            this.machine.rwlocks.remove(&lock);
+                            None
+                        }
+                    }
+                    "pthread_rwlock_unlock" => {
+                        let state = this.machine.rwlocks.entry(lock).or_default();
+                        if state.writer {
+                            state.writer = false;
+                            None
+                        } else if state.readers > 0 {
+                            state.readers -= 1;
+                            None
+                        } else {
+                            // The lock is not held.
+                            Some("EPERM")
+                        }
+                    }
+                    _ if blocked => {
+                        Some(if link_name.contains("try") { "EBUSY" } else { "EDEADLK" })
+                    }
+                    _ => {
+                        let state = this.machine.rwlocks.entry(lock).or_default();
+                        if link_name.ends_with("rdlock") {
+                            state.readers += 1;
+                        } else {
+                            state.writer = true;
+                        }
+                        None
+                    }
+                };
+                match result {
+                    Some(error) => {
+                        let error = this.eval_libc_i32(error)?;
+                        this.write_scalar(Scalar::from_int(error, dest.layout.size), dest)?;
+                    }
+                    None => this.write_null(dest)?,
+                }
+            }
+
+            // glibc's macros call the functions with the leading underscore, with a buffer for
+            // the handler as the first argument. We keep the handlers ourselves.
+            "pthread_cleanup_push" | "_pthread_cleanup_push" => {
+                let routine = this.read_scalar(args[args.len() - 2])?.to_ptr()?;
+                let routine = this.memory().get_fn(routine)?;
+                let arg = this.read_scalar(args[args.len() - 1])?.not_undef()?;
+                this.machine.cleanup_handlers.push((routine, arg));
+            }
+            "pthread_cleanup_pop" | "_pthread_cleanup_pop" => {
+                let execute = this.read_scalar(args[args.len() - 1])?.to_i32()?;
+                let (routine, arg) = match this.machine.cleanup_handlers.pop() {
+                    Some(handler) => handler,
+                    None => return err!(MachineError(format!(
+                        "{} called without a matching pthread_cleanup_push", link_name,
+                    ))),
+                };
+                if execute != 0 {
+                    let mir = this.load_mir(routine.def)?;
+                    let ret_place = MPlaceTy::dangling(this.layout_of(this.tcx.mk_unit())?, this).into();
+                    this.push_stack_frame(
+                        routine,
+                        mir.span,
+                        mir,
+                        Some(ret_place),
+                        // Directly return to caller.
+                        StackPopCleanup::Goto(Some(ret)),
+                    )?;
+                    let arg_local = this.frame().body.args_iter().next().ok_or_else(
+                        || InterpError::AbiViolation("cleanup handler does not take enough arguments.".to_owned()),
+                    )?;
+                    let arg_dest = this.eval_place(&mir::Place::Base(mir::PlaceBase::Local(arg_local)))?;
+                    this.write_scalar(arg, arg_dest)?;
+                    // Don't fall through, we do *not* want to `goto_block`!
+                    return Ok(());
+                }
             }
 
-            // Stub out calls for condvar, mutex and rwlock, to just return `0`.
-            "pthread_mutexattr_init" | "pthread_mutexattr_settype" | "pthread_mutex_init" |
-            "pthread_mutexattr_destroy" | "pthread_mutex_lock" | "pthread_mutex_unlock" |
-            "pthread_mutex_destroy" | "pthread_rwlock_rdlock" | "pthread_rwlock_unlock" |
-            "pthread_rwlock_wrlock" | "pthread_rwlock_destroy" | "pthread_condattr_init" |
-            "pthread_condattr_setclock" | "pthread_cond_init" | "pthread_condattr_destroy" |
-            "pthread_cond_destroy" => {
-                this.write_null(dest)?;
+            "pthread_cancel" => {
+                // `pthread_self` gives `0`, and there are no other threads.
+                let thread = this.read_scalar(args[0])?.to_bits(args[0].layout.size)?;
+                if thread == 0 {
+                    this.machine.cancel_pending = true;
+                    this.write_null(dest)?;
+                } else {
+                    let esrch = this.eval_libc_i32("ESRCH")?;
+                    this.write_scalar(Scalar::from_int(esrch, dest.layout.size), dest)?;
+                }
+            }
+            "pthread_setcancelstate" | "pthread_setcanceltype" => {
+                // We only remember whether the state or type differs from the default, which
+                // is enabled and deferred, respectively.
+                let (non_default, default) = if link_name == "pthread_setcancelstate" {
+                    ("PTHREAD_CANCEL_DISABLE", "PTHREAD_CANCEL_ENABLE")
+                } else {
+                    ("PTHREAD_CANCEL_ASYNCHRONOUS", "PTHREAD_CANCEL_DEFERRED")
+                };
+                let non_default = this.eval_libc_i32(non_default)?;
+                let default = this.eval_libc_i32(default)?;
+                let new = this.read_scalar(args[0])?.to_i32()?;
+                if new != non_default && new != default {
+                    let einval = this.eval_libc_i32("EINVAL")?;
+                    this.write_scalar(Scalar::from_int(einval, dest.layout.size), dest)?;
+                } else {
+                    let new = new == non_default;
+                    let old = if link_name == "pthread_setcancelstate" {
+                        std::mem::replace(&mut this.machine.cancel_disabled, new)
+                    } else {
+                        std::mem::replace(&mut this.machine.cancel_asynchronous, new)
+                    };
+                    let old_ptr = this.read_scalar(args[1])?.not_undef()?;
+                    if !old_ptr.is_null_ptr(this) {
+                        let old = if old { non_default } else { default };
+                        let old_place = this.deref_operand(args[1])?;
+                        this.write_scalar(Scalar::from_int(old, old_place.layout.size), old_place.into())?;
+                    }
+                    this.write_null(dest)?;
+                }
+            }
+            "pthread_testcancel" => {
+                // A pending cancellation is only acted upon here, even for asynchronous
+                // cancelability.
+                if this.machine.cancel_pending && !this.machine.cancel_disabled {
+                    // Cancellation acts like `pthread_exit`: the cleanup handlers run one at a
+                    // time, each returning to this call, which is then executed again. Once
+                    // they are done, the cancelled main thread ends the process.
+                    if this.run_cleanup_handler()? {
+                        return Ok(());
+                    }
+                    return err!(Exit(0));
+                }
             }
 
             // We don't support fork so we don't have to do anything for atfork.
@@ -754,17 +2475,185 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
             }
 
             "mmap" => {
-                // This is a horrible hack, but since the guard page mechanism calls mmap and expects a particular return value, we just give it that value.
                 let addr = this.read_scalar(args[0])?.not_undef()?;
-                this.write_scalar(addr, dest)?;
+                let length = this.read_scalar(args[1])?.to_usize(this)?;
+                let prot = this.read_scalar(args[2])?.to_i32()?;
+                let flags = this.read_scalar(args[3])?.to_i32()?;
+                // We only support anonymous mappings, so the file descriptor and offset do not matter.
+                let prot_none = this.eval_libc_i32("PROT_NONE")?;
+                let prot_all = this.eval_libc_i32("PROT_READ")?
+                    | this.eval_libc_i32("PROT_WRITE")?
+                    | this.eval_libc_i32("PROT_EXEC")?;
+                let map_private = this.eval_libc_i32("MAP_PRIVATE")?;
+                let map_shared = this.eval_libc_i32("MAP_SHARED")?;
+                let map_anonymous = this.eval_libc_i32("MAP_ANONYMOUS")?;
+                let map_fixed = this.eval_libc_i32("MAP_FIXED")?;
+                let mut supported_flags = map_private | map_shared | map_anonymous | map_fixed;
+                if this.tcx.sess.target.target.target_os.to_lowercase() == "linux" {
+                    // We cannot do anything useful with these, and do not have to.
+                    supported_flags |= this.eval_libc_i32("MAP_POPULATE")?
+                        | this.eval_libc_i32("MAP_HUGETLB")?;
+                }
+                let map_failed = Scalar::from_int(-1, dest.layout.size);
+
+                if flags & map_anonymous == 0 {
+                    // We have no files to map.
+                    this.set_errno("ENODEV")?;
+                    this.write_scalar(map_failed, dest)?;
+                } else if length == 0
+                    || prot & !prot_all != 0
+                    || flags & !supported_flags != 0
+                    || (flags & map_private == 0) == (flags & map_shared == 0)
+                {
+                    this.set_errno("EINVAL")?;
+                    this.write_scalar(map_failed, dest)?;
+                } else if flags & map_fixed != 0 {
+                    match addr {
+                        Scalar::Ptr(ptr) => {
+                            // We cannot create memory at a given place, but we can replace the
+                            // contents of existing memory with fresh zero pages.
+                            let size = Size::from_bytes(length);
+                            let in_bounds = this.memory()
+                                .check_ptr_access(addr, size, Align::from_bytes(1).unwrap())
+                                .is_ok();
+                            if in_bounds {
+                                this.memory_mut()
+                                    .get_mut(ptr.alloc_id)?
+                                    .write_repeat(tcx, ptr, 0, size)?;
+                                this.write_scalar(addr, dest)?;
+                            } else {
+                                this.set_errno("EINVAL")?;
+                                this.write_scalar(map_failed, dest)?;
+                            }
+                        }
+                        Scalar::Raw { .. } => {
+                            // This is a horrible hack, but since the guard page mechanism calls mmap
+                            // with the fake stack address and expects a particular return value, we
+                            // just give it that value.
+                            this.write_scalar(addr, dest)?;
+                        }
+                    }
+                } else {
+                    let size = Size::from_bytes((length + PAGE_SIZE - 1) / PAGE_SIZE * PAGE_SIZE);
+                    let ptr = this.memory_mut()
+                        .allocate(size, Align::from_bytes(PAGE_SIZE).unwrap(), MiriMemoryKind::Mmap.into());
+                    let alloc = this.memory_mut().get_mut(ptr.alloc_id)?;
+                    alloc.write_repeat(tcx, ptr, 0, size)?;
+                    alloc.extra.inaccessible = prot == prot_none;
+                    this.write_scalar(Scalar::Ptr(ptr), dest)?;
+                }
+            }
+            "munmap" => {
+                let ptr = this.read_scalar(args[0])?.to_ptr()?;
+                let length = this.read_scalar(args[1])?.to_usize(this)?;
+                let (size, _) = this.memory().get_size_and_align(ptr.alloc_id, AllocCheck::Live)?;
+                // Mappings are whole pages.
+                if ptr.offset != Size::ZERO || (length + PAGE_SIZE - 1) / PAGE_SIZE * PAGE_SIZE < size.bytes() {
+                    return err!(Unimplemented("munmap of a part of a mapping".to_string()));
+                }
+                this.memory_mut().deallocate(ptr, None, MiriMemoryKind::Mmap.into())?;
+                this.write_null(dest)?;
             }
             "mprotect" => {
+                let addr = this.read_scalar(args[0])?.not_undef()?;
+                let length = this.read_scalar(args[1])?.to_usize(this)?;
+                let prot = this.read_scalar(args[2])?.to_i32()?;
+                let prot_none = this.eval_libc_i32("PROT_NONE")?;
+                // The guard page mechanism protects the page below the fake stack, which is no
+                // memory of ours, see `mmap`.
+                if let Scalar::Ptr(ptr) = addr {
+                    let alloc = this.memory_mut().get_mut(ptr.alloc_id)?;
+                    if alloc.extra.kind == MiriMemoryKind::Mmap.into() {
+                        // We only track the protection of entire mappings, so protecting a part
+                        // of one has no effect, and unprotecting a part unprotects all of it.
+                        let whole = ptr.offset == Size::ZERO
+                            && (length + PAGE_SIZE - 1) / PAGE_SIZE * PAGE_SIZE >= alloc.bytes.len() as u64;
+                        if whole || prot != prot_none {
+                            alloc.extra.inaccessible = prot == prot_none;
+                        }
+                    }
+                }
+                this.write_null(dest)?;
+            }
+            "at_quick_exit" => {
+                let handler = this.read_scalar(args[0])?.to_ptr()?;
+                let handler = this.memory().get_fn(handler)?;
+                if this.load_mir(handler.def)?.arg_count != 0 {
+                    return err!(AbiViolation(
+                        "the function passed to `at_quick_exit` must not take arguments".to_string(),
+                    ));
+                }
+                this.machine.at_quick_exit_handlers.push(handler);
                 this.write_null(dest)?;
             }
+            "__libc_start_main" => {
+                let main = this.read_scalar(args[0])?.not_undef()?;
+                let argc = this.read_scalar(args[1])?.not_undef()?;
+                let argv = this.read_scalar(args[2])?.not_undef()?;
+                let init = this.read_scalar(args[3])?.not_undef()?;
+                let fini = this.read_scalar(args[4])?.not_undef()?;
+                let rtld_fini = this.read_scalar(args[5])?.not_undef()?;
+                // Like glibc, we pass the env vars that follow `argv` to `init` and `main`.
+                let envp = argv.ptr_wrapping_offset(
+                    this.memory().pointer_size() * (argc.to_i32()? as u64 + 1),
+                    this,
+                );
+                let (mut called, exit_code) = match this.machine.libc_start_main.take() {
+                    Some(state) => state,
+                    None => {
+                        let layout = this.layout_of(this.tcx.types.i32)?;
+                        (0, this.allocate(layout, MiriMemoryKind::C.into()))
+                    }
+                };
+                // The functions run one at a time: each returns to this call, which is then
+                // executed again to call the next one. Once they are done, we return the exit
+                // code of `main`.
+                loop {
+                    let (func, func_args, ret_place) = match called {
+                        0 => (init, vec![argc, argv, envp], None),
+                        1 => (main, vec![argc, argv, envp], Some(exit_code)),
+                        2 => (fini, vec![], None),
+                        3 => (rtld_fini, vec![], None),
+                        _ => break,
+                    };
+                    called += 1;
+                    // Only `main` is required.
+                    if ret_place.is_none() && func.is_null_ptr(this) {
+                        continue;
+                    }
+                    let func = this.memory().get_fn(func.to_ptr()?)?;
+                    let mir = this.load_mir(func.def)?;
+                    let ret_place = match ret_place {
+                        Some(place) => place.into(),
+                        None => MPlaceTy::dangling(this.layout_of(this.tcx.mk_unit())?, this).into(),
+                    };
+                    this.push_stack_frame(
+                        func,
+                        mir.span,
+                        mir,
+                        Some(ret_place),
+                        StackPopCleanup::None { cleanup: true },
+                    )?;
+                    // C functions may ignore their arguments.
+                    let arg_locals: Vec<mir::Local> = this.frame().body.args_iter().collect();
+                    for (arg_local, arg) in arg_locals.into_iter().zip(func_args) {
+                        let arg_dest = this.eval_place(&mir::Place::Base(mir::PlaceBase::Local(arg_local)))?;
+                        this.write_scalar(arg, arg_dest)?;
+                    }
+                    this.machine.libc_start_main = Some((called, exit_code));
+                    return Ok(());
+                }
+                let code = this.read_scalar(exit_code.into())?.not_undef()?;
+                this.memory_mut().deallocate(exit_code.to_ptr()?, None, MiriMemoryKind::C.into())?;
+                this.write_scalar(code, dest)?;
+            }
 
             // macOS API stubs.
             "_tlv_atexit" => {
-                // FIXME: register the destructor.
+                let dtor = this.read_scalar(args[0])?.to_ptr()?;
+                let dtor = this.memory().get_fn(dtor)?;
+                let data = this.read_scalar(args[1])?.not_undef()?;
+                this.machine.tls.macos_thread_dtors.push((dtor, data));
             },
             "_NSGetArgc" => {
                 this.write_scalar(Scalar::Ptr(this.machine.argc.unwrap()), dest)?;
@@ -772,6 +2661,118 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
             "_NSGetArgv" => {
                 this.write_scalar(Scalar::Ptr(this.machine.argv.unwrap()), dest)?;
             },
+            "task_self_trap" | "mach_task_self" => {
+                this.write_scalar(Scalar::from_u32(MACH_TASK_SELF), dest)?;
+            },
+            "mach_thread_self" => {
+                this.write_scalar(Scalar::from_u32(MACH_THREAD_SELF), dest)?;
+            },
+            "mach_port_deallocate" => {
+                // Our ports are not reference counted. Return `KERN_SUCCESS`.
+                this.write_null(dest)?;
+            },
+            // `libkern/OSAtomic.h`. With only one thread, the barrier variants are no different.
+            "OSAtomicAdd32" | "OSAtomicAdd32Barrier" | "OSAtomicAdd64" | "OSAtomicAdd64Barrier" |
+            "OSAtomicAnd32" | "OSAtomicAnd32Barrier" | "OSAtomicOr32" | "OSAtomicOr32Barrier" |
+            "OSAtomicXor32" | "OSAtomicXor32Barrier" => {
+                let operand = this.read_scalar(args[0])?.not_undef()?;
+                let place = this.deref_operand(args[1])?;
+                let old = this.read_scalar(place.into())?.not_undef()?;
+                let size = place.layout.size;
+                let (old, operand) = (old.to_bits(size)?, operand.to_bits(size)?);
+                let new = match link_name.trim_end_matches("Barrier") {
+                    "OSAtomicAdd32" | "OSAtomicAdd64" => old.wrapping_add(operand),
+                    "OSAtomicAnd32" => old & operand,
+                    "OSAtomicOr32" => old | operand,
+                    "OSAtomicXor32" => old ^ operand,
+                    _ => bug!(),
+                };
+                let new = Scalar::from_uint(this.truncate(new, place.layout), size);
+                this.write_scalar(new, place.into())?;
+                // These return the new value.
+                this.write_scalar(new, dest)?;
+            },
+            "OSAtomicIncrement32" | "OSAtomicIncrement32Barrier" |
+            "OSAtomicDecrement32" | "OSAtomicDecrement32Barrier" => {
+                let place = this.deref_operand(args[0])?;
+                let old = this.read_scalar(place.into())?.to_i32()?;
+                let new = if link_name.starts_with("OSAtomicIncrement") {
+                    old.wrapping_add(1)
+                } else {
+                    old.wrapping_sub(1)
+                };
+                let new = Scalar::from_int(new, place.layout.size);
+                this.write_scalar(new, place.into())?;
+                this.write_scalar(new, dest)?;
+            },
+            "OSAtomicCompareAndSwap32" | "OSAtomicCompareAndSwap32Barrier" |
+            "OSAtomicCompareAndSwap64" | "OSAtomicCompareAndSwap64Barrier" => {
+                let expected = this.read_scalar(args[0])?.not_undef()?;
+                let new = this.read_scalar(args[1])?.not_undef()?;
+                let place = this.deref_operand(args[2])?;
+                let size = place.layout.size;
+                let old = this.read_scalar(place.into())?.to_bits(size)?;
+                let swapped = old == expected.to_bits(size)?;
+                if swapped {
+                    this.write_scalar(new, place.into())?;
+                }
+                this.write_scalar(Scalar::from_bool(swapped), dest)?;
+            },
+            // CoreFoundation objects are never freed, so reference counting is a no-op.
+            "CFRetain" => {
+                let cf = this.read_scalar(args[0])?.not_undef()?;
+                this.write_scalar(cf, dest)?;
+            },
+            "CFRelease" | "CFRunLoopStop" => {
+                // Nothing to do.
+            },
+            "CFRunLoopRun" => {
+                return err!(MachineError("CFRunLoop not supported in miri".to_string()));
+            },
+            // We pretend the program is the only image dyld loaded.
+            "_dyld_image_count" => {
+                this.write_scalar(Scalar::from_u32(1), dest)?;
+            },
+            "_dyld_get_image_name" | "_dyld_get_image_header" => {
+                let idx = this.read_scalar(args[0])?.to_u32()?;
+                if idx != 0 {
+                    this.write_null(dest)?;
+                } else {
+                    let bytes = if link_name == "_dyld_get_image_name" {
+                        b"miri_program\0".to_vec()
+                    } else {
+                        fake_mach_header(&this.tcx.sess.target.target.arch, this.tcx.data_layout.endian)
+                    };
+                    let ptr = this.memory_mut().allocate_static_bytes(&bytes, MiriMemoryKind::Static.into());
+                    this.write_scalar(Scalar::Ptr(ptr), dest)?;
+                }
+            },
+            "arc4random" => {
+                let random = random_u32(this)?;
+                this.write_scalar(Scalar::from_u32(random), dest)?;
+            }
+            "arc4random_buf" => {
+                let ptr = this.read_scalar(args[0])?.not_undef()?;
+                let len = this.read_scalar(args[1])?.to_usize(this)?;
+                gen_random(this, len as usize, ptr)?;
+            }
+            "arc4random_uniform" => {
+                let upper_bound = this.read_scalar(args[0])?.to_u32()?;
+                let random = if upper_bound < 2 {
+                    0
+                } else {
+                    // Reject the values below `2^32 % upper_bound`, so that every remainder
+                    // is equally likely.
+                    let min = upper_bound.wrapping_neg() % upper_bound;
+                    loop {
+                        let random = random_u32(this)?;
+                        if random >= min {
+                            break random % upper_bound;
+                        }
+                    }
+                };
+                this.write_scalar(Scalar::from_u32(random), dest)?;
+            }
             "SecRandomCopyBytes" => {
                 let len = this.read_scalar(args[1])?.to_usize(this)?;
                 let ptr = this.read_scalar(args[2])?.not_undef()?;
@@ -812,11 +2813,80 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
             }
 
             "SetLastError" => {
-                let err = this.read_scalar(args[0])?.to_u32()?;
-                this.machine.last_error = err;
+                let err = this.read_scalar(args[0])?.not_undef()?;
+                this.set_last_error(err)?;
             }
             "GetLastError" => {
-                this.write_scalar(Scalar::from_u32(this.machine.last_error), dest)?;
+                let last_error = this.get_last_error()?;
+                this.write_scalar(last_error, dest)?;
+            }
+
+            "RtlCaptureStackBackTrace" => {
+                let skip = this.read_scalar(args[0])?.to_u32()?;
+                let capture = this.read_scalar(args[1])?.to_u32()?;
+                let hash_ptr = this.read_scalar(args[3])?.not_undef()?;
+                // There are no real return addresses, so we make up one per interpreter frame,
+                // innermost first.
+                let frames: Vec<Scalar<Tag>> = this.stack().iter().rev()
+                    .skip(skip as usize)
+                    .take(capture as usize)
+                    .map(|frame| fake_frame_address(this, frame.extra, true))
+                    .collect();
+                if !frames.is_empty() {
+                    let back_trace = this.deref_operand(args[2])?;
+                    let ptr_layout = back_trace.layout;
+                    let mut hash = 0u32;
+                    for (i, &frame) in frames.iter().enumerate() {
+                        let offset = ptr_layout.size * i as u64;
+                        let place = back_trace.offset(offset, None, ptr_layout, this)?;
+                        this.write_scalar(frame, place.into())?;
+                        hash = hash.rotate_left(5) ^ this.force_bits(frame, ptr_layout.size)? as u32;
+                    }
+                    if !hash_ptr.is_null_ptr(this) {
+                        let hash_place = this.deref_operand(args[3])?;
+                        this.write_scalar(Scalar::from_u32(hash), hash_place.into())?;
+                    }
+                }
+                this.write_scalar(Scalar::from_uint(frames.len() as u64, dest.layout.size), dest)?;
+            }
+            "RtlLookupFunctionEntry" => {
+                // There is no unwind information.
+                this.write_null(dest)?;
+            }
+            // DbgHelp. We have no debug information, so all symbols are unknown.
+            "SymInitialize" | "SymInitializeW" | "SymCleanup" => {
+                // Return `TRUE`.
+                this.write_scalar(Scalar::from_int(1, dest.layout.size), dest)?;
+            }
+            "SymFromAddr" => {
+                let address = this.read_scalar(args[1])?.to_u64()?;
+                let displacement = this.read_scalar(args[2])?.not_undef()?;
+                let symbol = this.read_scalar(args[3])?.not_undef()?;
+                if !displacement.is_null_ptr(this) {
+                    this.write_raw_bytes(displacement, &0u64.to_le_bytes())?;
+                }
+                // Fill in the `SYMBOL_INFO` (Windows is always little-endian). `MaxNameLen`
+                // includes the null terminator.
+                let address_field = symbol.ptr_offset(Size::from_bytes(56), this)?;
+                let name_len_field = symbol.ptr_offset(Size::from_bytes(76), this)?;
+                let max_name_len_field = symbol.ptr_offset(Size::from_bytes(80), this)?;
+                let name_field = symbol.ptr_offset(Size::from_bytes(84), this)?;
+                let max_name_len = this.memory().read_bytes(max_name_len_field, Size::from_bytes(4))?;
+                let max_name_len = u32::from_le_bytes([max_name_len[0], max_name_len[1], max_name_len[2], max_name_len[3]]);
+                let name: &[u8] = b"??";
+                let name_len = name.len().min((max_name_len as usize).saturating_sub(1));
+                this.write_raw_bytes(address_field, &address.to_le_bytes())?;
+                this.write_raw_bytes(name_len_field, &(name_len as u32).to_le_bytes())?;
+                if max_name_len > 0 {
+                    let mut name = name[..name_len].to_vec();
+                    name.push(0);
+                    this.write_raw_bytes(name_field, &name)?;
+                }
+                this.write_scalar(Scalar::from_int(1, dest.layout.size), dest)?;
+            }
+            "SymGetLineFromAddr64" | "SymGetLineFromAddrW64" => {
+                // Return `FALSE`.
+                this.write_null(dest)?;
             }
 
             "AddVectoredExceptionHandler" => {
@@ -830,13 +2900,172 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
                 // Nothing to do, not even a return value.
             },
             "GetModuleHandleW" |
-            "GetProcAddress" |
             "TryEnterCriticalSection" |
             "GetConsoleScreenBufferInfo" |
             "SetConsoleTextAttribute" => {
                 // Pretend these do not exist / nothing happened, by returning zero.
                 this.write_null(dest)?;
             },
+            // We cannot load any libraries. The standard library looks up optional functions
+            // with `GetProcAddress`, and falls back to something else if they do not exist.
+            "LoadLibraryW" | "LoadLibraryExW" => {
+                this.set_last_error(Scalar::from_u32(126))?; // ERROR_MOD_NOT_FOUND
+                this.write_null(dest)?;
+            }
+            "GetProcAddress" => {
+                this.set_last_error(Scalar::from_u32(127))?; // ERROR_PROC_NOT_FOUND
+                this.write_null(dest)?;
+            }
+            "FreeLibrary" => {
+                // The only module handle we ever hand out is the null handle of `GetModuleHandleW`.
+                let module = this.read_scalar(args[0])?.not_undef()?;
+                let freed = module.is_null_ptr(this);
+                if !freed {
+                    this.set_last_error(Scalar::from_u32(6))?; // ERROR_INVALID_HANDLE
+                }
+                this.write_scalar(Scalar::from_int(freed as i32, dest.layout.size), dest)?;
+            }
+            // Miri cannot create threads, so no handle can refer to one.
+            // Kernel objects.
+            "WaitForSingleObject" => {
+                let handle = this.read_scalar(args[0])?.to_isize(this)?;
+                let timeout = this.read_scalar(args[1])?.to_u32()?;
+                let result = this.wait_for_objects(&[handle], false, timeout)?;
+                this.write_scalar(Scalar::from_u32(result), dest)?;
+            }
+            "WaitForMultipleObjects" => {
+                let count = this.read_scalar(args[0])?.to_u32()?;
+                let wait_all = this.read_scalar(args[2])?.to_i32()? != 0;
+                let timeout = this.read_scalar(args[3])?.to_u32()?;
+                let result = if count == 0 || count > 64 {
+                    this.set_last_error(Scalar::from_u32(87))?; // ERROR_INVALID_PARAMETER
+                    WAIT_FAILED
+                } else {
+                    let handles_place = this.deref_operand(args[1])?;
+                    let handle_layout = handles_place.layout;
+                    let mut handles = Vec::with_capacity(count as usize);
+                    for i in 0..u64::from(count) {
+                        let place = handles_place.offset(handle_layout.size * i, None, handle_layout, this)?;
+                        handles.push(this.read_scalar(place.into())?.to_isize(this)?);
+                    }
+                    this.wait_for_objects(&handles, wait_all, timeout)?
+                };
+                this.write_scalar(Scalar::from_u32(result), dest)?;
+            }
+            "CloseHandle" => {
+                let handle = this.read_scalar(args[0])?.to_isize(this)?;
+                let closed = this.machine.kernel_objects.close(handle);
+                if !closed {
+                    this.set_last_error(Scalar::from_u32(6))?; // ERROR_INVALID_HANDLE
+                }
+                this.write_scalar(Scalar::from_int(closed as i32, dest.layout.size), dest)?;
+            }
+            "CreateMutexW" => {
+                let initial_owner = this.read_scalar(args[1])?.to_i32()? != 0;
+                let name = this.read_scalar(args[2])?.not_undef()?;
+                let mutex = KernelObject::Mutex { lock_count: initial_owner as u32 };
+                let handle = this.create_kernel_object(mutex, name)?;
+                this.write_scalar(Scalar::from_int(handle, dest.layout.size), dest)?;
+            }
+            "ReleaseMutex" => {
+                let handle = this.read_scalar(args[0])?.to_isize(this)?;
+                let error = match this.machine.kernel_objects.get_mut(handle) {
+                    Some(KernelObject::Mutex { lock_count }) if *lock_count > 0 => {
+                        *lock_count -= 1;
+                        None
+                    }
+                    Some(KernelObject::Mutex { .. }) => Some(288), // ERROR_NOT_OWNER
+                    _ => Some(6), // ERROR_INVALID_HANDLE
+                };
+                if let Some(error) = error {
+                    this.set_last_error(Scalar::from_u32(error))?;
+                }
+                this.write_scalar(Scalar::from_int(error.is_none() as i32, dest.layout.size), dest)?;
+            }
+            "CreateEventW" => {
+                let manual_reset = this.read_scalar(args[1])?.to_i32()? != 0;
+                let signaled = this.read_scalar(args[2])?.to_i32()? != 0;
+                let name = this.read_scalar(args[3])?.not_undef()?;
+                let event = KernelObject::Event { manual_reset, signaled };
+                let handle = this.create_kernel_object(event, name)?;
+                this.write_scalar(Scalar::from_int(handle, dest.layout.size), dest)?;
+            }
+            "SetEvent" | "ResetEvent" | "PulseEvent" => {
+                let handle = this.read_scalar(args[0])?.to_isize(this)?;
+                let ok = match this.machine.kernel_objects.get_mut(handle) {
+                    Some(KernelObject::Event { signaled, .. }) => {
+                        // Nobody else can be waiting, so pulsing an event just leaves it reset.
+                        *signaled = link_name == "SetEvent";
+                        true
+                    }
+                    _ => false,
+                };
+                if !ok {
+                    this.set_last_error(Scalar::from_u32(6))?; // ERROR_INVALID_HANDLE
+                }
+                this.write_scalar(Scalar::from_int(ok as i32, dest.layout.size), dest)?;
+            }
+            "CreateSemaphoreW" => {
+                let initial = this.read_scalar(args[1])?.to_i32()?;
+                let maximum = this.read_scalar(args[2])?.to_i32()?;
+                let name = this.read_scalar(args[3])?.not_undef()?;
+                let handle = if maximum <= 0 || initial < 0 || initial > maximum {
+                    this.set_last_error(Scalar::from_u32(87))?; // ERROR_INVALID_PARAMETER
+                    0
+                } else {
+                    let semaphore = KernelObject::Semaphore { count: initial as u32, maximum: maximum as u32 };
+                    this.create_kernel_object(semaphore, name)?
+                };
+                this.write_scalar(Scalar::from_int(handle, dest.layout.size), dest)?;
+            }
+            "OpenSemaphoreW" => {
+                let name = this.read_scalar(args[2])?.not_undef()?;
+                let name = String::from_utf16_lossy(&this.read_wide_str(name)?);
+                let handle = match this.machine.kernel_objects.open(&name) {
+                    Some(handle) => match this.machine.kernel_objects.get(handle) {
+                        Some(KernelObject::Semaphore { .. }) => handle,
+                        _ => {
+                            // The name belongs to a different kind of object.
+                            this.machine.kernel_objects.close(handle);
+                            this.set_last_error(Scalar::from_u32(6))?; // ERROR_INVALID_HANDLE
+                            0
+                        }
+                    },
+                    None => {
+                        this.set_last_error(Scalar::from_u32(2))?; // ERROR_FILE_NOT_FOUND
+                        0
+                    }
+                };
+                this.write_scalar(Scalar::from_int(handle, dest.layout.size), dest)?;
+            }
+            "ReleaseSemaphore" => {
+                let handle = this.read_scalar(args[0])?.to_isize(this)?;
+                let release = this.read_scalar(args[1])?.to_i32()?;
+                let previous_ptr = this.read_scalar(args[2])?.not_undef()?;
+                let result = match this.machine.kernel_objects.get_mut(handle) {
+                    Some(KernelObject::Semaphore { .. }) if release <= 0 => Err(87), // ERROR_INVALID_PARAMETER
+                    Some(KernelObject::Semaphore { count, maximum }) => {
+                        let previous = *count;
+                        if u64::from(previous) + release as u64 > u64::from(*maximum) {
+                            Err(298) // ERROR_TOO_MANY_POSTS
+                        } else {
+                            *count += release as u32;
+                            Ok(previous)
+                        }
+                    }
+                    _ => Err(6), // ERROR_INVALID_HANDLE
+                };
+                match result {
+                    Ok(previous) => {
+                        if !previous_ptr.is_null_ptr(this) {
+                            let previous_place = this.deref_operand(args[2])?;
+                            this.write_scalar(Scalar::from_u32(previous), previous_place.into())?;
+                        }
+                    }
+                    Err(error) => this.set_last_error(Scalar::from_u32(error))?,
+                }
+                this.write_scalar(Scalar::from_int(result.is_ok() as i32, dest.layout.size), dest)?;
+            }
             "GetSystemInfo" => {
                 let system_info = this.deref_operand(args[0])?;
                 let system_info_ptr = system_info.ptr.to_ptr()?;
@@ -860,6 +3089,7 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
 
                 // Create key and return it.
                 let key = this.machine.tls.create_tls_key(None, tcx) as u128;
+                this.log_event(Event::TlsCreate { key });
 
                 // Figure out how large a TLS key actually is. This is `c::DWORD`.
                 if dest.layout.size.bits() < 128
@@ -920,18 +3150,158 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
                     dest,
                 )?;
             }
+            // The only handles we know are the standard handles from `GetStdHandle`.
+            "FlushFileBuffers" => {
+                use std::io::{self, Write};
+
+                let handle = this.read_scalar(args[0])?.to_isize(this)?;
+                let flushed = match handle {
+                    -11 => io::stdout().flush().is_ok(),
+                    -12 => io::stderr().flush().is_ok(),
+                    -10 => true,
+                    _ => {
+                        this.set_last_error(Scalar::from_u32(6))?; // ERROR_INVALID_HANDLE
+                        false
+                    }
+                };
+                this.write_scalar(Scalar::from_int(flushed as i32, dest.layout.size), dest)?;
+            }
+            "SetEndOfFile" | "SetFilePointerEx" | "GetFileSizeEx" => {
+                let handle = this.read_scalar(args[0])?.to_isize(this)?;
+                // The standard handles have no size or position that could be moved or
+                // truncated at.
+                let error = if is_std_handle(handle) {
+                    1 // ERROR_INVALID_FUNCTION
+                } else {
+                    6 // ERROR_INVALID_HANDLE
+                };
+                this.set_last_error(Scalar::from_u32(error))?;
+                this.write_null(dest)?;
+            }
+            "GetFileSize" => {
+                let handle = this.read_scalar(args[0])?.to_isize(this)?;
+                let error = if is_std_handle(handle) {
+                    1 // ERROR_INVALID_FUNCTION
+                } else {
+                    6 // ERROR_INVALID_HANDLE
+                };
+                this.set_last_error(Scalar::from_u32(error))?;
+                // `INVALID_FILE_SIZE`
+                this.write_scalar(Scalar::from_u32(0xFFFF_FFFF), dest)?;
+            }
+            "GetFileType" => {
+                let handle = this.read_scalar(args[0])?.to_isize(this)?;
+                let file_type = if is_std_handle(handle) {
+                    2 // FILE_TYPE_CHAR
+                } else {
+                    this.set_last_error(Scalar::from_u32(6))?; // ERROR_INVALID_HANDLE
+                    0 // FILE_TYPE_UNKNOWN
+                };
+                this.write_scalar(Scalar::from_u32(file_type), dest)?;
+            }
             "GetConsoleMode" => {
                 // Everything is a pipe.
                 this.write_null(dest)?;
             }
             "GetEnvironmentVariableW" => {
                 // This is not the env var you are looking for.
-                this.machine.last_error = 203; // ERROR_ENVVAR_NOT_FOUND
+                this.set_last_error(Scalar::from_u32(203))?; // ERROR_ENVVAR_NOT_FOUND
                 this.write_null(dest)?;
             }
             "GetCommandLineW" => {
                 this.write_scalar(Scalar::Ptr(this.machine.cmd_line.unwrap()), dest)?;
             }
+            // The registry is empty. These return their error code instead of setting the last error.
+            "RegOpenKeyExW" | "RegQueryValueExW" => {
+                this.write_scalar(Scalar::from_int(2, dest.layout.size), dest)?; // ERROR_FILE_NOT_FOUND
+            }
+            "RegCloseKey" => {
+                // `RegOpenKeyExW` never hands out keys, but closing the predefined ones is fine.
+                this.write_null(dest)?; // ERROR_SUCCESS
+            }
+            "FormatMessageW" => {
+                let flags = this.read_scalar(args[0])?.to_u32()?;
+                let code = this.read_scalar(args[2])?.to_u32()?;
+                let buf = this.read_scalar(args[4])?.not_undef()?;
+                let size = this.read_scalar(args[5])?.to_u32()?;
+                if flags & 0x100 != 0 {
+                    return err!(Unimplemented(
+                        "FormatMessageW with FORMAT_MESSAGE_ALLOCATE_BUFFER is not supported".to_string(),
+                    ));
+                }
+                // We only know a few system messages, and nothing else.
+                let message = if flags & 0x1000 != 0 { windows_error_message(code) } else { None };
+                let result = match message {
+                    Some(message) => {
+                        let message: Vec<u16> = message.encode_utf16().chain("\r\n".encode_utf16()).collect();
+                        if message.len() as u64 + 1 > u64::from(size) {
+                            this.set_last_error(Scalar::from_u32(122))?; // ERROR_INSUFFICIENT_BUFFER
+                            0
+                        } else {
+                            this.write_wide_str(buf, &message)?;
+                            message.len()
+                        }
+                    }
+                    None => {
+                        this.set_last_error(Scalar::from_u32(317))?; // ERROR_MR_MID_NOT_FOUND
+                        0
+                    }
+                };
+                this.write_scalar(Scalar::from_uint(result as u64, dest.layout.size), dest)?;
+            }
+            // Paths. We pretend the current directory is `C:\miri`.
+            "GetFullPathNameW" => {
+                let name = this.read_scalar(args[0])?.not_undef()?;
+                let buf_len = this.read_scalar(args[1])?.to_u32()?;
+                let buf = this.read_scalar(args[2])?.not_undef()?;
+                let file_part = this.read_scalar(args[3])?.not_undef()?;
+                let name = String::from_utf16_lossy(&this.read_wide_str(name)?);
+                let full: Vec<u16> = windows_full_path(&name).encode_utf16().collect();
+                let result = if full.len() as u64 + 1 > u64::from(buf_len) {
+                    // Return the required buffer size, including the null terminator.
+                    full.len() + 1
+                } else {
+                    this.write_wide_str(buf, &full)?;
+                    if !file_part.is_null_ptr(this) {
+                        // Point to the last component, if there is one.
+                        let start = full.iter().rposition(|&c| c == u16::from(b'\\')).map_or(0, |idx| idx + 1);
+                        let file_part_ptr = if start < full.len() {
+                            buf.ptr_offset(Size::from_bytes(2 * start as u64), this)?
+                        } else {
+                            Scalar::ptr_null(this)
+                        };
+                        let file_part = this.deref_operand(args[3])?;
+                        this.write_scalar(file_part_ptr, file_part.into())?;
+                    }
+                    full.len()
+                };
+                this.write_scalar(Scalar::from_uint(result as u64, dest.layout.size), dest)?;
+            }
+            "GetFileAttributesW" => {
+                let name = this.read_scalar(args[0])?.not_undef()?;
+                let name = String::from_utf16_lossy(&this.read_wide_str(name)?);
+                let path = windows_full_path(&name).to_lowercase();
+                if this.machine.virtual_dirs.contains(path.trim_end_matches('\\')) {
+                    // `FILE_ATTRIBUTE_DIRECTORY`
+                    this.write_scalar(Scalar::from_u32(0x10), dest)?;
+                } else {
+                    this.set_last_error(Scalar::from_u32(2))?; // ERROR_FILE_NOT_FOUND
+                    // `INVALID_FILE_ATTRIBUTES`
+                    this.write_scalar(Scalar::from_u32(0xFFFF_FFFF), dest)?;
+                }
+            }
+            "CreateDirectoryW" => {
+                let name = this.read_scalar(args[0])?.not_undef()?;
+                let name = String::from_utf16_lossy(&this.read_wide_str(name)?);
+                let path = windows_full_path(&name).to_lowercase();
+                let path = path.trim_end_matches('\\').to_string();
+                if this.machine.virtual_dirs.insert(path) {
+                    this.write_scalar(Scalar::from_int(1, dest.layout.size), dest)?;
+                } else {
+                    this.set_last_error(Scalar::from_u32(183))?; // ERROR_ALREADY_EXISTS
+                    this.write_null(dest)?;
+                }
+            }
             // The actual name of 'RtlGenRandom'
             "SystemFunction036" => {
                 let ptr = this.read_scalar(args[0])?.not_undef()?;
@@ -957,6 +3327,32 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
         self.eval_context_mut().write_scalar(Scalar::from_int(0, dest.layout.size), dest)
     }
 
+    /// Records the rounding mode of the floating-point environment.
+    fn set_rounding_mode(&mut self, round: i32) {
+        let this = self.eval_context_mut();
+        if round != FE_TONEAREST && round != this.machine.fenv {
+            this.tcx.sess.warn(&format!(
+                "the evaluated program set floating-point rounding mode {:#x}, \
+                but Miri always rounds to nearest",
+                round,
+            ));
+        }
+        this.machine.fenv = round;
+    }
+
+    /// Reads the direction argument of `nextafter`/`nexttoward`, widened to `f64`.
+    /// For `nexttoward` this is a `long double`, which we only support if it is the same as `double`.
+    fn read_next_toward_target(&mut self, link_name: &str, arg: OpTy<'tcx, Tag>) -> InterpResult<'tcx, f64> {
+        let this = self.eval_context_mut();
+        Ok(match arg.layout.size.bytes() {
+            4 => f32::from_bits(this.read_scalar(arg)?.to_u32()?) as f64,
+            8 => f64::from_bits(this.read_scalar(arg)?.to_u64()?),
+            _ => return err!(Unimplemented(
+                format!("{} is not supported for `long double` arguments", link_name),
+            )),
+        })
+    }
+
     /// Evaluates the scalar at the specified path. Returns Some(val)
     /// if the path could be resolved, and None otherwise
     fn eval_path_scalar(&mut self, path: &[&str]) -> InterpResult<'tcx, Option<ScalarMaybeUndef<Tag>>> {
@@ -985,23 +3381,230 @@ fn gen_random<'mir, 'tcx>(
     }
     let ptr = dest.to_ptr()?;
 
-    let data = match &mut this.memory_mut().extra.rng {
+    let mut data = vec![0; len];
+    fill_random(this, &mut data)?;
+    this.machine.random_allocs.insert(ptr.alloc_id);
+    let tcx = &{this.tcx.tcx};
+    this.memory_mut().get_mut(ptr.alloc_id)?
+        .write_bytes(tcx, ptr, &data)
+}
+
+/// Returns a random `u32`, like `arc4random`.
+fn random_u32<'mir, 'tcx>(this: &mut MiriEvalContext<'mir, 'tcx>) -> InterpResult<'tcx, u32> {
+    let mut bytes = [0; 4];
+    fill_random(this, &mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+/// Fills `data` with random bytes from our PRNG, which only exists if we have a seed.
+fn fill_random<'mir, 'tcx>(this: &mut MiriEvalContext<'mir, 'tcx>, data: &mut [u8]) -> InterpResult<'tcx> {
+    match &this.memory().extra.rng {
         Some(rng) => {
-            let mut rng = rng.borrow_mut();
-            let mut data = vec![0; len];
-            rng.fill_bytes(&mut data);
-            data
+            rng.borrow_mut().fill_bytes(data);
+            Ok(())
         }
         None => {
-            return err!(Unimplemented(
+            err!(Unimplemented(
                 "miri does not support gathering system entropy in deterministic mode!
                 Use '-Zmiri-seed=<seed>' to enable random number generation.
                 WARNING: Miri does *not* generate cryptographically secure entropy -
                 do not use Miri to run any program that needs secure random number generation".to_owned(),
-            ));
+            ))
         }
+    }
+}
+
+fn clamp_to_i32(val: i128) -> i32 {
+    val.max(i32::min_value() as i128).min(i32::max_value() as i128) as i32
+}
+
+/// Parses the tag passed to `nan` the way `strtoull` with base `0` does:
+/// hexadecimal with a `0x` prefix, octal with a leading `0`, decimal otherwise.
+fn parse_nan_payload(tag: &[u8]) -> Option<u64> {
+    let tag = std::str::from_utf8(tag).ok()?;
+    if tag.starts_with("0x") || tag.starts_with("0X") {
+        u64::from_str_radix(&tag[2..], 16).ok()
+    } else if tag.starts_with('0') && tag.len() > 1 {
+        u64::from_str_radix(&tag[1..], 8).ok()
+    } else {
+        tag.parse().ok()
+    }
+}
+
+/// Returns the representable `f32` adjacent to the non-NaN `f` in the given direction.
+fn next_after_f32(f: f32, up: bool) -> f32 {
+    if f == 0.0 {
+        // The smallest subnormal, with the sign of the direction we step into.
+        let tiny = f32::from_bits(1);
+        return if up { tiny } else { -tiny };
+    }
+    // Floats are sign-magnitude, so stepping away from zero increments the bits.
+    let bits = f.to_bits();
+    f32::from_bits(if (f > 0.0) == up { bits + 1 } else { bits - 1 })
+}
+
+/// Returns the representable `f64` adjacent to the non-NaN `f` in the given direction.
+fn next_after_f64(f: f64, up: bool) -> f64 {
+    if f == 0.0 {
+        // The smallest subnormal, with the sign of the direction we step into.
+        let tiny = f64::from_bits(1);
+        return if up { tiny } else { -tiny };
+    }
+    // Floats are sign-magnitude, so stepping away from zero increments the bits.
+    let bits = f.to_bits();
+    f64::from_bits(if (f > 0.0) == up { bits + 1 } else { bits - 1 })
+}
+
+/// Computes `sqrt(x*x + y*y)` without the squares overflowing or underflowing, by scaling
+/// with the larger magnitude.
+fn hypot(x: f64, y: f64) -> f64 {
+    let (x, y) = (x.abs(), y.abs());
+    // An infinite argument wins even against NaN.
+    if x.is_infinite() || y.is_infinite() {
+        return std::f64::INFINITY;
+    }
+    if x.is_nan() || y.is_nan() {
+        return std::f64::NAN;
+    }
+    let (max, min) = if x > y { (x, y) } else { (y, x) };
+    if max == 0.0 {
+        return 0.0;
+    }
+    let ratio = min / max;
+    max * (1.0 + ratio * ratio).sqrt()
+}
+
+/// Computes `ln(1 + x)` without losing the precision of small `x` in the addition: the
+/// rounding error of `1 + x` is compensated by dividing by the `x` it actually represents.
+fn log1p(x: f64) -> f64 {
+    let u = 1.0 + x;
+    if u == 1.0 {
+        // This also preserves the sign of zero.
+        x
+    } else if u.is_infinite() {
+        u.ln()
+    } else {
+        u.ln() * (x / (u - 1.0))
+    }
+}
+
+/// Rounds `f` to the nearest integer, and halfway cases to the even one. This is what `rint`
+/// does in the default rounding mode, which is the only one we support.
+fn round_ties_even(f: f64) -> f64 {
+    if (f - f.trunc()).abs() == 0.5 {
+        2.0 * (f / 2.0).round()
+    } else {
+        f.round()
+    }
+}
+
+/// Checks the length of an access done by a `_FORTIFY_SOURCE` function against the size of
+/// the destination buffer that the compiler determined.
+fn check_fortify_len<'tcx>(link_name: &str, len: u64, dest_len: u64) -> InterpResult<'tcx> {
+    if len > dest_len {
+        return err!(MachineError(format!("buffer overflow detected by {}", link_name)));
+    }
+    Ok(())
+}
+
+/// Checks whether `c` is in the `<ctype.h>` character class `class` (e.g. `"alpha"`)
+/// of the C locale.
+fn is_ascii_class(class: &str, c: u8) -> bool {
+    match class {
+        "alpha" => c.is_ascii_alphabetic(),
+        "digit" => c.is_ascii_digit(),
+        "alnum" => c.is_ascii_alphanumeric(),
+        // Unlike `u8::is_ascii_whitespace`, this includes the vertical tab.
+        "space" => c.is_ascii_whitespace() || c == b'\x0b',
+        "punct" => c.is_ascii_punctuation(),
+        "cntrl" => c.is_ascii_control(),
+        "upper" => c.is_ascii_uppercase(),
+        "lower" => c.is_ascii_lowercase(),
+        "xdigit" => c.is_ascii_hexdigit(),
+        "print" => c.is_ascii_graphic() || c == b' ',
+        "graph" => c.is_ascii_graphic(),
+        "blank" => c == b' ' || c == b'\t',
+        _ => bug!("unknown character class {}", class),
+    }
+}
+
+/// Builds a `mach_header` (or `mach_header_64`) for an executable of the given architecture.
+/// Only the magic number and CPU type mean anything; there are no load commands.
+fn fake_mach_header(arch: &str, endian: layout::Endian) -> Vec<u8> {
+    // `MH_MAGIC`/`MH_MAGIC_64`, `CPU_TYPE_*`, `CPU_SUBTYPE_*_ALL`.
+    let (magic, cpu_type, cpu_subtype) = match arch {
+        "x86_64" => (0xfeed_facf, 0x0100_0007, 3),
+        "aarch64" => (0xfeed_facf, 0x0100_000c, 0),
+        "arm" => (0xfeed_face, 12, 0),
+        _ => (0xfeed_face, 7, 3),
     };
-    let tcx = &{this.tcx.tcx};
-    this.memory_mut().get_mut(ptr.alloc_id)?
-        .write_bytes(tcx, ptr, &data)
+    let mut fields: Vec<u32> = vec![
+        magic,
+        cpu_type,
+        cpu_subtype,
+        2, // `MH_EXECUTE`
+        0, // `ncmds`
+        0, // `sizeofcmds`
+        0, // `flags`
+    ];
+    if magic == 0xfeed_facf {
+        fields.push(0); // `reserved`
+    }
+    fields.into_iter().flat_map(|field| match endian {
+        layout::Endian::Little => field.to_le_bytes(),
+        layout::Endian::Big => field.to_be_bytes(),
+    }.to_vec()).collect()
+}
+
+/// Makes up a code (`return_address`) or stack address for the frame with the given call ID.
+/// We use the upper half of the address space, which is not used for allocations.
+fn fake_frame_address<'mir, 'tcx>(
+    this: &MiriEvalContext<'mir, 'tcx>,
+    call_id: crate::stacked_borrows::CallId,
+    return_address: bool,
+) -> Scalar<Tag> {
+    let ptr_size = this.pointer_size();
+    let base = 1u128 << (ptr_size.bits() - 1);
+    let addr = base + u128::from(call_id.get() * 2 + return_address as u64) * 8;
+    Scalar::from_uint(addr & (u128::max_value() >> (128 - ptr_size.bits())), ptr_size)
+}
+
+/// Makes a Windows path absolute, relative to our fake current directory `C:\miri`.
+/// `.` and `..` components are kept as they are.
+fn windows_full_path(path: &str) -> String {
+    let path = path.replace('/', "\\");
+    if path.starts_with("\\\\") || path.as_bytes().get(1) == Some(&b':') {
+        path
+    } else if path.starts_with('\\') {
+        // Relative to the root of the current drive.
+        format!("C:{}", path)
+    } else {
+        format!("C:\\miri\\{}", path)
+    }
+}
+
+/// Checks whether `handle` is one of the standard handles returned by our `GetStdHandle`.
+fn is_std_handle(handle: i64) -> bool {
+    handle >= -12 && handle <= -10
+}
+
+/// The system message for some common Windows error codes, as `FormatMessageW` would return it
+/// (minus the trailing line break).
+fn windows_error_message(code: u32) -> Option<&'static str> {
+    Some(match code {
+        0 => "The operation completed successfully.", // ERROR_SUCCESS
+        1 => "Incorrect function.", // ERROR_INVALID_FUNCTION
+        2 => "The system cannot find the file specified.", // ERROR_FILE_NOT_FOUND
+        3 => "The system cannot find the path specified.", // ERROR_PATH_NOT_FOUND
+        5 => "Access is denied.", // ERROR_ACCESS_DENIED
+        6 => "The handle is invalid.", // ERROR_INVALID_HANDLE
+        8 => "Not enough memory resources are available to process this command.", // ERROR_NOT_ENOUGH_MEMORY
+        87 => "The parameter is incorrect.", // ERROR_INVALID_PARAMETER
+        122 => "The data area passed to a system call is too small.", // ERROR_INSUFFICIENT_BUFFER
+        126 => "The specified module could not be found.", // ERROR_MOD_NOT_FOUND
+        127 => "The specified procedure could not be found.", // ERROR_PROC_NOT_FOUND
+        183 => "Cannot create a file when that file already exists.", // ERROR_ALREADY_EXISTS
+        203 => "The system could not find the environment option that was entered.", // ERROR_ENVVAR_NOT_FOUND
+        _ => return None,
+    })
 }
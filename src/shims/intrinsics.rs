@@ -1,3 +1,5 @@
+use rand::Rng;
+
 use rustc_apfloat::Float;
 use rustc::mir;
 use rustc::mir::interpret::{InterpResult, PointerArithmetic};
@@ -93,6 +95,12 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
                 let old = this.read_immediate(ptr.into())?; // read as immediate for the sake of `binary_op()`
                 // binary_op will bail if either of them is not a scalar
                 let (eq, _) = this.binary_op(mir::BinOp::Eq, old, expect_old)?;
+                // `compare_exchange_weak` may fail spuriously even if the values are equal.
+                let failure_rate = this.machine.cmpxchg_weak_failure_rate;
+                let spurious_failure = intrinsic_name.starts_with("atomic_cxchgweak")
+                    && failure_rate > 0.0
+                    && this.memory().extra.rng.as_ref().map_or(false, |rng| rng.borrow_mut().gen_bool(failure_rate));
+                let eq = Scalar::from_bool(eq.to_bool()? && !spurious_failure);
                 let res = Immediate::ScalarPair(old.to_scalar_or_undef(), eq.into());
                 this.write_immediate(res, dest)?; // old value is returned
                 // update ptr depending on comparison
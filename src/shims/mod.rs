@@ -1,2 +1,4 @@
+pub mod abi;
 pub mod foreign_items;
 pub mod intrinsics;
+pub mod va_list;
@@ -0,0 +1,112 @@
+//! Reading the variadic arguments of calls to emulated functions like `printf`.
+//!
+//! The caller passes variadic arguments just like the fixed ones, so they are simply the
+//! arguments after those. `VaList` hands them out one at a time, like C's `va_arg`.
+
+use std::slice;
+
+use rustc::ty::layout::Size;
+
+use crate::*;
+
+/// The variadic arguments of a call to an emulated function.
+pub struct VaList<'a, 'tcx> {
+    link_name: &'a str,
+    args: slice::Iter<'a, OpTy<'tcx, Tag>>,
+}
+
+impl<'a, 'tcx> VaList<'a, 'tcx> {
+    /// The variadic arguments of a call to `link_name`: all of `args` after the first `fixed`.
+    pub fn new(link_name: &'a str, args: &'a [OpTy<'tcx, Tag>], fixed: usize) -> Self {
+        VaList { link_name, args: args.get(fixed..).unwrap_or(&[]).iter() }
+    }
+
+    /// Returns the next argument, whatever its type.
+    pub fn next_arg(&mut self) -> InterpResult<'tcx, OpTy<'tcx, Tag>> {
+        self.args.next().copied().ok_or_else(|| InterpError::MachineError(format!(
+            "`{}` requires more arguments than were passed", self.link_name,
+        )))
+    }
+
+    /// Reads the next argument, which must be a scalar.
+    pub fn read_scalar<'mir>(&mut self, ecx: &MiriEvalContext<'mir, 'tcx>) -> InterpResult<'tcx, Scalar<Tag>> {
+        let arg = self.next_arg()?;
+        ecx.read_scalar(arg)?.not_undef()
+    }
+
+    /// Reads the next argument as a `T`, like C's `va_arg(ap, T)`.
+    pub fn read_va_arg<'mir, T: VaArg>(&mut self, ecx: &MiriEvalContext<'mir, 'tcx>) -> InterpResult<'tcx, T> {
+        let arg = self.next_arg()?;
+        self.check_size(arg, Size::from_bytes(T::SIZE), T::C_TYPE)?;
+        T::from_scalar(ecx.read_scalar(arg)?.not_undef()?)
+    }
+
+    /// Reads the next argument, which must be an `int`. The caller promoted smaller integers
+    /// to `int`.
+    pub fn read_int<'mir>(&mut self, ecx: &MiriEvalContext<'mir, 'tcx>) -> InterpResult<'tcx, i32> {
+        self.read_va_arg(ecx)
+    }
+
+    /// Reads the next argument, which must be a `double`. The caller promoted `float`s to
+    /// `double`.
+    pub fn read_double<'mir>(&mut self, ecx: &MiriEvalContext<'mir, 'tcx>) -> InterpResult<'tcx, f64> {
+        self.read_va_arg(ecx)
+    }
+
+    fn check_size(&self, arg: OpTy<'tcx, Tag>, size: Size, ty: &str) -> InterpResult<'tcx> {
+        if arg.layout.size != size {
+            return err!(AbiViolation(format!(
+                "`{}` expected a variadic argument of type `{}`, but got one of size {}",
+                self.link_name, ty, arg.layout.size.bytes(),
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// A type that can be passed as a variadic argument, i.e., one that the default argument
+/// promotions leave unchanged.
+pub trait VaArg: Sized {
+    /// The name of the C type, for error messages.
+    const C_TYPE: &'static str;
+    /// The size of the C type in bytes.
+    const SIZE: u64;
+
+    fn from_scalar<'tcx>(scalar: Scalar<Tag>) -> InterpResult<'tcx, Self>;
+}
+
+impl VaArg for i32 {
+    const C_TYPE: &'static str = "int";
+    const SIZE: u64 = 4;
+
+    fn from_scalar<'tcx>(scalar: Scalar<Tag>) -> InterpResult<'tcx, Self> {
+        scalar.to_i32()
+    }
+}
+
+impl VaArg for u32 {
+    const C_TYPE: &'static str = "unsigned int";
+    const SIZE: u64 = 4;
+
+    fn from_scalar<'tcx>(scalar: Scalar<Tag>) -> InterpResult<'tcx, Self> {
+        scalar.to_u32()
+    }
+}
+
+impl VaArg for u64 {
+    const C_TYPE: &'static str = "unsigned long long";
+    const SIZE: u64 = 8;
+
+    fn from_scalar<'tcx>(scalar: Scalar<Tag>) -> InterpResult<'tcx, Self> {
+        scalar.to_u64()
+    }
+}
+
+impl VaArg for f64 {
+    const C_TYPE: &'static str = "double";
+    const SIZE: u64 = 8;
+
+    fn from_scalar<'tcx>(scalar: Scalar<Tag>) -> InterpResult<'tcx, Self> {
+        Ok(f64::from_bits(scalar.to_u64()?))
+    }
+}
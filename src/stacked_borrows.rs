@@ -11,11 +11,11 @@ use rustc::mir::RetagKind;
 use crate::{
     InterpResult, InterpError, MiriEvalContext, HelpersEvalContextExt, Evaluator, MutValueVisitor,
     MemoryKind, MiriMemoryKind, RangeMap, AllocId, Pointer, Immediate, ImmTy, PlaceTy, MPlaceTy,
+    Tree, TreePermission,
 };
 
 pub type PtrId = NonZeroU64;
 pub type CallId = NonZeroU64;
-pub type AllocExtra = Stacks;
 
 /// Tracking pointer provenance
 #[derive(Copy, Clone, Hash, PartialEq, Eq)]
@@ -89,6 +89,13 @@ pub struct Stacks {
     global: MemoryExtra,
 }
 
+/// Extra per-allocation state, for the aliasing model picked with `-Zmiri-tree-borrows`.
+#[derive(Clone, Debug)]
+pub enum AllocExtra {
+    Stacks(Stacks),
+    Tree(Tree),
+}
+
 /// Extra global state, available to the memory access hooks.
 #[derive(Debug)]
 pub struct GlobalState {
@@ -102,6 +109,15 @@ pub struct GlobalState {
     next_call_id: CallId,
     /// Those call IDs corresponding to functions that are still running.
     active_calls: HashSet<CallId>,
+    /// Whether raw pointers get a fresh tag when they are created from a reference, instead
+    /// of being untagged.
+    pub(crate) tag_raw_pointers: bool,
+    /// While the checks are disabled with `__miri_disable_stacked_borrows`, the violations
+    /// we found so far. They are reported as warnings when the checks are enabled again.
+    pub(crate) suppressed_violations: Option<Vec<String>>,
+    /// Whether we check with Tree Borrows instead of Stacked Borrows. This only affects
+    /// new allocations.
+    pub(crate) tree_borrows: bool,
 }
 /// Memory extra state gives us interior mutable access to the global state.
 pub type MemoryExtra = Rc<RefCell<GlobalState>>;
@@ -155,6 +171,9 @@ impl Default for GlobalState {
             base_ptr_ids: HashMap::default(),
             next_call_id: NonZeroU64::new(1).unwrap(),
             active_calls: HashSet::default(),
+            tag_raw_pointers: false,
+            suppressed_violations: None,
+            tree_borrows: false,
         }
     }
 }
@@ -178,7 +197,7 @@ impl GlobalState {
         assert!(self.active_calls.remove(&id));
     }
 
-    fn is_active(&self, id: CallId) -> bool {
+    pub(crate) fn is_active(&self, id: CallId) -> bool {
         self.active_calls.contains(&id)
     }
 
@@ -437,24 +456,86 @@ impl<'tcx> Stacks {
     }
 
     /// Call `f` on every stack in the range.
+    /// If the checks are disabled, violations are recorded, and we go on with the next stack.
     fn for_each(
         &self,
         ptr: Pointer<Tag>,
         size: Size,
         f: impl Fn(&mut Stack, &GlobalState) -> InterpResult<'tcx>,
     ) -> InterpResult<'tcx> {
-        let global = self.global.borrow();
+        let mut global = self.global.borrow_mut();
         let mut stacks = self.stacks.borrow_mut();
         for stack in stacks.iter_mut(ptr.offset, size) {
-            f(stack, &*global)?;
+            if let Err(err) = f(stack, &*global) {
+                match global.suppressed_violations {
+                    Some(ref mut violations) => violations.push(err.kind.to_string()),
+                    None => return Err(err),
+                }
+            }
         }
         Ok(())
     }
 }
 
 /// Glue code to connect with Miri Machine Hooks
-impl Stacks {
+impl AllocExtra {
     pub fn new_allocation(
+        id: AllocId,
+        size: Size,
+        extra: MemoryExtra,
+        kind: MemoryKind<MiriMemoryKind>,
+    ) -> (Self, Tag) {
+        if extra.borrow().tree_borrows {
+            let tag = match kind {
+                MemoryKind::Machine(MiriMemoryKind::Static) => extra.borrow_mut().static_base_ptr(id),
+                MemoryKind::Stack => Tag::Tagged(extra.borrow_mut().new_ptr()),
+                _ => Tag::Untagged,
+            };
+            return (AllocExtra::Tree(Tree::new(size, tag, extra)), tag);
+        }
+        let (stacks, tag) = Stacks::new_allocation(id, size, extra, kind);
+        (AllocExtra::Stacks(stacks), tag)
+    }
+
+    #[inline(always)]
+    pub fn memory_read<'tcx>(
+        &self,
+        ptr: Pointer<Tag>,
+        size: Size,
+    ) -> InterpResult<'tcx> {
+        match self {
+            AllocExtra::Stacks(stacks) => stacks.memory_read(ptr, size),
+            AllocExtra::Tree(tree) => tree.memory_read(ptr, size),
+        }
+    }
+
+    #[inline(always)]
+    pub fn memory_written<'tcx>(
+        &mut self,
+        ptr: Pointer<Tag>,
+        size: Size,
+    ) -> InterpResult<'tcx> {
+        match self {
+            AllocExtra::Stacks(stacks) => stacks.memory_written(ptr, size),
+            AllocExtra::Tree(tree) => tree.memory_written(ptr, size),
+        }
+    }
+
+    #[inline(always)]
+    pub fn memory_deallocated<'tcx>(
+        &mut self,
+        ptr: Pointer<Tag>,
+        size: Size,
+    ) -> InterpResult<'tcx> {
+        match self {
+            AllocExtra::Stacks(stacks) => stacks.memory_deallocated(ptr, size),
+            AllocExtra::Tree(tree) => tree.memory_deallocated(ptr, size),
+        }
+    }
+}
+
+impl Stacks {
+    fn new_allocation(
         id: AllocId,
         size: Size,
         extra: MemoryExtra, 
@@ -538,6 +619,19 @@ trait EvalContextPrivExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
 
         // Get the allocation. It might not be mutable, so we cannot use `get_mut`.
         let alloc = this.memory().get(ptr.alloc_id)?;
+        let stacks = match alloc.extra.stacked_borrows {
+            AllocExtra::Stacks(ref stacks) => stacks,
+            AllocExtra::Tree(ref tree) => {
+                // `retag_reference` does not create tags for the other kinds of pointers.
+                let perm = match kind {
+                    RefKind::Unique { .. } =>
+                        TreePermission::Reserved { interior_mut: !this.type_is_freeze(place.layout.ty) },
+                    RefKind::Shared => TreePermission::Frozen,
+                    RefKind::Raw { .. } => bug!("raw pointers are not retagged with Tree Borrows"),
+                };
+                return tree.reborrow(ptr, size, new_tag, perm, protector);
+            }
+        };
         // Update the stacks.
         // Make sure that raw pointers and mutable shared references are reborrowed "weak":
         // There could be existing unique pointers reborrowed from them that should remain valid!
@@ -553,14 +647,14 @@ trait EvalContextPrivExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
                     // We are only ever `SharedReadOnly` inside the frozen bits.
                     let perm = if frozen { Permission::SharedReadOnly } else { Permission::SharedReadWrite };
                     let item = Item { perm, tag: new_tag, protector };
-                    alloc.extra.stacked_borrows.for_each(cur_ptr, size, |stack, global| {
+                    stacks.for_each(cur_ptr, size, |stack, global| {
                         stack.grant(cur_ptr.tag, item, global)
                     })
                 });
             }
         };
         let item = Item { perm, tag: new_tag, protector };
-        alloc.extra.stacked_borrows.for_each(ptr, size, |stack, global| {
+        stacks.for_each(ptr, size, |stack, global| {
             stack.grant(ptr.tag, item, global)
         })
     }
@@ -585,9 +679,24 @@ trait EvalContextPrivExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
         }
 
         // Compute new borrow.
-        let new_tag = match kind {
-            RefKind::Raw { .. } => Tag::Untagged,
-            _ => Tag::Tagged(this.memory().extra.stacked_borrows.borrow_mut().new_ptr()),
+        let new_tag = {
+            let mut global = this.memory().extra.stacked_borrows.borrow_mut();
+            if global.tree_borrows {
+                // Raw pointers and shared references with interior mutability act like the
+                // pointer they are derived from.
+                let keep_tag = match kind {
+                    RefKind::Raw { .. } => true,
+                    RefKind::Shared => !this.type_is_freeze(place.layout.ty),
+                    RefKind::Unique { .. } => false,
+                };
+                if keep_tag {
+                    return Ok(*val);
+                }
+            }
+            match kind {
+                RefKind::Raw { .. } if !global.tag_raw_pointers => Tag::Untagged,
+                _ => Tag::Tagged(global.new_ptr()),
+            }
         };
 
         // Reborrow.
@@ -0,0 +1,406 @@
+use std::collections::HashMap;
+
+use rustc::ty::layout::Size;
+use rustc::mir::interpret::PointerArithmetic;
+
+use crate::{
+    InterpResult, AllocId, Scalar, Tag, MiriMemoryKind, VaList,
+};
+
+/// The end-of-file indicator of a stream.
+pub const STREAM_EOF: u32 = 1 << 0;
+/// The error indicator of a stream.
+pub const STREAM_ERROR: u32 = 1 << 1;
+
+/// A C `FILE` stream.
+#[derive(Copy, Clone, Debug)]
+pub struct FileStream {
+    /// The file descriptor the stream reads from and writes to.
+    pub(crate) fd: i32,
+    /// The `STREAM_EOF` and `STREAM_ERROR` indicators.
+    pub(crate) flags: u32,
+    /// The buffering mode (`_IOFBF`, `_IOLBF` or `_IONBF`) set by `setvbuf`, if any.
+    /// We never buffer anything ourselves, this is only recorded.
+    pub(crate) buffering: Option<i32>,
+    /// How often `flockfile` has been called on this stream without a matching `funlockfile`.
+    pub(crate) lock_count: u32,
+    /// Whether we already warned about an `_unlocked` function being called on this stream
+    /// without holding its lock.
+    pub(crate) warned_unlocked: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct StdioData {
+    /// The open streams, keyed by the allocation backing their `FILE` object.
+    /// The `FILE` object itself is opaque to the program, so we never look at its contents.
+    pub(crate) streams: HashMap<AllocId, FileStream>,
+}
+
+impl<'mir, 'tcx> EvalContextExt<'mir, 'tcx> for crate::MiriEvalContext<'mir, 'tcx> {}
+pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx> {
+    /// Creates a new stream for `fd` and returns a pointer to its `FILE` object.
+    fn open_stream(&mut self, fd: i32) -> Scalar<Tag> {
+        let this = self.eval_context_mut();
+        let ptr = this.memory_mut().allocate(
+            this.pointer_size(),
+            this.tcx.data_layout.pointer_align.abi,
+            MiriMemoryKind::C.into(),
+        );
+        trace!("New stream {:?} for fd {}", ptr.alloc_id, fd);
+        let stream = FileStream {
+            fd,
+            flags: 0,
+            buffering: None,
+            lock_count: 0,
+            warned_unlocked: false,
+        };
+        this.machine.stdio.streams.insert(ptr.alloc_id, stream);
+        Scalar::Ptr(ptr)
+    }
+
+    /// Closes the stream, returning `false` if `stream` is not an open stream.
+    fn close_stream(&mut self, stream: Scalar<Tag>) -> InterpResult<'tcx, bool> {
+        let this = self.eval_context_mut();
+        if this.get_stream_mut(stream)?.is_none() {
+            return Ok(false);
+        }
+        let ptr = stream.to_ptr()?;
+        this.machine.stdio.streams.remove(&ptr.alloc_id);
+        this.memory_mut().deallocate(ptr, None, MiriMemoryKind::C.into())?;
+        Ok(true)
+    }
+
+    /// Like `get_stream_mut`, but fails if `stream` is not an open stream.
+    fn expect_stream_mut(
+        &mut self,
+        stream: Scalar<Tag>,
+        link_name: &str,
+    ) -> InterpResult<'tcx, &mut FileStream> {
+        let this = self.eval_context_mut();
+        match this.get_stream_mut(stream)? {
+            Some(stream) => Ok(stream),
+            None => err!(MachineError(format!("{} called on a stream that is not open", link_name))),
+        }
+    }
+
+    /// Checks that the program holds the lock of `stream` when calling an `_unlocked` function.
+    /// Not holding it is fine in a single-threaded program, so we only warn once per stream.
+    fn check_stream_locked(&mut self, stream: Scalar<Tag>, link_name: &str) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+        let stream = this.expect_stream_mut(stream, link_name)?;
+        if stream.lock_count == 0 && !stream.warned_unlocked {
+            stream.warned_unlocked = true;
+            this.tcx.sess.warn(&format!("{} called on a stream not locked with `flockfile`", link_name));
+        }
+        Ok(())
+    }
+
+    /// Writes `bytes` to `stream`, setting its error indicator on failure.
+    /// Returns whether all bytes were written.
+    fn write_to_stream(&mut self, stream: Scalar<Tag>, bytes: &[u8], link_name: &str) -> InterpResult<'tcx, bool> {
+        let this = self.eval_context_mut();
+        let stream = this.expect_stream_mut(stream, link_name)?;
+        let res = write_to_host(stream.fd, bytes);
+        if res.is_err() {
+            stream.flags |= STREAM_ERROR;
+        }
+        Ok(res.is_ok())
+    }
+
+    /// Reads from `stream`. Miri does not forward the host's standard input, so all reads
+    /// immediately hit end-of-file; this sets the end-of-file indicator.
+    fn read_from_stream(&mut self, stream: Scalar<Tag>, link_name: &str) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+        this.expect_stream_mut(stream, link_name)?.flags |= STREAM_EOF;
+        Ok(())
+    }
+
+    /// Formats `args` according to the `printf` format string `fmt`.
+    fn format_printf(&mut self, fmt: Scalar<Tag>, mut args: VaList<'_, 'tcx>) -> InterpResult<'tcx, Vec<u8>> {
+        let this = self.eval_context_mut();
+        let tcx = &{this.tcx.tcx};
+        let fmt = fmt.to_ptr()?;
+        let fmt = this.memory().get(fmt.alloc_id)?.read_c_str(tcx, fmt)?.to_owned();
+
+        let mut out = Vec::new();
+        let mut chars = fmt.iter().copied().peekable();
+        while let Some(c) = chars.next() {
+            if c != b'%' {
+                out.push(c);
+                continue;
+            }
+            let mut spec = FormatSpec::default();
+            while let Some(&flag) = chars.peek() {
+                match flag {
+                    b'-' => spec.left = true,
+                    b'+' => spec.plus = true,
+                    b' ' => spec.space = true,
+                    b'#' => spec.alternate = true,
+                    b'0' => spec.zero = true,
+                    _ => break,
+                }
+                chars.next();
+            }
+            if chars.peek() == Some(&b'*') {
+                chars.next();
+                let width = args.read_int(this)?;
+                // A negative width is taken as a `-` flag followed by a positive width.
+                spec.left |= width < 0;
+                spec.width = width.abs() as usize;
+            } else {
+                while let Some(digit @ b'0'..=b'9') = chars.peek().copied() {
+                    spec.width = spec.width * 10 + (digit - b'0') as usize;
+                    chars.next();
+                }
+            }
+            if chars.peek() == Some(&b'.') {
+                chars.next();
+                if chars.peek() == Some(&b'*') {
+                    chars.next();
+                    // A negative precision is taken as if the precision were omitted.
+                    let precision = args.read_int(this)?;
+                    spec.precision = if precision < 0 { None } else { Some(precision as usize) };
+                } else {
+                    let mut precision = 0;
+                    while let Some(digit @ b'0'..=b'9') = chars.peek().copied() {
+                        precision = precision * 10 + (digit - b'0') as usize;
+                        chars.next();
+                    }
+                    spec.precision = Some(precision);
+                }
+            }
+            // The length modifier tells us how many bytes of an integer argument to use.
+            // Arguments smaller than `int` got promoted to `int` by the caller.
+            let mut int_size = Some(4);
+            while let Some(&modifier) = chars.peek() {
+                match modifier {
+                    b'h' => int_size = Some(if int_size == Some(2) { 1 } else { 2 }),
+                    // The argument already has the right size.
+                    b'l' | b'q' | b'j' | b'z' | b't' => int_size = None,
+                    b'L' => return err!(Unimplemented(
+                        "printf does not support `long double` arguments".to_string(),
+                    )),
+                    _ => break,
+                }
+                chars.next();
+            }
+            let conversion = match chars.next() {
+                Some(conversion) => conversion,
+                None => return err!(MachineError("printf format string ends in a `%`".to_string())),
+            };
+            let formatted = match conversion {
+                b'%' => b"%".to_vec(),
+                b'd' | b'i' | b'u' | b'x' | b'X' | b'o' => {
+                    let arg = args.next_arg()?;
+                    let bits = this.read_scalar(arg)?.not_undef()?;
+                    let bits = this.force_bits(bits, arg.layout.size)?;
+                    let size = int_size.unwrap_or(arg.layout.size.bytes() as usize);
+                    let bits = bits & (u128::max_value() >> (128 - 8 * size));
+                    let (negative, magnitude) = if conversion == b'd' || conversion == b'i' {
+                        let shift = 128 - 8 * size;
+                        let value = ((bits << shift) as i128) >> shift;
+                        (value < 0, value.wrapping_abs() as u128)
+                    } else {
+                        (false, bits)
+                    };
+                    spec.format_int(conversion, negative, magnitude)
+                }
+                b'c' => {
+                    let c = args.read_int(this)? as u8;
+                    spec.pad(Vec::new(), vec![c], false)
+                }
+                b's' => {
+                    let ptr = args.read_scalar(this)?;
+                    let mut s = if ptr.is_null_ptr(this) {
+                        b"(null)".to_vec()
+                    } else {
+                        let ptr = ptr.to_ptr()?;
+                        this.memory().get(ptr.alloc_id)?.read_c_str(tcx, ptr)?.to_owned()
+                    };
+                    if let Some(precision) = spec.precision {
+                        s.truncate(precision);
+                    }
+                    spec.pad(Vec::new(), s, false)
+                }
+                b'p' => {
+                    let ptr = args.read_scalar(this)?;
+                    if ptr.is_null_ptr(this) {
+                        spec.pad(Vec::new(), b"(nil)".to_vec(), false)
+                    } else {
+                        let addr = this.force_bits(ptr, this.pointer_size())?;
+                        spec.alternate = true;
+                        spec.format_int(b'x', false, addr)
+                    }
+                }
+                b'f' | b'F' | b'e' | b'E' | b'g' | b'G' => {
+                    // FIXME: Using host floats.
+                    let f = args.read_double(this)?;
+                    spec.format_float(conversion, f)
+                }
+                _ => return err!(Unimplemented(format!(
+                    "printf conversion specifier `%{}` is not supported",
+                    conversion as char,
+                ))),
+            };
+            out.extend(formatted);
+        }
+        Ok(out)
+    }
+
+    /// Looks up the stream whose `FILE` object `stream` points to.
+    fn get_stream_mut(&mut self, stream: Scalar<Tag>) -> InterpResult<'tcx, Option<&mut FileStream>> {
+        let this = self.eval_context_mut();
+        Ok(match stream {
+            Scalar::Ptr(ptr) if ptr.offset == Size::ZERO =>
+                this.machine.stdio.streams.get_mut(&ptr.alloc_id),
+            _ => None,
+        })
+    }
+}
+
+/// Writes `bytes` to the host's standard output (`fd == 1`) or standard error (`fd == 2`).
+pub(crate) fn write_to_host(fd: i32, bytes: &[u8]) -> std::io::Result<()> {
+    use std::io::{self, Write};
+
+    // Like `write`, we want this to correspond to a write on the host, so we do not buffer.
+    match fd {
+        1 => io::stdout().write_all(bytes).and_then(|()| io::stdout().flush()),
+        2 => io::stderr().write_all(bytes),
+        _ => Err(io::ErrorKind::PermissionDenied.into()),
+    }
+}
+
+/// A parsed `printf` conversion specification.
+#[derive(Default)]
+struct FormatSpec {
+    left: bool,
+    plus: bool,
+    space: bool,
+    alternate: bool,
+    zero: bool,
+    width: usize,
+    precision: Option<usize>,
+}
+
+impl FormatSpec {
+    /// Pads `body` (which follows the sign and base `prefix`) to the field width.
+    /// `numeric` enables padding with zeros.
+    fn pad(&self, mut prefix: Vec<u8>, body: Vec<u8>, numeric: bool) -> Vec<u8> {
+        let len = prefix.len() + body.len();
+        if len >= self.width {
+            prefix.extend(body);
+            return prefix;
+        }
+        let fill = self.width - len;
+        if self.left {
+            prefix.extend(body);
+            prefix.extend(std::iter::repeat(b' ').take(fill));
+            prefix
+        } else if self.zero && numeric {
+            prefix.extend(std::iter::repeat(b'0').take(fill));
+            prefix.extend(body);
+            prefix
+        } else {
+            let mut out = vec![b' '; fill];
+            out.extend(prefix);
+            out.extend(body);
+            out
+        }
+    }
+
+    fn sign(&self, negative: bool) -> Vec<u8> {
+        if negative {
+            b"-".to_vec()
+        } else if self.plus {
+            b"+".to_vec()
+        } else if self.space {
+            b" ".to_vec()
+        } else {
+            Vec::new()
+        }
+    }
+
+    fn format_int(&self, conversion: u8, negative: bool, magnitude: u128) -> Vec<u8> {
+        let mut digits = match conversion {
+            b'x' => format!("{:x}", magnitude),
+            b'X' => format!("{:X}", magnitude),
+            b'o' => format!("{:o}", magnitude),
+            _ => magnitude.to_string(),
+        }.into_bytes();
+        let mut prefix = self.sign(negative);
+        match self.precision {
+            Some(0) if magnitude == 0 => digits.clear(),
+            Some(precision) if precision > digits.len() => {
+                let mut padded = vec![b'0'; precision - digits.len()];
+                padded.extend(digits);
+                digits = padded;
+            }
+            _ => {}
+        }
+        if self.alternate {
+            match conversion {
+                b'o' if digits.first() != Some(&b'0') => digits.insert(0, b'0'),
+                b'x' if magnitude != 0 => prefix.extend(b"0x"),
+                b'X' if magnitude != 0 => prefix.extend(b"0X"),
+                _ => {}
+            }
+        }
+        // With a precision, the `0` flag is ignored for integers.
+        self.pad(prefix, digits, self.precision.is_none())
+    }
+
+    fn format_float(&self, conversion: u8, f: f64) -> Vec<u8> {
+        let upper = conversion.is_ascii_uppercase();
+        // Like glibc, we print the sign of NaNs.
+        let prefix = self.sign(f.is_sign_negative());
+        if !f.is_finite() {
+            let body = if f.is_nan() { "nan" } else { "inf" };
+            let body = if upper { body.to_ascii_uppercase() } else { body.to_string() };
+            return self.pad(prefix, body.into_bytes(), false);
+        }
+        let f = f.abs();
+        let precision = self.precision.unwrap_or(6);
+        let body = match conversion.to_ascii_lowercase() {
+            b'f' => format!("{:.*}", precision, f),
+            b'e' => format_exp(f, precision),
+            _ => {
+                // `%g` uses the style of `%e` if the exponent is less than -4 or at least
+                // the precision, and the style of `%f` otherwise.
+                let precision = precision.max(1);
+                let exp = if f == 0.0 {
+                    0
+                } else {
+                    let e = format!("{:.*e}", precision - 1, f);
+                    e[e.find('e').unwrap() + 1..].parse::<i32>().unwrap()
+                };
+                let mut body = if exp < -4 || exp >= precision as i32 {
+                    format_exp(f, precision - 1)
+                } else {
+                    format!("{:.*}", (precision as i32 - 1 - exp) as usize, f)
+                };
+                if !self.alternate && body.contains('.') {
+                    // Remove trailing zeros of the fraction.
+                    let exp_start = body.find('e').unwrap_or(body.len());
+                    let (mantissa, exp) = body.split_at(exp_start);
+                    let mantissa = mantissa.trim_end_matches('0').trim_end_matches('.');
+                    body = format!("{}{}", mantissa, exp);
+                }
+                body
+            }
+        };
+        let mut body = if upper { body.to_ascii_uppercase() } else { body };
+        if self.alternate && !body.contains('.') {
+            let exp_start = body.find(|c| c == 'e' || c == 'E').unwrap_or(body.len());
+            body.insert(exp_start, '.');
+        }
+        self.pad(prefix, body.into_bytes(), true)
+    }
+}
+
+/// Formats `f` like `%.<precision>e`: Rust uses `1.5e0` where C uses `1.5e+00`.
+fn format_exp(f: f64, precision: usize) -> String {
+    let s = format!("{:.*e}", precision, f);
+    let (mantissa, exp) = s.split_at(s.find('e').unwrap());
+    let exp: i32 = exp[1..].parse().unwrap();
+    format!("{}e{}{:02}", mantissa, if exp < 0 { '-' } else { '+' }, exp.abs())
+}
@@ -23,6 +23,9 @@ pub struct TlsData<'tcx> {
 
     /// pthreads-style thread-local storage.
     pub(crate) keys: BTreeMap<TlsKey, TlsEntry<'tcx>>,
+
+    /// Destructors registered with `_tlv_atexit` on macOS, with their argument.
+    pub(crate) macos_thread_dtors: Vec<(ty::Instance<'tcx>, Scalar<Tag>)>,
 }
 
 impl<'tcx> Default for TlsData<'tcx> {
@@ -30,6 +33,7 @@ impl<'tcx> Default for TlsData<'tcx> {
         TlsData {
             next_key: 1, // start with 1 as we must not use 0 on Windows
             keys: Default::default(),
+            macos_thread_dtors: Default::default(),
         }
     }
 }
@@ -136,33 +140,44 @@ pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx
         let mut dtor = this.machine.tls.fetch_tls_dtor(None, &*this.tcx);
         // FIXME: replace loop by some structure that works with stepping
         while let Some((instance, ptr, key)) = dtor {
-            trace!("Running TLS dtor {:?} on {:?}", instance, ptr);
-            // TODO: Potentially, this has to support all the other possible instances?
-            // See eval_fn_call in interpret/terminator/mod.rs
-            let mir = this.load_mir(instance.def)?;
-            let ret_place = MPlaceTy::dangling(this.layout_of(this.tcx.mk_unit())?, this).into();
-            this.push_stack_frame(
-                instance,
-                mir.span,
-                mir,
-                Some(ret_place),
-                StackPopCleanup::None { cleanup: true },
-            )?;
-            let arg_local = this.frame().body.args_iter().next().ok_or_else(
-                || InterpError::AbiViolation("TLS dtor does not take enough arguments.".to_owned()),
-            )?;
-            let dest = this.eval_place(&mir::Place::Base(mir::PlaceBase::Local(arg_local)))?;
-            this.write_scalar(ptr, dest)?;
-
-            // step until out of stackframes
-            this.run()?;
+            this.call_tls_dtor(instance, ptr)?;
 
             dtor = match this.machine.tls.fetch_tls_dtor(Some(key), &*this.tcx) {
                 dtor @ Some(_) => dtor,
                 None => this.machine.tls.fetch_tls_dtor(None, &*this.tcx),
             };
         }
+        // The macOS destructors run in reverse order of registration. They may register
+        // more destructors, which we then also run.
+        while let Some((instance, data)) = this.machine.tls.macos_thread_dtors.pop() {
+            this.call_tls_dtor(instance, data)?;
+        }
         // FIXME: On a windows target, call `unsafe extern "system" fn on_tls_callback`.
         Ok(())
     }
+
+    /// Runs the TLS destructor `instance` with argument `ptr` to completion.
+    fn call_tls_dtor(&mut self, instance: ty::Instance<'tcx>, ptr: Scalar<Tag>) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+        trace!("Running TLS dtor {:?} on {:?}", instance, ptr);
+        // TODO: Potentially, this has to support all the other possible instances?
+        // See eval_fn_call in interpret/terminator/mod.rs
+        let mir = this.load_mir(instance.def)?;
+        let ret_place = MPlaceTy::dangling(this.layout_of(this.tcx.mk_unit())?, this).into();
+        this.push_stack_frame(
+            instance,
+            mir.span,
+            mir,
+            Some(ret_place),
+            StackPopCleanup::None { cleanup: true },
+        )?;
+        let arg_local = this.frame().body.args_iter().next().ok_or_else(
+            || InterpError::AbiViolation("TLS dtor does not take enough arguments.".to_owned()),
+        )?;
+        let dest = this.eval_place(&mir::Place::Base(mir::PlaceBase::Local(arg_local)))?;
+        this.write_scalar(ptr, dest)?;
+
+        // step until out of stackframes
+        this.run_recording_errors()
+    }
 }
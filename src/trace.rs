@@ -0,0 +1,65 @@
+//! Exporting the interpreted program's calls in the Chrome trace event format, for
+//! `-Zmiri-trace`. The result can be loaded into `chrome://tracing` or Perfetto.
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+
+use crate::*;
+use crate::memory_dump::escape_json;
+use crate::stacked_borrows::CallId;
+
+pub struct ChromeTrace {
+    out: BufWriter<File>,
+    /// The frames we opened an event for and did not close yet, outermost first.
+    open: Vec<CallId>,
+    /// Whether we already wrote an event, which the next one needs to be separated from.
+    wrote_event: bool,
+}
+
+impl ChromeTrace {
+    pub fn create(path: &str) -> io::Result<Self> {
+        let mut out = BufWriter::new(File::create(path)?);
+        out.write_all(b"[\n")?;
+        Ok(ChromeTrace { out, open: Vec::new(), wrote_event: false })
+    }
+
+    /// Emits the events for all frames that were pushed or popped since the last call.
+    /// The timestamps come from our fake clock, so one "microsecond" is one terminator.
+    pub fn sync<'mir, 'tcx>(
+        &mut self,
+        stack: &[Frame<'mir, 'tcx, Tag, CallId>],
+        clock: u64,
+    ) -> io::Result<()> {
+        let common = self.open.iter().zip(stack)
+            .take_while(|&(&open, frame)| open == frame.extra)
+            .count();
+        while self.open.len() > common {
+            self.open.pop();
+            self.event(format!("{{\"ph\":\"E\",\"ts\":{},\"pid\":0,\"tid\":0}}", clock))?;
+        }
+        for frame in &stack[common..] {
+            let name = escape_json(&frame.instance.to_string());
+            self.event(format!(
+                "{{\"name\":\"{0}\",\"cat\":\"{0}\",\"ph\":\"B\",\"ts\":{1},\"pid\":0,\"tid\":0}}",
+                name, clock,
+            ))?;
+            self.open.push(frame.extra);
+        }
+        Ok(())
+    }
+
+    /// Closes all open events and finishes the file.
+    pub fn finish(mut self, clock: u64) -> io::Result<()> {
+        self.sync(&[], clock)?;
+        self.out.write_all(b"\n]\n")?;
+        self.out.flush()
+    }
+
+    fn event(&mut self, event: String) -> io::Result<()> {
+        if self.wrote_event {
+            self.out.write_all(b",\n")?;
+        }
+        self.wrote_event = true;
+        self.out.write_all(event.as_bytes())
+    }
+}
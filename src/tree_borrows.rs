@@ -0,0 +1,225 @@
+//! The Tree Borrows aliasing model (by Neven Villani and Ralf Jung), which `-Zmiri-tree-borrows`
+//! uses instead of Stacked Borrows.
+//!
+//! Instead of a stack per location, every allocation has a tree of the tags derived from its
+//! base pointer, and every tag has a permission at every location. An access through a tag is
+//! *local* to that tag and its ancestors, and *foreign* to all other tags. It changes their
+//! permissions, and it is undefined behavior if one of the permissions does not allow it.
+//!
+//! Compared to the full model, we simplify a few things:
+//! * Raw pointers and shared references to types with interior mutability do not get their own
+//!   tag, they act like the pointer they are derived from. Untagged pointers, like the ones cast
+//!   from integers, act like the base pointer.
+//! * The permission of a tag covers the entire allocation, not just the range of its pointee.
+//! * Protected `Reserved` tags do not remember that they saw a foreign read.
+
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+
+use rustc::ty::layout::Size;
+
+use crate::{InterpResult, InterpError, RangeMap, Pointer};
+use crate::stacked_borrows::{Tag, CallId, AccessKind, GlobalState, MemoryExtra};
+
+/// The permission of a tag at one location.
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+pub enum TreePermission {
+    /// A mutable reference that was not written to yet. It allows foreign reads, and with
+    /// interior mutability also foreign writes.
+    Reserved { interior_mut: bool },
+    /// A mutable reference that was written to.
+    Active,
+    /// A shared reference, or a mutable reference after a foreign read.
+    Frozen,
+    /// A reference that cannot be used any more.
+    Disabled,
+}
+
+impl TreePermission {
+    /// Returns the permission after an access that is local or foreign to the tag, or `None`
+    /// if the tag does not allow the access.
+    fn perform_access(self, access: AccessKind, local: bool) -> Option<TreePermission> {
+        use TreePermission::*;
+        match (self, access, local) {
+            (Disabled, _, true) => None,
+            (Frozen, AccessKind::Write, true) => None,
+            (Reserved { .. }, AccessKind::Write, true) => Some(Active),
+            (perm, _, true) => Some(perm),
+            (Active, AccessKind::Read, false) => Some(Frozen),
+            (perm, AccessKind::Read, false) => Some(perm),
+            (Reserved { interior_mut: true }, AccessKind::Write, false) => Some(self),
+            (_, AccessKind::Write, false) => Some(Disabled),
+        }
+    }
+}
+
+/// A tag in the tree.
+#[derive(Clone, Debug)]
+struct Node {
+    /// The tag this one was derived from, `None` for the base tag.
+    parent: Option<Tag>,
+    /// The call that the tag is protected for.
+    protector: Option<CallId>,
+    /// The permission at the locations that do not store a permission for the tag.
+    initial: TreePermission,
+}
+
+/// Extra per-allocation state.
+#[derive(Clone, Debug)]
+pub struct Tree {
+    /// The tag of the base pointer, which is the root of the tree.
+    root: Tag,
+    nodes: RefCell<HashMap<Tag, Node>>,
+    /// For every location, the permissions that differ from the initial one of their tag.
+    perms: RefCell<RangeMap<HashMap<Tag, TreePermission>>>,
+    // Pointer to global state
+    global: MemoryExtra,
+}
+
+impl<'tcx> Tree {
+    /// Creates a new tree with just the base tag, which may do anything.
+    pub fn new(size: Size, root: Tag, extra: MemoryExtra) -> Self {
+        let mut nodes = HashMap::new();
+        nodes.insert(root, Node { parent: None, protector: None, initial: TreePermission::Active });
+        Tree {
+            root,
+            nodes: RefCell::new(nodes),
+            perms: RefCell::new(RangeMap::new(size, HashMap::new())),
+            global: extra,
+        }
+    }
+
+    /// Derives `new_tag` from the tag of `ptr`, and performs the read access that a retag
+    /// implies on the `size` bytes at `ptr`.
+    pub fn reborrow(
+        &self,
+        ptr: Pointer<Tag>,
+        size: Size,
+        new_tag: Tag,
+        perm: TreePermission,
+        protector: Option<CallId>,
+    ) -> InterpResult<'tcx> {
+        {
+            let mut nodes = self.nodes.borrow_mut();
+            let parent = if nodes.contains_key(&ptr.tag) { ptr.tag } else { self.root };
+            nodes.insert(new_tag, Node { parent: Some(parent), protector, initial: perm });
+        }
+        self.access(AccessKind::Read, new_tag, ptr.offset, size, /*dealloc:*/ false)
+    }
+
+    /// Returns `tag` and its ancestors, the tags that an access through `tag` is local to.
+    fn local_tags(&self, nodes: &HashMap<Tag, Node>, tag: Tag) -> HashSet<Tag> {
+        let mut local = HashSet::new();
+        // Tags that are not in the tree act like the base pointer.
+        let mut cur = Some(if nodes.contains_key(&tag) { tag } else { self.root });
+        while let Some(tag) = cur {
+            local.insert(tag);
+            cur = nodes[&tag].parent;
+        }
+        local
+    }
+
+    /// Performs an access through `tag` on every location in the range.
+    /// If the checks are disabled, violations are recorded, and we go on with the next location.
+    fn access(
+        &self,
+        access: AccessKind,
+        tag: Tag,
+        offset: Size,
+        size: Size,
+        dealloc: bool,
+    ) -> InterpResult<'tcx> {
+        let mut global = self.global.borrow_mut();
+        let nodes = self.nodes.borrow();
+        let local = self.local_tags(&nodes, tag);
+        let mut perms = self.perms.borrow_mut();
+        for location in perms.iter_mut(offset, size) {
+            if let Err(err) = Tree::access_location(location, &nodes, &local, access, tag, dealloc, &*global) {
+                match global.suppressed_violations {
+                    Some(ref mut violations) => violations.push(err.kind.to_string()),
+                    None => return Err(err),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Updates the permissions at one location for an access through `tag`. Nothing changes
+    /// if the access is not allowed.
+    fn access_location(
+        location: &mut HashMap<Tag, TreePermission>,
+        nodes: &HashMap<Tag, Node>,
+        local: &HashSet<Tag>,
+        access: AccessKind,
+        tag: Tag,
+        dealloc: bool,
+        global: &GlobalState,
+    ) -> InterpResult<'tcx> {
+        let mut updates = Vec::new();
+        for (&node_tag, node) in nodes.iter() {
+            let perm = location.get(&node_tag).copied().unwrap_or(node.initial);
+            let is_local = local.contains(&node_tag);
+            let protected = node.protector.map_or(false, |call| global.is_active(call));
+            if dealloc && protected {
+                return err!(MachineError(format!(
+                    "deallocating while tag {:?} is protected", node_tag,
+                )));
+            }
+            let new_perm = perm.perform_access(access, is_local)
+                .ok_or_else(|| InterpError::MachineError(format!(
+                    "{} through tag {:?} is not allowed because tag {:?} has permission {:?}",
+                    access, tag, node_tag, perm,
+                )))?;
+            // Protected tags must stay usable until their call returns.
+            if protected && (new_perm == TreePermission::Disabled
+                || (perm == TreePermission::Active && new_perm == TreePermission::Frozen))
+            {
+                return err!(MachineError(format!(
+                    "{} through tag {:?} would make protected tag {:?} {:?}",
+                    access, tag, node_tag, new_perm,
+                )));
+            }
+            if new_perm != perm {
+                updates.push((node_tag, new_perm));
+            }
+        }
+        for (node_tag, perm) in updates {
+            if perm == nodes[&node_tag].initial {
+                location.remove(&node_tag);
+            } else {
+                location.insert(node_tag, perm);
+            }
+        }
+        Ok(())
+    }
+
+    #[inline(always)]
+    pub fn memory_read(
+        &self,
+        ptr: Pointer<Tag>,
+        size: Size,
+    ) -> InterpResult<'tcx> {
+        trace!("read access with tag {:?}: {:?}, size {}", ptr.tag, ptr.erase_tag(), size.bytes());
+        self.access(AccessKind::Read, ptr.tag, ptr.offset, size, /*dealloc:*/ false)
+    }
+
+    #[inline(always)]
+    pub fn memory_written(
+        &mut self,
+        ptr: Pointer<Tag>,
+        size: Size,
+    ) -> InterpResult<'tcx> {
+        trace!("write access with tag {:?}: {:?}, size {}", ptr.tag, ptr.erase_tag(), size.bytes());
+        self.access(AccessKind::Write, ptr.tag, ptr.offset, size, /*dealloc:*/ false)
+    }
+
+    #[inline(always)]
+    pub fn memory_deallocated(
+        &mut self,
+        ptr: Pointer<Tag>,
+        size: Size,
+    ) -> InterpResult<'tcx> {
+        trace!("deallocation with tag {:?}: {:?}, size {}", ptr.tag, ptr.erase_tag(), size.bytes());
+        self.access(AccessKind::Write, ptr.tag, ptr.offset, size, /*dealloc:*/ true)
+    }
+}
@@ -0,0 +1,91 @@
+//! Memory watchpoints set by the interpreted program with `__miri_watch`.
+//!
+//! The memory access hooks do not have access to the interpreter, so they only record which
+//! watchpoints were hit. `report_watchpoint_hits` prints them with a stack trace before the
+//! next terminator is executed.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use rustc::ty::layout::Size;
+
+use crate::*;
+
+/// Report reads of the watched memory.
+pub const WATCH_READ: u8 = 1;
+/// Report writes to the watched memory.
+pub const WATCH_WRITE: u8 = 2;
+
+#[derive(Copy, Clone, Debug)]
+struct Watchpoint {
+    alloc_id: AllocId,
+    offset: Size,
+    size: Size,
+    mode: u8,
+}
+
+/// An access to watched memory that was not reported yet.
+#[derive(Copy, Clone, Debug)]
+struct Hit {
+    write: bool,
+    ptr: Pointer<Tag>,
+    size: Size,
+}
+
+#[derive(Default, Debug)]
+pub struct GlobalState {
+    watchpoints: Vec<Watchpoint>,
+    hits: Vec<Hit>,
+}
+
+/// Memory extra state gives us interior mutable access to the global state.
+pub type MemoryExtra = Rc<RefCell<GlobalState>>;
+
+impl GlobalState {
+    pub fn watch(&mut self, ptr: Pointer<Tag>, size: Size, mode: u8) {
+        self.watchpoints.push(Watchpoint { alloc_id: ptr.alloc_id, offset: ptr.offset, size, mode });
+    }
+
+    /// Records the access if it overlaps with a watchpoint for this kind of access.
+    pub fn access(&mut self, ptr: Pointer<Tag>, size: Size, write: bool) {
+        let mode = if write { WATCH_WRITE } else { WATCH_READ };
+        let hit = self.watchpoints.iter().any(|watchpoint| {
+            watchpoint.alloc_id == ptr.alloc_id
+                && watchpoint.mode & mode != 0
+                && watchpoint.offset < ptr.offset + size
+                && ptr.offset < watchpoint.offset + watchpoint.size
+        });
+        if hit {
+            self.hits.push(Hit { write, ptr, size });
+        }
+    }
+}
+
+impl<'mir, 'tcx> EvalContextExt<'mir, 'tcx> for crate::MiriEvalContext<'mir, 'tcx> {}
+pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriEvalContextExt<'mir, 'tcx> {
+    /// Reports all accesses of the given kinds (`WATCH_READ` and/or `WATCH_WRITE`) to the
+    /// `size` bytes at `ptr`.
+    fn watch_address(&mut self, ptr: Pointer<Tag>, size: Size, mode: u8) {
+        let this = self.eval_context_mut();
+        this.memory().extra.watchpoints.borrow_mut().watch(ptr, size, mode);
+    }
+
+    /// Prints the watchpoint hits since the last call, with the current stack trace.
+    fn report_watchpoint_hits(&mut self) {
+        let this = self.eval_context_mut();
+        let hits = std::mem::replace(&mut this.memory().extra.watchpoints.borrow_mut().hits, Vec::new());
+        for hit in hits {
+            let msg = format!(
+                "watchpoint: {} of {} bytes at {:?}",
+                if hit.write { "write" } else { "read" },
+                hit.size.bytes(),
+                hit.ptr.erase_tag(),
+            );
+            let mut diag = this.tcx.sess.struct_span_warn(this.current_span(), &msg);
+            for frame in this.generate_stacktrace(None) {
+                diag.note(&frame.to_string());
+            }
+            diag.emit();
+        }
+    }
+}
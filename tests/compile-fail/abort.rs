@@ -0,0 +1,17 @@
+extern "Rust" {
+    fn __miri_set_abort_handler(handler: Option<extern fn()>);
+}
+
+extern {
+    fn abort() -> !;
+}
+
+extern fn handler() {}
+
+fn main() {
+    unsafe {
+        __miri_set_abort_handler(Some(handler));
+        // The handler returns, so this aborts after all.
+        abort(); //~ ERROR abort called
+    }
+}
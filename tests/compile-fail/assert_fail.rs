@@ -0,0 +1,22 @@
+// ignore-macos: Uses glibc's `__assert_fail`
+// ignore-windows: Uses glibc's `__assert_fail`
+
+#![feature(rustc_private)]
+extern crate libc;
+
+use libc::{c_char, c_uint};
+
+extern {
+    fn __assert_fail(expr: *const c_char, file: *const c_char, line: c_uint, func: *const c_char) -> !;
+}
+
+fn main() {
+    unsafe {
+        __assert_fail( //~ ERROR assertion failed: x > 0 at test.c:42 in main
+            b"x > 0\0".as_ptr() as *const c_char,
+            b"test.c\0".as_ptr() as *const c_char,
+            42,
+            b"main\0".as_ptr() as *const c_char,
+        );
+    }
+}
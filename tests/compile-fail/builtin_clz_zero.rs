@@ -0,0 +1,11 @@
+use std::os::raw::{c_int, c_uint};
+
+extern {
+    fn __builtin_clz(x: c_uint) -> c_int;
+}
+
+fn main() {
+    unsafe {
+        __builtin_clz(0); //~ ERROR ctlz_nonzero called on 0
+    }
+}
@@ -0,0 +1,11 @@
+use std::os::raw::{c_int, c_uint};
+
+extern {
+    fn __builtin_ctz(x: c_uint) -> c_int;
+}
+
+fn main() {
+    unsafe {
+        __builtin_ctz(0); //~ ERROR cttz_nonzero called on 0
+    }
+}
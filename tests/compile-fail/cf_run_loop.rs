@@ -0,0 +1,17 @@
+use std::os::raw::c_void;
+
+extern {
+    fn CFRetain(cf: *const c_void) -> *const c_void;
+    fn CFRelease(cf: *const c_void);
+    fn CFRunLoopRun();
+}
+
+fn main() {
+    let x = 0u8;
+    let cf = &x as *const u8 as *const c_void;
+    unsafe {
+        assert_eq!(CFRetain(cf), cf);
+        CFRelease(cf);
+        CFRunLoopRun(); //~ ERROR CFRunLoop not supported in miri
+    }
+}
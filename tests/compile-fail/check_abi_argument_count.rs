@@ -0,0 +1,11 @@
+// compile-flags: --check-abi
+
+extern "C" {
+    // `memset` takes a length, too.
+    fn memset(s: *mut u8, c: i32) -> *mut u8;
+}
+
+fn main() {
+    let mut buf = [0u8; 4];
+    unsafe { memset(buf.as_mut_ptr(), 1) }; //~ ERROR calling `memset` with 2 arguments, but it takes 3
+}
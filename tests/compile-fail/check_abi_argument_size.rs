@@ -0,0 +1,10 @@
+// compile-flags: --check-abi
+
+extern "C" {
+    // `malloc` takes a `size_t`, not a `u8`.
+    fn malloc(size: u8) -> *mut u8;
+}
+
+fn main() {
+    unsafe { malloc(8) }; //~ ERROR argument 1 of `malloc` should have type `size_t`
+}
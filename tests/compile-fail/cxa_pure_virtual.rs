@@ -0,0 +1,7 @@
+extern {
+    fn __cxa_pure_virtual() -> !;
+}
+
+fn main() {
+    unsafe { __cxa_pure_virtual() }; //~ ERROR pure virtual function called
+}
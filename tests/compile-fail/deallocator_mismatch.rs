@@ -0,0 +1,15 @@
+#![feature(rustc_private)]
+#![allow(unused_extern_crates)] // rustc bug https://github.com/rust-lang/rust/issues/56098
+
+extern crate libc;
+
+use std::alloc::*;
+
+// error-pattern: tried to deallocate Machine(C) memory but gave Machine(Rust) as the kind
+
+fn main() {
+    unsafe {
+        let x = libc::malloc(1) as *mut u8;
+        dealloc(x, Layout::from_size_align_unchecked(1, 1));
+    }
+}
@@ -0,0 +1,15 @@
+use std::os::raw::c_int;
+
+#[repr(C)]
+struct DivT {
+    quot: c_int,
+    rem: c_int,
+}
+
+extern "C" {
+    fn div(numer: c_int, denom: c_int) -> DivT;
+}
+
+fn main() {
+    unsafe { div(1, 0) }; //~ ERROR attempt to divide with overflow
+}
@@ -0,0 +1,14 @@
+//ignore-windows: Uses POSIX APIs
+
+#![feature(rustc_private)]
+extern crate libc;
+
+use libc::c_void;
+
+fn main() {
+    let mut buf = [0u8; 8];
+    let ptr = buf.as_mut_ptr();
+    unsafe {
+        libc::memcpy(ptr.add(2) as *mut c_void, ptr as *const c_void, 4); //~ ERROR memcpy called with overlapping buffers
+    }
+}
@@ -0,0 +1,19 @@
+// ignore-macos: Uses glibc's `__memset_chk`
+// ignore-windows: Uses glibc's `__memset_chk`
+
+#![feature(rustc_private)]
+extern crate libc;
+
+use libc::{c_int, c_void, size_t};
+
+extern {
+    fn __memset_chk(dest: *mut c_void, c: c_int, n: size_t, dest_len: size_t) -> *mut c_void;
+}
+
+fn main() {
+    let mut buf = [0u8; 16];
+    unsafe {
+        // The buffer is big enough, but the compiler thought otherwise.
+        __memset_chk(buf.as_mut_ptr() as *mut c_void, 0, 16, 8); //~ ERROR buffer overflow detected by __memset_chk
+    }
+}
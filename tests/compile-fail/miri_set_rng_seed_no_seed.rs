@@ -0,0 +1,7 @@
+extern "Rust" {
+    fn __miri_set_rng_seed(seed: u64);
+}
+
+fn main() {
+    unsafe { __miri_set_rng_seed(42) }; //~ ERROR `__miri_set_rng_seed` requires `-Zmiri-seed`
+}
@@ -0,0 +1,8 @@
+extern "Rust" {
+    fn __miri_watch(ptr: *const u8, size: usize, mode: u32);
+}
+
+fn main() {
+    let data = [0u8; 8];
+    unsafe { __miri_watch(data.as_ptr(), 8, 4) }; //~ ERROR __miri_watch: invalid mode 4
+}
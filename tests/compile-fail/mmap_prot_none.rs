@@ -0,0 +1,14 @@
+//ignore-windows: Uses POSIX APIs
+
+#![feature(rustc_private)]
+#![allow(unused_extern_crates)] // rustc bug https://github.com/rust-lang/rust/issues/56098
+
+extern crate libc;
+
+fn main() {
+    unsafe {
+        let flags = libc::MAP_PRIVATE | libc::MAP_ANONYMOUS;
+        let ptr = libc::mmap(std::ptr::null_mut(), 4096, libc::PROT_NONE, flags, -1, 0);
+        let _val = *(ptr as *const u8); //~ ERROR read from memory mapped with PROT_NONE
+    }
+}
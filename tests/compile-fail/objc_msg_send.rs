@@ -0,0 +1,11 @@
+use std::os::raw::{c_char, c_void};
+
+extern {
+    fn sel_registerName(name: *const c_char) -> *mut c_void;
+}
+
+fn main() {
+    unsafe {
+        sel_registerName(b"alloc\0".as_ptr() as *const c_char); //~ ERROR Objective-C runtime calls are not supported in miri
+    }
+}
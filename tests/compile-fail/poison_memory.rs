@@ -0,0 +1,12 @@
+extern "Rust" {
+    fn __miri_poison_memory(ptr: *mut u8, size: usize);
+}
+
+fn main() {
+    let mut data = [1u8, 2, 3, 4];
+    unsafe { __miri_poison_memory(data[1..].as_mut_ptr(), 2) };
+    // The bytes before and after the poisoned ones are still initialized.
+    assert_eq!(data[0] + data[3], 5);
+    let x = data[2]; //~ ERROR attempted to read undefined bytes
+    panic!("this should never print: {}", x);
+}
@@ -0,0 +1,12 @@
+//ignore-windows: Uses POSIX APIs
+
+use std::os::raw::{c_char, c_int};
+
+extern "C" {
+    fn printf(fmt: *const c_char, ...) -> c_int;
+}
+
+fn main() {
+    let fmt = b"%d and %d\n\0";
+    unsafe { printf(fmt.as_ptr() as *const c_char, 1 as c_int) }; //~ ERROR `printf` requires more arguments than were passed
+}
@@ -0,0 +1,17 @@
+extern "Rust" {
+    fn __miri_disable_stacked_borrows();
+    fn __miri_enable_stacked_borrows();
+}
+
+fn main() {
+    let mut x = 0;
+    let raw = &mut x as *mut i32;
+    let xref = unsafe { &mut *raw };
+    unsafe {
+        __miri_disable_stacked_borrows();
+        *raw = 1; // this invalidates `xref`
+        *xref = 2; // not reported as an error
+        __miri_enable_stacked_borrows(); //~ WARN Stacked Borrows violation while the checks were disabled
+    }
+    *xref = 3; //~ ERROR borrow stack
+}
@@ -0,0 +1,11 @@
+// compile-flags: --tag-raw-pointers
+
+fn main() {
+    let mut target = Box::new(0u8);
+    let xref = &mut *target;
+    let raw = xref as *mut u8;
+    // Writing through the reference pops the raw pointer's tag off the stack.
+    *xref = 1;
+    // Without its own tag, the raw pointer could use the untagged base item of the box.
+    unsafe { *raw = 2; } //~ ERROR borrow stack
+}
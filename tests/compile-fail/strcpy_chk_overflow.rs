@@ -0,0 +1,18 @@
+// ignore-macos: Uses glibc's `__strcpy_chk`
+// ignore-windows: Uses glibc's `__strcpy_chk`
+
+#![feature(rustc_private)]
+extern crate libc;
+
+use libc::{c_char, size_t};
+
+extern {
+    fn __strcpy_chk(dest: *mut c_char, src: *const c_char, dest_len: size_t) -> *mut c_char;
+}
+
+fn main() {
+    let mut buf = [0 as c_char; 4];
+    unsafe {
+        __strcpy_chk(buf.as_mut_ptr(), b"hello\0".as_ptr() as *const c_char, buf.len()); //~ ERROR buffer overflow detected by __strcpy_chk
+    }
+}
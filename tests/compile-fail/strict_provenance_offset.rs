@@ -0,0 +1,9 @@
+// compile-flags: -Zmiri-seed=0000000000000000 -Zmiri-strict-provenance
+
+fn main() {
+    let arr = [1u8, 2, 3];
+    let addr = &arr[0] as *const u8 as usize;
+    // This integer never came out of a pointer-to-integer cast.
+    let x = unsafe { *((addr + 1) as *const u8) }; //~ ERROR strict provenance
+    panic!("this should never print: {}", x);
+}
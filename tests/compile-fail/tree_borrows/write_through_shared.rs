@@ -0,0 +1,8 @@
+// compile-flags: -Zmiri-tree-borrows
+
+fn main() {
+    let x = &mut 0;
+    let y = &*x;
+    let z = y as *const i32 as *mut i32;
+    unsafe { *z = 1; } //~ ERROR has permission Frozen
+}
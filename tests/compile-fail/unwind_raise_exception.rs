@@ -0,0 +1,11 @@
+use std::os::raw::{c_int, c_void};
+
+extern {
+    fn _Unwind_RaiseException(exception: *mut c_void) -> c_int;
+}
+
+fn main() {
+    unsafe {
+        _Unwind_RaiseException(std::ptr::null_mut()); //~ ERROR unwind not supported in miri
+    }
+}
@@ -0,0 +1,11 @@
+extern "system" {
+    fn CreateEventW(attributes: *mut u8, manual_reset: i32, initial_state: i32, name: *const u16) -> isize;
+    fn WaitForSingleObject(handle: isize, timeout: u32) -> u32;
+}
+
+fn main() {
+    unsafe {
+        let event = CreateEventW(std::ptr::null_mut(), 1, 0, std::ptr::null());
+        WaitForSingleObject(event, 0xFFFF_FFFF); //~ ERROR deadlock
+    }
+}
@@ -0,0 +1,15 @@
+#![feature(rustc_private)]
+#![allow(unused_extern_crates)] // rustc bug https://github.com/rust-lang/rust/issues/56098
+
+extern crate libc;
+
+fn main() {
+    unsafe {
+        let p = libc::malloc(8);
+        libc::free(p);
+        let mut key = 0;
+        assert_eq!(libc::pthread_key_create(&mut key, None), 0);
+        assert_eq!(libc::pthread_key_delete(key), 0);
+    }
+    drop(Box::new(1u8));
+}
@@ -0,0 +1,28 @@
+extern "C" {
+    fn malloc(size: usize) -> *mut u8;
+}
+
+extern "Rust" {
+    fn __miri_get_alloc_id(ptr: *const u8) -> u64;
+}
+
+fn main() {
+    // Leak some memory, so that it is still live when the program exits.
+    let rust = Box::into_raw(Box::new([1u8, 2, 3, 4]));
+    let c = unsafe { malloc(4) };
+    unsafe {
+        // The other two bytes stay uninitialized.
+        *c = 5;
+        *c.add(1) = 6;
+    }
+    let ptr = Box::into_raw(Box::new(c as *const u8));
+    unsafe {
+        println!(
+            "{} {} {}",
+            __miri_get_alloc_id(rust as *const u8),
+            __miri_get_alloc_id(c),
+            __miri_get_alloc_id(ptr as *const u8),
+        );
+    }
+    println!("{}", std::mem::size_of::<usize>());
+}
@@ -0,0 +1,215 @@
+#!/usr/bin/env python3
+'''
+Test the flags that make Miri write files or dump memory: run Miri on the programs in this
+directory and check what it wrote.
+Assumes `miri` and `cargo-miri` to be installed. Uses `MIRI_SYSROOT` if it is set, and
+asks `cargo miri setup` for the sysroot otherwise.
+'''
+
+import sys, subprocess, os, re, json, struct, tempfile
+
+def fail(msg):
+    print("\nTEST FAIL: {}".format(msg))
+    sys.exit(1)
+
+def target_args():
+    if 'MIRI_TEST_TARGET' in os.environ:
+        return ["--target", os.environ['MIRI_TEST_TARGET']]
+    return []
+
+def sysroot():
+    if 'MIRI_SYSROOT' in os.environ:
+        return os.environ['MIRI_SYSROOT']
+    out = subprocess.check_output(["cargo", "miri", "setup", "--env"] + target_args())
+    for line in out.decode("UTF-8").splitlines():
+        if line.startswith("MIRI_SYSROOT="):
+            return line[len("MIRI_SYSROOT="):]
+    fail("`cargo miri setup --env` did not print MIRI_SYSROOT")
+
+def miri(program, flags, cwd):
+    '''Runs Miri on `program` in `cwd`. Returns the exit status, stdout and stderr.'''
+    cmd = ["miri", "--sysroot", SYSROOT] + target_args() + flags
+    cmd.append(os.path.join(TEST_DIR, program))
+    p = subprocess.Popen(cmd, cwd=cwd, stdout=subprocess.PIPE, stderr=subprocess.PIPE)
+    (stdout, stderr) = p.communicate()
+    return (p.returncode, stdout.decode("UTF-8"), stderr.decode("UTF-8"))
+
+def run_leak(flags, cwd):
+    '''Runs `leak.rs`, which leaks a Rust, a C, and a pointer-holding Rust allocation,
+    and returns their IDs and the pointer size.'''
+    (status, stdout, stderr) = miri("leak.rs", flags, cwd)
+    if status == 0 or "the evaluated program leaked memory" not in stderr:
+        print(stderr, end="")
+        fail("leak.rs did not report its leaks")
+    (ids, ptr_size) = stdout.splitlines()
+    return ([int(id) for id in ids.split()], int(ptr_size))
+
+def parse_binary_dump(data):
+    '''Parses the binary format written by `--emit-miri-output=binary` into the same shape
+    as the JSON format.'''
+    pos = 0
+    def take(fmt):
+        nonlocal pos
+        values = struct.unpack_from("<" + fmt, data, pos)
+        pos += struct.calcsize("<" + fmt)
+        return values
+    if data[:8] != b"MIRIMEM\0":
+        fail("binary dump does not start with the magic")
+    pos = 8
+    (count,) = take("Q")
+    allocations = []
+    for _ in range(count):
+        (id, kind, site_len) = take("QBI")
+        site = data[pos:pos + site_len].decode("UTF-8")
+        pos += site_len
+        (align, size) = take("QQ")
+        values = data[pos:pos + size]
+        pos += size
+        mask = data[pos:pos + (size + 7) // 8]
+        pos += (size + 7) // 8
+        bytes = [values[i] if mask[i // 8] & (1 << (i % 8)) else None for i in range(size)]
+        (reloc_count,) = take("Q")
+        relocations = []
+        for _ in range(reloc_count):
+            (offset, alloc, tag) = take("QQQ")
+            relocations.append({"offset": offset, "alloc": alloc, "tag": tag or None})
+        allocations.append({
+            "id": id, "kind": ["Rust", "C"][kind], "site": site, "align": align,
+            "bytes": bytes, "relocations": relocations,
+        })
+    if pos != len(data):
+        fail("binary dump has trailing bytes")
+    return {"allocations": allocations}
+
+def check_dump(dump, ids):
+    (rust, c, ptr) = ids
+    allocations = {alloc["id"]: alloc for alloc in dump["allocations"]}
+    if sorted(allocations) != sorted(ids):
+        fail("the dump has allocations {}, but the live ones are {}".format(sorted(allocations), sorted(ids)))
+    if (allocations[rust]["kind"], allocations[rust]["bytes"]) != ("Rust", [1, 2, 3, 4]):
+        fail("wrong Rust allocation: {}".format(allocations[rust]))
+    if (allocations[c]["kind"], allocations[c]["bytes"]) != ("C", [5, 6, None, None]):
+        fail("wrong C allocation: {}".format(allocations[c]))
+    if "leak.rs" not in allocations[c]["site"]:
+        fail("wrong allocation site for the C allocation: {}".format(allocations[c]["site"]))
+    relocations = allocations[ptr]["relocations"]
+    if [(r["offset"], r["alloc"]) for r in relocations] != [(0, c)]:
+        fail("wrong pointers in the allocation holding a pointer: {}".format(relocations))
+
+def test_emit_miri_output():
+    print("==> Testing `--emit-miri-output` <==")
+    with tempfile.TemporaryDirectory() as cwd:
+        (ids, _) = run_leak(["--emit-miri-output=json"], cwd)
+        with open(os.path.join(cwd, "leak.miri.json")) as f:
+            check_dump(json.load(f), ids)
+    with tempfile.TemporaryDirectory() as cwd:
+        (ids, _) = run_leak(["--emit-miri-output=binary"], cwd)
+        with open(os.path.join(cwd, "leak.miri.bin"), "rb") as f:
+            check_dump(parse_binary_dump(f.read()), ids)
+
+def test_print_alloc_id():
+    print("==> Testing `--print-alloc-id` <==")
+    missing = 2**64 - 1
+    with tempfile.TemporaryDirectory() as cwd:
+        # Allocation IDs are deterministic, so a first run tells us which ones to ask for.
+        ((rust, c, ptr), ptr_size) = run_leak([], cwd)
+        flags = ["--print-alloc-id={}".format(id) for id in (rust, c, ptr, missing)]
+        (status, _, stderr) = miri("leak.rs", flags, cwd)
+    # The allocations get printed before the leak is reported.
+    printed = ""
+    for line in stderr.splitlines(keepends=True):
+        if line.startswith("error"):
+            break
+        printed += line
+    printed = re.sub(r"with tag .*", "with tag $TAG", printed)
+    with open(os.path.join(TEST_DIR, "leak.print-alloc-id.stderr")) as f:
+        expected = f.read()
+    expected = expected.replace("$PTR_SIZE", str(ptr_size))
+    expected = expected.replace("$PTR_BYTES", " ".join(["00"] * ptr_size))
+    for (name, id) in (("$RUST", rust), ("$C", c), ("$PTR", ptr), ("$MISSING", missing)):
+        expected = expected.replace(name, str(id))
+    if status == 0 or printed != expected:
+        print(stderr, end="")
+        fail("the printed allocations do not match leak.print-alloc-id.stderr")
+
+def test_trace():
+    print("==> Testing `-Zmiri-trace` <==")
+    with tempfile.TemporaryDirectory() as cwd:
+        (status, _, stderr) = miri("trace.rs", ["-Zmiri-trace=trace.json"], cwd)
+        if status != 0:
+            print(stderr, end="")
+            fail("trace.rs failed")
+        with open(os.path.join(cwd, "trace.json")) as f:
+            events = json.load(f)
+    # Replay the events, and record which functions got called from which.
+    stack = []
+    calls = set()
+    last_ts = 0
+    for event in events:
+        if event["ts"] < last_ts:
+            fail("the timestamps go backwards at {}".format(event))
+        last_ts = event["ts"]
+        if event["ph"] == "B":
+            name = event["name"].split("::")[-1]
+            if stack:
+                calls.add((stack[-1], name))
+            stack.append(name)
+        elif event["ph"] == "E":
+            if not stack:
+                fail("an event ends without having begun")
+            stack.pop()
+        else:
+            fail("unexpected event {}".format(event))
+    if stack:
+        fail("the events for {} never end".format(stack))
+    if ("main", "outer") not in calls or ("outer", "inner") not in calls:
+        fail("the trace does not contain main -> outer -> inner, only {}".format(sorted(calls)))
+
+def test_event_log():
+    print("==> Testing `-Zmiri-event-log` <==")
+    with tempfile.TemporaryDirectory() as cwd:
+        (status, _, stderr) = miri("events.rs", ["-Zmiri-event-log=events.log"], cwd)
+        if status != 0:
+            print(stderr, end="")
+            fail("events.rs failed")
+        with open(os.path.join(cwd, "events.log")) as f:
+            lines = [json.loads(line) for line in f]
+    if not lines or "stacks" not in lines[-1]:
+        fail("the event log does not end with the stack traces")
+    stacks = lines[-1]["stacks"]
+    events = lines[:-1]
+    last_ts = 0
+    for event in events:
+        if event["ts"] < last_ts:
+            fail("the timestamps go backwards at {}".format(event))
+        last_ts = event["ts"]
+        if str(event["stack"]) not in stacks:
+            fail("the stack trace of {} is missing".format(event))
+    # Every heap allocation made by the program directly is freed again.
+    allocs = {e["alloc"]: e for e in events if e["event"] == "alloc"}
+    freed = {e["alloc"] for e in events if e["event"] == "dealloc"}
+    for kind in ("C", "Rust"):
+        if not any(e["kind"] == kind and id in freed for (id, e) in allocs.items()):
+            fail("no {} allocation that got freed in the event log".format(kind))
+    calls = [e for e in events if e["event"] == "foreign_call"]
+    for name in ("malloc", "free", "pthread_key_create", "pthread_key_delete"):
+        if not any(e["name"] == name for e in calls):
+            fail("the call of `{}` is missing from the event log".format(name))
+    malloc = next(e for e in calls if e["name"] == "malloc")
+    if not any("`main`" in frame for frame in stacks[str(malloc["stack"])]):
+        fail("the stack trace of the call of `malloc` does not contain `main`")
+    created = [e["key"] for e in events if e["event"] == "tls_create"]
+    deleted = [e["key"] for e in events if e["event"] == "tls_delete"]
+    if not any(key in deleted for key in created):
+        fail("no TLS key got created and deleted in the event log")
+
+TEST_DIR = os.path.dirname(os.path.realpath(__file__))
+SYSROOT = sysroot()
+
+test_emit_miri_output()
+test_print_alloc_id()
+test_trace()
+test_event_log()
+
+print("\nTEST SUCCESSFUL!")
+sys.exit(0)
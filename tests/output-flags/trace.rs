@@ -0,0 +1,13 @@
+#[inline(never)]
+fn inner(x: u32) -> u32 {
+    x * 2
+}
+
+#[inline(never)]
+fn outer(x: u32) -> u32 {
+    inner(x) + inner(x + 1)
+}
+
+fn main() {
+    assert_eq!(outer(1), 6);
+}
@@ -0,0 +1,13 @@
+extern "Rust" {
+    fn __miri_set_abort_handler(handler: Option<extern fn()>);
+}
+
+extern fn handler() {
+    // Leave the program cleanly, which the default `abort` would not.
+    std::process::exit(0);
+}
+
+fn main() {
+    unsafe { __miri_set_abort_handler(Some(handler)) };
+    std::process::abort();
+}
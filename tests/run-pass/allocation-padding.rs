@@ -0,0 +1,14 @@
+// compile-flags: -Zmiri-seed=0000000000000000 -Zmiri-allocation-padding=64
+
+fn main() {
+    let boxes: Vec<Box<u64>> = (0..16).map(Box::new).collect();
+    let addrs: Vec<usize> = boxes.iter().map(|b| &**b as *const u64 as usize).collect();
+    for (i, &addr) in addrs.iter().enumerate() {
+        assert_eq!(addr % std::mem::align_of::<u64>(), 0);
+        // Allocations never overlap, padding or not.
+        for &other in &addrs[..i] {
+            assert!(addr >= other + 8 || other >= addr + 8);
+        }
+        assert_eq!(unsafe { *(addr as *const u64) }, i as u64);
+    }
+}
@@ -0,0 +1,27 @@
+// compile-flags: -Zmiri-seed=0000000000000000
+
+extern "C" {
+    fn arc4random() -> u32;
+    fn arc4random_buf(buf: *mut u8, nbytes: usize);
+    fn arc4random_uniform(upper_bound: u32) -> u32;
+}
+
+fn main() {
+    unsafe {
+        assert_ne!(arc4random(), arc4random());
+
+        let mut buf = [0u8; 32];
+        arc4random_buf(buf.as_mut_ptr(), buf.len());
+        assert!(buf.iter().any(|&b| b != 0));
+
+        assert_eq!(arc4random_uniform(0), 0);
+        assert_eq!(arc4random_uniform(1), 0);
+        let mut seen = [false; 6];
+        for _ in 0..100 {
+            let x = arc4random_uniform(6);
+            assert!(x < 6);
+            seen[x as usize] = true;
+        }
+        assert!(seen.iter().all(|&seen| seen));
+    }
+}
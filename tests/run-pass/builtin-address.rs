@@ -0,0 +1,27 @@
+use std::ffi::c_void;
+
+extern {
+    fn __builtin_frame_address(level: u32) -> *mut c_void;
+    fn __builtin_return_address(level: u32) -> *mut c_void;
+}
+
+#[inline(never)]
+fn frame_address() -> *mut c_void {
+    unsafe { __builtin_frame_address(0) }
+}
+
+fn main() {
+    unsafe {
+        let frame = __builtin_frame_address(0);
+        let ret = __builtin_return_address(0);
+        assert!(!frame.is_null());
+        assert!(!ret.is_null());
+        assert_ne!(frame, ret);
+        // The address is stable within a frame, and differs between frames.
+        assert_eq!(__builtin_frame_address(0), frame);
+        assert_ne!(frame_address(), frame);
+        // We do not support looking further up the stack.
+        assert!(__builtin_frame_address(1).is_null());
+        assert!(__builtin_return_address(2).is_null());
+    }
+}
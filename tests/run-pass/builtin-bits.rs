@@ -0,0 +1,27 @@
+use std::os::raw::{c_int, c_uint, c_ulonglong};
+
+extern {
+    fn __builtin_clz(x: c_uint) -> c_int;
+    fn __builtin_clzll(x: c_ulonglong) -> c_int;
+    fn __builtin_ctz(x: c_uint) -> c_int;
+    fn __builtin_ctzll(x: c_ulonglong) -> c_int;
+    fn __builtin_popcount(x: c_uint) -> c_int;
+    fn __builtin_popcountll(x: c_ulonglong) -> c_int;
+    fn __builtin_parity(x: c_uint) -> c_int;
+    fn __builtin_parityll(x: c_ulonglong) -> c_int;
+}
+
+fn main() {
+    unsafe {
+        assert_eq!(__builtin_clz(1), 31);
+        assert_eq!(__builtin_clz(!0), 0);
+        assert_eq!(__builtin_clzll(1), 63);
+        assert_eq!(__builtin_ctz(8), 3);
+        assert_eq!(__builtin_ctzll(1 << 40), 40);
+        assert_eq!(__builtin_popcount(0), 0);
+        assert_eq!(__builtin_popcount(0xff00ff), 16);
+        assert_eq!(__builtin_popcountll(!0), 64);
+        assert_eq!(__builtin_parity(7), 1);
+        assert_eq!(__builtin_parityll(3 << 50), 0);
+    }
+}
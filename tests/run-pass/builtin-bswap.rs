@@ -0,0 +1,17 @@
+extern {
+    fn __builtin_bswap16(x: u16) -> u16;
+    fn __builtin_bswap32(x: u32) -> u32;
+    fn __builtin_bswap64(x: u64) -> u64;
+}
+
+fn main() {
+    unsafe {
+        assert_eq!(__builtin_bswap16(0x1234), 0x3412);
+        assert_eq!(__builtin_bswap32(0x1234_5678), 0x7856_3412);
+        assert_eq!(__builtin_bswap64(0x0102_0304_0506_0708), 0x0807_0605_0403_0201);
+        assert_eq!(__builtin_bswap32(u32::from_be(0xdead_beef)), u32::from_le(0xdead_beef));
+
+        let bswap: unsafe extern "C" fn(u16) -> u16 = __builtin_bswap16;
+        assert_eq!(bswap(0xff00), 0x00ff);
+    }
+}
@@ -0,0 +1,18 @@
+use std::os::raw::{c_long, c_double};
+
+extern {
+    fn __builtin_expect(val: c_long, expected: c_long) -> c_long;
+    fn __builtin_expect_with_probability(val: c_long, expected: c_long, probability: c_double) -> c_long;
+    fn __builtin_unpredictable(val: c_long) -> c_long;
+}
+
+fn main() {
+    unsafe {
+        assert_eq!(__builtin_expect(3, 0), 3);
+        assert_eq!(__builtin_expect_with_probability(-1, 1, 0.9), -1);
+        assert_eq!(__builtin_unpredictable(42), 42);
+
+        let expect: unsafe extern "C" fn(c_long, c_long) -> c_long = __builtin_expect;
+        assert_eq!(expect(7, 7), 7);
+    }
+}
@@ -0,0 +1,22 @@
+use std::os::raw::c_int;
+
+extern {
+    fn __atomic_thread_fence(order: c_int);
+    fn __atomic_signal_fence(order: c_int);
+    fn atomic_thread_fence(order: c_int);
+}
+
+// `__ATOMIC_SEQ_CST` and `memory_order_acquire`.
+const SEQ_CST: c_int = 5;
+const ACQUIRE: c_int = 2;
+
+fn main() {
+    let mut x = 0;
+    unsafe {
+        x += 1;
+        __atomic_thread_fence(SEQ_CST);
+        __atomic_signal_fence(SEQ_CST);
+        atomic_thread_fence(ACQUIRE);
+    }
+    assert_eq!(x, 1);
+}
@@ -0,0 +1,43 @@
+//ignore-windows: Uses POSIX APIs
+
+#![feature(rustc_private)]
+extern crate libc;
+
+use libc::{c_double, c_int, c_long, c_uint};
+
+extern "C" {
+    fn rand_r(seed: *mut c_uint) -> c_int;
+    fn srand48(seed: c_long);
+    fn drand48() -> c_double;
+    fn lrand48() -> c_long;
+    fn mrand48() -> c_long;
+}
+
+fn main() {
+    unsafe {
+        // `rand` is deterministic, and `srand` restarts the sequence.
+        libc::srand(7);
+        let first: Vec<c_int> = (0..5).map(|_| libc::rand()).collect();
+        assert!(first.iter().all(|&x| x >= 0 && x <= libc::RAND_MAX));
+        assert!(first.windows(2).any(|w| w[0] != w[1]));
+        libc::srand(7);
+        let second: Vec<c_int> = (0..5).map(|_| libc::rand()).collect();
+        assert_eq!(first, second);
+
+        let mut seed = 7;
+        let a = rand_r(&mut seed);
+        assert_ne!(seed, 7);
+        let mut seed = 7;
+        assert_eq!(rand_r(&mut seed), a);
+
+        srand48(42);
+        let d = drand48();
+        assert!(d >= 0.0 && d < 1.0);
+        let l = lrand48();
+        assert!(l >= 0 && l < 1 << 31);
+        let m = mrand48();
+        assert!(m >= -(1 << 31) && m < 1 << 31);
+        srand48(42);
+        assert_eq!(drand48(), d);
+    }
+}
@@ -0,0 +1,109 @@
+//ignore-windows: Uses POSIX APIs
+// compile-flags: --check-abi
+
+// Calls every function whose signature `--check-abi` knows, with the right signature, so
+// that each of them is accepted by the check and has a shim.
+
+#![feature(rustc_private)]
+
+extern crate libc;
+
+use std::cell::UnsafeCell;
+use libc::{c_char, c_int, c_void, size_t};
+
+extern {
+    // Not declared by `libc` on all targets.
+    fn memrchr(s: *const c_void, c: c_int, n: size_t) -> *mut c_void;
+    fn sqrt(x: f64) -> f64;
+    fn cbrt(x: f64) -> f64;
+    fn exp(x: f64) -> f64;
+    fn log(x: f64) -> f64;
+    fn sin(x: f64) -> f64;
+    fn cos(x: f64) -> f64;
+    fn tan(x: f64) -> f64;
+    fn floor(x: f64) -> f64;
+    fn ceil(x: f64) -> f64;
+    fn pow(x: f64, y: f64) -> f64;
+    fn hypot(x: f64, y: f64) -> f64;
+    fn atan2(y: f64, x: f64) -> f64;
+    fn fmod(x: f64, y: f64) -> f64;
+}
+
+extern "Rust" {
+    fn __miri_set_abort_handler(handler: Option<extern fn()>);
+}
+
+extern fn handler() {
+    // `exit` and `_exit` share a shim, so this covers both.
+    unsafe { libc::exit(0) };
+}
+
+fn main() {
+    unsafe {
+        // Memory.
+        let p = libc::malloc(4) as *mut u8;
+        let p = libc::realloc(p as *mut c_void, 8) as *mut u8;
+        libc::memset(p as *mut c_void, 1, 8);
+        let q = libc::calloc(2, 4) as *mut u8;
+        libc::memcpy(q as *mut c_void, p as *const c_void, 4);
+        libc::memmove(q.add(2) as *mut c_void, q as *const c_void, 4);
+        assert_eq!(libc::memcmp(p as *const c_void, q as *const c_void, 6), 0);
+        assert_eq!(libc::memchr(q as *const c_void, 0, 8), q.add(6) as *mut c_void);
+        assert_eq!(memrchr(q as *const c_void, 1, 8), q.add(5) as *mut c_void);
+        libc::free(q as *mut c_void);
+        libc::free(p as *mut c_void);
+        let mut r: *mut c_void = std::ptr::null_mut();
+        assert_eq!(libc::posix_memalign(&mut r, 16, 16), 0);
+        libc::free(r);
+
+        // Environment and system.
+        let name = b"MIRI_CHECK_ABI\0".as_ptr() as *const c_char;
+        assert_eq!(libc::setenv(name, b"yes\0".as_ptr() as *const c_char, 1), 0);
+        assert_eq!(libc::strlen(libc::getenv(name)), 3);
+        assert_eq!(libc::unsetenv(name), 0);
+        assert!(libc::getenv(name).is_null());
+        assert!(libc::sysconf(libc::_SC_PAGESIZE) > 0);
+        libc::isatty(0);
+        let msg = b"check_abi\n";
+        assert_eq!(libc::write(1, msg.as_ptr() as *const c_void, msg.len()), msg.len() as isize);
+
+        // Threads.
+        let mut key = 0;
+        assert_eq!(libc::pthread_key_create(&mut key, None), 0);
+        let mutex = UnsafeCell::new(libc::PTHREAD_MUTEX_INITIALIZER);
+        assert_eq!(libc::pthread_mutex_init(mutex.get(), std::ptr::null()), 0);
+        assert_eq!(libc::pthread_mutex_lock(mutex.get()), 0);
+        assert_eq!(libc::pthread_mutex_unlock(mutex.get()), 0);
+        assert_eq!(libc::pthread_mutex_destroy(mutex.get()), 0);
+        let rwlock = UnsafeCell::new(libc::PTHREAD_RWLOCK_INITIALIZER);
+        assert_eq!(libc::pthread_rwlock_init(rwlock.get(), std::ptr::null()), 0);
+        assert_eq!(libc::pthread_rwlock_rdlock(rwlock.get()), 0);
+        assert_eq!(libc::pthread_rwlock_tryrdlock(rwlock.get()), 0);
+        assert_eq!(libc::pthread_rwlock_unlock(rwlock.get()), 0);
+        assert_eq!(libc::pthread_rwlock_unlock(rwlock.get()), 0);
+        assert_eq!(libc::pthread_rwlock_wrlock(rwlock.get()), 0);
+        assert_eq!(libc::pthread_rwlock_unlock(rwlock.get()), 0);
+        assert_eq!(libc::pthread_rwlock_trywrlock(rwlock.get()), 0);
+        assert_eq!(libc::pthread_rwlock_unlock(rwlock.get()), 0);
+        assert_eq!(libc::pthread_rwlock_destroy(rwlock.get()), 0);
+
+        // Math.
+        assert_eq!(sqrt(4.0), 2.0);
+        assert_eq!(cbrt(8.0), 2.0);
+        assert_eq!(exp(0.0), 1.0);
+        assert_eq!(log(1.0), 0.0);
+        assert_eq!(sin(0.0), 0.0);
+        assert_eq!(cos(0.0), 1.0);
+        assert_eq!(tan(0.0), 0.0);
+        assert_eq!(floor(1.5), 1.0);
+        assert_eq!(ceil(1.5), 2.0);
+        assert_eq!(pow(2.0, 3.0), 8.0);
+        assert_eq!(hypot(3.0, 4.0), 5.0);
+        assert_eq!(atan2(0.0, 1.0), 0.0);
+        assert_eq!(fmod(5.0, 3.0), 2.0);
+
+        // Leaving the program: the abort handler exits cleanly.
+        __miri_set_abort_handler(Some(handler));
+        libc::abort();
+    }
+}
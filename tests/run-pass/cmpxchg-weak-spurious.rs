@@ -0,0 +1,20 @@
+// compile-flags: -Zmiri-seed=0 -Zmiri-compare-exchange-weak-failure-rate=0.5
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+fn main() {
+    let x = AtomicUsize::new(0);
+    let mut failures = 0;
+    for i in 0..64 {
+        // Retry until the weak exchange succeeds, like every correct use of it does.
+        while let Err(old) = x.compare_exchange_weak(i, i + 1, Ordering::SeqCst, Ordering::Relaxed) {
+            assert_eq!(old, i);
+            failures += 1;
+        }
+    }
+    assert_eq!(x.load(Ordering::SeqCst), 64);
+    assert!(failures > 0);
+
+    // The strong version never fails spuriously.
+    assert_eq!(x.compare_exchange(64, 0, Ordering::SeqCst, Ordering::Relaxed), Ok(64));
+}
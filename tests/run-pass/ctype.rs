@@ -0,0 +1,44 @@
+//ignore-windows: Uses POSIX APIs
+
+#![feature(rustc_private)]
+#![allow(unused_extern_crates)] // rustc bug https://github.com/rust-lang/rust/issues/56098
+
+extern crate libc;
+
+use libc::c_int;
+
+fn classify() {
+    unsafe {
+        assert_ne!(libc::isalpha(b'q' as c_int), 0);
+        assert_eq!(libc::isalpha(b'1' as c_int), 0);
+        assert_ne!(libc::isdigit(b'7' as c_int), 0);
+        assert_ne!(libc::isalnum(b'Z' as c_int), 0);
+        assert_eq!(libc::isalnum(b'_' as c_int), 0);
+        assert_ne!(libc::isspace(b'\x0b' as c_int), 0);
+        assert_eq!(libc::isspace(b'x' as c_int), 0);
+        assert_ne!(libc::ispunct(b'!' as c_int), 0);
+        assert_ne!(libc::iscntrl(0x7f), 0);
+        // Non-ASCII characters and `EOF` are in no class in the C locale.
+        assert_eq!(libc::isalpha(0xe9), 0);
+        assert_eq!(libc::isspace(libc::EOF), 0);
+    }
+}
+
+fn convert() {
+    unsafe {
+        assert_eq!(libc::toupper(b'a' as c_int), b'A' as c_int);
+        assert_eq!(libc::toupper(b'A' as c_int), b'A' as c_int);
+        assert_eq!(libc::tolower(b'Q' as c_int), b'q' as c_int);
+        assert_eq!(libc::tolower(b'3' as c_int), b'3' as c_int);
+        assert_eq!(libc::toupper(libc::EOF), libc::EOF);
+    }
+}
+
+fn main() {
+    classify();
+    convert();
+
+    // The functions can also be called through function pointers.
+    let isdigit: unsafe extern "C" fn(c_int) -> c_int = libc::isdigit;
+    assert_ne!(unsafe { isdigit(b'0' as c_int) }, 0);
+}
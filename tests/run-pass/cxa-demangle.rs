@@ -0,0 +1,41 @@
+//ignore-windows: Uses POSIX APIs
+
+#![feature(rustc_private)]
+#![allow(unused_extern_crates)] // rustc bug https://github.com/rust-lang/rust/issues/56098
+
+extern crate libc;
+
+use std::ffi::CStr;
+
+use libc::{c_char, c_int, size_t};
+
+extern {
+    fn __cxa_demangle(
+        mangled: *const c_char,
+        buf: *mut c_char,
+        length: *mut size_t,
+        status: *mut c_int,
+    ) -> *mut c_char;
+}
+
+const MANGLED: &[u8] = b"_ZN3foo3bar17h05af221e174051e9E\0";
+
+fn main() {
+    unsafe {
+        let mut status = 1;
+        let res = __cxa_demangle(MANGLED.as_ptr() as *const c_char, std::ptr::null_mut(), std::ptr::null_mut(), &mut status);
+        assert_eq!(status, 0);
+        assert_eq!(CStr::from_ptr(res).to_bytes(), b"foo::bar");
+
+        // The buffer is large enough, so it is reused.
+        let mut length = 64;
+        let res2 = __cxa_demangle(MANGLED.as_ptr() as *const c_char, res, &mut length, &mut status);
+        assert_eq!(res2, res);
+        assert_eq!(length, 64);
+        libc::free(res2 as *mut _);
+
+        let res = __cxa_demangle(b"main\0".as_ptr() as *const c_char, std::ptr::null_mut(), std::ptr::null_mut(), &mut status);
+        assert!(res.is_null());
+        assert_eq!(status, -2);
+    }
+}
@@ -0,0 +1,23 @@
+#[cfg(target_os = "macos")]
+fn main() {
+    use std::ffi::CStr;
+    use std::os::raw::c_char;
+
+    extern {
+        fn _dyld_image_count() -> u32;
+        fn _dyld_get_image_name(image_index: u32) -> *const c_char;
+        fn _dyld_get_image_header(image_index: u32) -> *const u32;
+    }
+
+    unsafe {
+        assert_eq!(_dyld_image_count(), 1);
+        assert_eq!(CStr::from_ptr(_dyld_get_image_name(0)).to_bytes(), b"miri_program");
+        let magic = *_dyld_get_image_header(0);
+        assert!(magic == 0xfeed_facf || magic == 0xfeed_face);
+        assert!(_dyld_get_image_name(1).is_null());
+        assert!(_dyld_get_image_header(1).is_null());
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+fn main() {}
@@ -0,0 +1,22 @@
+// Scalars are stored in memory in the byte order of the target, not of the host.
+
+fn main() {
+    let x: u32 = 0x0102_0304;
+    let bytes: [u8; 4] = unsafe { std::mem::transmute(x) };
+    if cfg!(target_endian = "little") {
+        assert_eq!(bytes, [4, 3, 2, 1]);
+    } else {
+        assert_eq!(bytes, [1, 2, 3, 4]);
+    }
+    assert_eq!(bytes, x.to_ne_bytes());
+
+    // Reading a prefix of a wider integer through a pointer cast.
+    let y: u64 = 0x1122_3344_5566_7788;
+    let first = unsafe { *(&y as *const u64 as *const u16) };
+    assert_eq!(first, if cfg!(target_endian = "little") { 0x7788 } else { 0x1122 });
+
+    // Writing individual bytes and reading them back as one integer.
+    let mut z = 0u16;
+    unsafe { *(&mut z as *mut u16 as *mut u8) = 0xAB };
+    assert_eq!(z, if cfg!(target_endian = "little") { 0x00AB } else { 0xAB00 });
+}
@@ -0,0 +1,12 @@
+//ignore-windows: TODO env var emulation stubbed out on Windows
+// compile-flags: -Zmiri-env-forward=PATH
+
+use std::env;
+
+fn main() {
+    // Every test runs with some `PATH` on the host.
+    assert!(!env::var("PATH").unwrap().is_empty());
+    // Forwarded variables can be changed like all others.
+    env::set_var("PATH", "/miri");
+    assert_eq!(env::var("PATH"), Ok("/miri".to_owned()));
+}
@@ -0,0 +1,404 @@
+//ignore-windows: Uses POSIX APIs
+// ignore-macos: Uses Linux-only `__errno_location`
+
+#![feature(rustc_private)]
+#![allow(unused_extern_crates)] // rustc bug https://github.com/rust-lang/rust/issues/56098
+
+extern crate libc;
+
+use libc::c_int;
+
+#[repr(C)]
+struct DivT<T> {
+    quot: T,
+    rem: T,
+}
+
+extern {
+    fn abs(x: c_int) -> c_int;
+    fn labs(x: libc::c_long) -> libc::c_long;
+    fn llabs(x: libc::c_longlong) -> libc::c_longlong;
+    fn div(numer: c_int, denom: c_int) -> DivT<c_int>;
+    fn lldiv(numer: libc::c_longlong, denom: libc::c_longlong) -> DivT<libc::c_longlong>;
+    fn fabs(x: f64) -> f64;
+    fn fabsf(x: f32) -> f32;
+    fn fmin(x: f64, y: f64) -> f64;
+    fn fmax(x: f64, y: f64) -> f64;
+    fn fminf(x: f32, y: f32) -> f32;
+    fn fmaxf(x: f32, y: f32) -> f32;
+    fn copysign(x: f64, y: f64) -> f64;
+    fn copysignf(x: f32, y: f32) -> f32;
+    fn fdim(x: f64, y: f64) -> f64;
+    fn fdimf(x: f32, y: f32) -> f32;
+    fn floor(x: f64) -> f64;
+    fn ceilf(x: f32) -> f32;
+    fn round(x: f64) -> f64;
+    fn truncf(x: f32) -> f32;
+    fn rint(x: f64) -> f64;
+    fn nearbyintf(x: f32) -> f32;
+    fn lrint(x: f64) -> libc::c_long;
+    fn llroundf(x: f32) -> libc::c_longlong;
+    fn sqrt(x: f64) -> f64;
+    fn sqrtf(x: f32) -> f32;
+    fn exp(x: f64) -> f64;
+    fn log(x: f64) -> f64;
+    fn log2f(x: f32) -> f32;
+    fn log10(x: f64) -> f64;
+    fn pow(x: f64, y: f64) -> f64;
+    fn hypot(x: f64, y: f64) -> f64;
+    fn hypotf(x: f32, y: f32) -> f32;
+    fn log1p(x: f64) -> f64;
+    fn log1pf(x: f32) -> f32;
+    fn powf(x: f32, y: f32) -> f32;
+    fn sin(x: f64) -> f64;
+    fn cosf(x: f32) -> f32;
+    fn sincos(x: f64, sin: *mut f64, cos: *mut f64);
+    fn sincosf(x: f32, sin: *mut f32, cos: *mut f32);
+    fn asin(x: f64) -> f64;
+    fn acosf(x: f32) -> f32;
+    fn atan(x: f64) -> f64;
+    fn isnan(x: f64) -> c_int;
+    fn isinff(x: f32) -> c_int;
+    fn finite(x: f64) -> c_int;
+    fn __fpclassify(x: f64) -> c_int;
+    fn frexp(x: f64, exp: *mut c_int) -> f64;
+    fn frexpf(x: f32, exp: *mut c_int) -> f32;
+    fn ldexp(x: f64, exp: c_int) -> f64;
+    fn ldexpf(x: f32, exp: c_int) -> f32;
+    fn modf(x: f64, iptr: *mut f64) -> f64;
+    fn modff(x: f32, iptr: *mut f32) -> f32;
+    fn j0(x: f64) -> f64;
+    fn j1f(x: f32) -> f32;
+    fn jn(n: c_int, x: f64) -> f64;
+    fn y0(x: f64) -> f64;
+    fn ynf(n: c_int, x: f32) -> f32;
+    fn scalbn(x: f64, n: c_int) -> f64;
+    fn scalbnf(x: f32, n: c_int) -> f32;
+    fn scalbln(x: f64, n: libc::c_long) -> f64;
+    fn logb(x: f64) -> f64;
+    fn ilogb(x: f64) -> c_int;
+    fn significand(x: f64) -> f64;
+    fn tgamma(x: f64) -> f64;
+    fn tgammaf(x: f32) -> f32;
+    fn lgamma(x: f64) -> f64;
+    fn lgammaf_r(x: f32, signgam: *mut c_int) -> f32;
+    fn erf(x: f64) -> f64;
+    fn erfc(x: f64) -> f64;
+    fn erff(x: f32) -> f32;
+    fn erfcf(x: f32) -> f32;
+    fn fmod(x: f64, y: f64) -> f64;
+    fn fmodf(x: f32, y: f32) -> f32;
+    fn remainder(x: f64, y: f64) -> f64;
+    fn remainderf(x: f32, y: f32) -> f32;
+    fn nextafter(x: f64, y: f64) -> f64;
+    fn nextafterf(x: f32, y: f32) -> f32;
+    fn nan(tagp: *const libc::c_char) -> f64;
+    fn nanf(tagp: *const libc::c_char) -> f32;
+    fn fegetround() -> c_int;
+    fn fesetround(round: c_int) -> c_int;
+}
+
+macro_rules! assert_approx_eq {
+    ($a:expr, $b:expr) => ({
+        let (a, b) = (&$a, &$b);
+        assert!((*a - *b).abs() < 1.0e-6,
+                "{} is not approximately equal to {}", *a, *b);
+    })
+}
+
+fn absolute_values() {
+    unsafe {
+        assert_eq!(abs(-5), 5);
+        assert_eq!(abs(5), 5);
+        // The overflow is C's problem, not ours.
+        assert_eq!(abs(c_int::min_value()), c_int::min_value());
+        assert_eq!(labs(-5), 5);
+        assert_eq!(labs(libc::c_long::min_value()), libc::c_long::min_value());
+        assert_eq!(llabs(-(1 << 40)), 1 << 40);
+        assert_eq!(fabs(-1.5), 1.5);
+        assert_eq!(fabs(-0.0).to_bits(), 0.0f64.to_bits());
+        assert_eq!(fabsf(-2.5), 2.5);
+        assert!(fabsf(-std::f32::NAN).is_sign_positive());
+    }
+}
+
+fn division() {
+    unsafe {
+        let res = div(7, 2);
+        assert_eq!((res.quot, res.rem), (3, 1));
+        // The quotient is truncated towards zero.
+        let res = div(-7, 2);
+        assert_eq!((res.quot, res.rem), (-3, -1));
+        let res = lldiv(1 << 40, -3);
+        assert_eq!((res.quot, res.rem), ((1 << 40) / -3, (1 << 40) % -3));
+    }
+}
+
+fn min_max() {
+    unsafe {
+        assert_eq!(fmin(1.0, 2.0), 1.0);
+        assert_eq!(fmax(1.0, 2.0), 2.0);
+        assert_eq!(fmin(std::f64::NAN, 2.0), 2.0);
+        assert_eq!(fmax(1.0, std::f64::NAN), 1.0);
+        assert!(fmin(std::f64::NAN, std::f64::NAN).is_nan());
+        assert_eq!(fminf(-1.5, std::f32::NEG_INFINITY), std::f32::NEG_INFINITY);
+        assert_eq!(fmaxf(std::f32::NAN, -1.5), -1.5);
+
+        assert_eq!(copysign(1.5, -0.0), -1.5);
+        assert_eq!(copysignf(-2.0, 1.0), 2.0);
+        assert!(copysign(std::f64::NAN, -1.0).is_sign_negative());
+        assert_eq!(fdim(5.0, 3.0), 2.0);
+        assert_eq!(fdim(3.0, 5.0), 0.0);
+        assert!(fdim(std::f64::NAN, 1.0).is_nan());
+        assert_eq!(fdimf(-1.0, -3.5), 2.5);
+    }
+}
+
+fn classification() {
+    unsafe {
+        assert_eq!(isnan(std::f64::NAN), 1);
+        assert_eq!(isnan(1.0), 0);
+        assert_eq!(isinff(std::f32::INFINITY), 1);
+        assert_eq!(isinff(std::f32::NEG_INFINITY), -1);
+        assert_eq!(isinff(std::f32::MAX), 0);
+        assert_eq!(finite(1.0), 1);
+        assert_eq!(finite(std::f64::NAN), 0);
+        // These are glibc's `FP_*` values.
+        assert_eq!(__fpclassify(std::f64::NAN), 0);
+        assert_eq!(__fpclassify(std::f64::INFINITY), 1);
+        assert_eq!(__fpclassify(-0.0), 2);
+        assert_eq!(__fpclassify(5e-324), 3);
+        assert_eq!(__fpclassify(1.0), 4);
+    }
+}
+
+fn rounding() {
+    unsafe {
+        assert_eq!(floor(-1.5), -2.0);
+        assert_eq!(ceilf(1.25), 2.0);
+        assert_eq!(round(2.5), 3.0);
+        assert_eq!(round(-2.5), -3.0);
+        assert_eq!(truncf(-1.75), -1.0);
+        // These round halfway cases to even.
+        assert_eq!(rint(2.5), 2.0);
+        assert_eq!(rint(3.5), 4.0);
+        assert_eq!(rint(-0.5).to_bits(), (-0.0f64).to_bits());
+        assert_eq!(nearbyintf(1.5), 2.0);
+        assert_eq!(lrint(2.5), 2);
+        assert_eq!(lrint(std::f64::NAN), libc::c_long::min_value());
+        assert_eq!(llroundf(-2.5), -3);
+    }
+}
+
+fn elementary() {
+    unsafe {
+        assert_eq!(sqrt(16.0), 4.0);
+        assert_eq!(sqrtf(2.25), 1.5);
+        assert!(sqrt(-1.0).is_nan());
+        assert_eq!(exp(0.0), 1.0);
+        assert_approx_eq!(exp(1.0), std::f64::consts::E);
+        assert_approx_eq!(log(std::f64::consts::E), 1.0);
+        assert_eq!(log(0.0), std::f64::NEG_INFINITY);
+        assert_eq!(log2f(8.0), 3.0);
+        assert_eq!(log10(1000.0), 3.0);
+        assert_eq!(pow(2.0, 10.0), 1024.0);
+        assert_eq!(pow(std::f64::NAN, 0.0), 1.0);
+        assert_eq!(powf(4.0, 0.5), 2.0);
+
+        assert_eq!(hypot(3.0, -4.0), 5.0);
+        // The squares of these overflow.
+        assert!((hypot(3e300, 4e300) / 5e300 - 1.0).abs() < 1e-15);
+        assert!((hypotf(3e30, 4e30) / 5e30 - 1.0).abs() < 1e-6);
+        assert_eq!(hypot(std::f64::NAN, std::f64::NEG_INFINITY), std::f64::INFINITY);
+        assert!(hypot(std::f64::NAN, 1.0).is_nan());
+        assert_eq!(hypot(0.0, 0.0), 0.0);
+
+        // `1 + x` rounds to `1` for these.
+        assert_eq!(log1p(1e-20), 1e-20);
+        assert_eq!(log1pf(-1e-10), -1e-10);
+        assert_approx_eq!(log1p(std::f64::consts::E - 1.0), 1.0);
+        assert_eq!(log1p(-1.0), std::f64::NEG_INFINITY);
+        assert_eq!(log1p(std::f64::INFINITY), std::f64::INFINITY);
+        assert!(log1p(-2.0).is_nan());
+    }
+}
+
+fn trigonometry() {
+    unsafe {
+        assert_eq!(sin(0.0), 0.0);
+        assert_approx_eq!(sin(std::f64::consts::FRAC_PI_2), 1.0);
+        assert_eq!(cosf(0.0), 1.0);
+        assert!(sin(std::f64::INFINITY).is_nan());
+
+        let (mut s, mut c) = (0.0, 0.0);
+        sincos(std::f64::consts::PI, &mut s, &mut c);
+        assert_approx_eq!(s, 0.0);
+        assert_approx_eq!(c, -1.0);
+        let (mut s, mut c) = (0.0, 0.0);
+        sincosf(0.0, &mut s, &mut c);
+        assert_eq!((s, c), (0.0, 1.0));
+
+        assert_approx_eq!(asin(1.0), std::f64::consts::FRAC_PI_2);
+        assert_approx_eq!(acosf(-1.0), std::f32::consts::PI);
+        assert_approx_eq!(atan(std::f64::INFINITY), std::f64::consts::FRAC_PI_2);
+        *libc::__errno_location() = 0;
+        assert!(asin(2.0).is_nan());
+        assert_eq!(*libc::__errno_location(), libc::EDOM);
+    }
+}
+
+fn decompose() {
+    unsafe {
+        let mut exp = 0;
+        assert_eq!(frexp(8.0, &mut exp), 0.5);
+        assert_eq!(exp, 4);
+        assert_eq!(frexpf(-3.0, &mut exp), -0.75);
+        assert_eq!(exp, 2);
+        assert_eq!(frexp(0.0, &mut exp), 0.0);
+        assert_eq!(exp, 0);
+
+        assert_eq!(ldexp(0.5, 4), 8.0);
+        assert_eq!(ldexpf(-0.75, 2), -3.0);
+        assert_eq!(ldexp(1.0, 2000), std::f64::INFINITY);
+
+        let mut int = 0.0;
+        assert_eq!(modf(3.25, &mut int), 0.25);
+        assert_eq!(int, 3.0);
+        let mut int = 0.0;
+        assert_eq!(modff(-1.5, &mut int), -0.5);
+        assert_eq!(int, -1.0);
+    }
+}
+
+fn bessel() {
+    unsafe {
+        assert_eq!(j0(0.0), 1.0);
+        assert_eq!(j1f(0.0), 0.0);
+        assert_approx_eq!(j0(1.0), 0.7651976865579666);
+        assert_approx_eq!(jn(2, 1.0), 0.1149034849319005);
+        assert_eq!(y0(0.0), std::f64::NEG_INFINITY);
+        assert!(y0(-1.0).is_nan());
+        assert_approx_eq!(ynf(1, 1.0), -0.7812128);
+    }
+}
+
+fn exponents() {
+    unsafe {
+        assert_eq!(scalbn(1.5, 3), 12.0);
+        assert_eq!(scalbnf(12.0, -3), 1.5);
+        assert_eq!(scalbln(1.0, libc::c_long::max_value()), std::f64::INFINITY);
+        assert_eq!(logb(12.0), 3.0);
+        assert_eq!(logb(0.0), std::f64::NEG_INFINITY);
+        assert_eq!(logb(std::f64::NEG_INFINITY), std::f64::INFINITY);
+        assert_eq!(ilogb(0.1), -4);
+        assert_eq!(significand(12.0), 1.5);
+        assert!(significand(std::f64::NAN).is_nan());
+    }
+}
+
+fn gamma() {
+    unsafe {
+        assert_approx_eq!(tgamma(5.0), 24.0);
+        assert_approx_eq!(tgamma(0.5), std::f64::consts::PI.sqrt());
+        assert!(tgamma(std::f64::NAN).is_nan());
+        assert_approx_eq!(lgamma(10.0), 362880f64.ln());
+
+        let mut sign = 0;
+        assert_approx_eq!(lgammaf_r(-0.5, &mut sign), (2.0 * std::f32::consts::PI.sqrt()).ln());
+        assert_eq!(sign, -1);
+
+        *libc::__errno_location() = 0;
+        assert_eq!(lgamma(-2.0), std::f64::INFINITY);
+        assert_eq!(*libc::__errno_location(), libc::ERANGE);
+
+        // Like glibc, `tgamma` reports a pole error only at zero. The negative integers are a
+        // domain error and give NaN.
+        *libc::__errno_location() = 0;
+        assert_eq!(tgamma(0.0), std::f64::INFINITY);
+        assert_eq!(*libc::__errno_location(), libc::ERANGE);
+        *libc::__errno_location() = 0;
+        assert!(tgamma(-2.0).is_nan());
+        assert_eq!(*libc::__errno_location(), libc::EDOM);
+        *libc::__errno_location() = 0;
+        assert!(tgammaf(-3.0).is_nan());
+        assert_eq!(*libc::__errno_location(), libc::EDOM);
+    }
+}
+
+fn error_function() {
+    unsafe {
+        assert_eq!(erf(0.0), 0.0);
+        assert_approx_eq!(erf(1.0), 0.8427007929497149);
+        assert_approx_eq!(erf(-1.0), -erf(1.0));
+        assert_approx_eq!(erfc(0.5), 1.0 - erf(0.5));
+        assert_eq!(erf(10.0), 1.0);
+        assert_eq!(erfc(30.0), 0.0);
+        assert_eq!(erff(-10.0), -1.0);
+        assert_eq!(erfcf(-10.0), 2.0);
+    }
+}
+
+fn remainders() {
+    unsafe {
+        assert_eq!(fmod(5.5, 2.0), 1.5);
+        assert_eq!(fmodf(-5.5, 2.0), -1.5);
+        assert!(fmod(1.0, 0.0).is_nan());
+        assert!(fmod(std::f64::INFINITY, 1.0).is_nan());
+        assert_eq!(fmod(1.0, std::f64::INFINITY), 1.0);
+
+        assert_eq!(remainder(5.5, 2.0), -0.5);
+        assert_eq!(remainderf(5.0, 2.0), 1.0);
+        assert!(remainder(1.0, 0.0).is_nan());
+    }
+}
+
+fn next_after() {
+    unsafe {
+        assert_eq!(nextafter(1.0, 2.0), 1.0 + std::f64::EPSILON);
+        assert_eq!(nextafter(1.0, 1.0), 1.0);
+        assert_eq!(nextafter(0.0, 1.0), 5e-324);
+        assert_eq!(nextafter(0.0, -1.0), -5e-324);
+        assert_eq!(nextafter(std::f64::MAX, std::f64::INFINITY), std::f64::INFINITY);
+        assert_eq!(nextafter(std::f64::INFINITY, 0.0), std::f64::MAX);
+        assert!(nextafter(std::f64::NAN, 0.0).is_nan());
+        assert_eq!(nextafterf(-1.0, 0.0), -1.0 + std::f32::EPSILON / 2.0);
+    }
+}
+
+fn nan_payload() {
+    unsafe {
+        assert_eq!(nan(b"\0".as_ptr() as *const _).to_bits(), 0x7ff8_0000_0000_0000);
+        assert_eq!(nan(b"1234\0".as_ptr() as *const _).to_bits(), 0x7ff8_0000_0000_0000 | 1234);
+        assert_eq!(nan(b"0x10\0".as_ptr() as *const _).to_bits(), 0x7ff8_0000_0000_0010);
+        assert_eq!(nan(b"junk\0".as_ptr() as *const _).to_bits(), 0x7ff8_0000_0000_0000);
+        assert_eq!(nanf(b"7\0".as_ptr() as *const _).to_bits(), 0x7fc0_0007);
+    }
+}
+
+fn rounding_mode() {
+    const FE_TONEAREST: c_int = 0;
+    unsafe {
+        assert_eq!(fegetround(), FE_TONEAREST);
+        assert_eq!(fesetround(FE_TONEAREST), 0);
+        assert_eq!(fegetround(), FE_TONEAREST);
+    }
+}
+
+fn main() {
+    absolute_values();
+    division();
+    min_max();
+    classification();
+    rounding();
+    elementary();
+    trigonometry();
+    decompose();
+    bessel();
+    exponents();
+    gamma();
+    error_function();
+    remainders();
+    next_after();
+    nan_payload();
+    rounding_mode();
+}
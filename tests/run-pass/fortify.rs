@@ -0,0 +1,81 @@
+//ignore-windows: Uses POSIX APIs
+// ignore-macos: Uses glibc's `_chk` functions
+
+#![feature(rustc_private)]
+#![allow(unused_extern_crates)] // rustc bug https://github.com/rust-lang/rust/issues/56098
+
+extern crate libc;
+
+use libc::{c_char, c_void, size_t};
+
+extern {
+    fn __strcpy_chk(dest: *mut c_char, src: *const c_char, dest_len: size_t) -> *mut c_char;
+    fn __strncpy_chk(dest: *mut c_char, src: *const c_char, n: size_t, dest_len: size_t) -> *mut c_char;
+    fn __strcat_chk(dest: *mut c_char, src: *const c_char, dest_len: size_t) -> *mut c_char;
+    fn __memcpy_chk(dest: *mut c_void, src: *const c_void, n: size_t, dest_len: size_t) -> *mut c_void;
+    fn __mempcpy_chk(dest: *mut c_void, src: *const c_void, n: size_t, dest_len: size_t) -> *mut c_void;
+    fn __memmove_chk(dest: *mut c_void, src: *const c_void, n: size_t, dest_len: size_t) -> *mut c_void;
+    fn __memset_chk(dest: *mut c_void, c: libc::c_int, n: size_t, dest_len: size_t) -> *mut c_void;
+}
+
+fn strings() {
+    let mut buf = [0xffu8; 12];
+    let dest = buf.as_mut_ptr() as *mut c_char;
+    unsafe {
+        assert_eq!(libc::strcpy(dest, b"ab\0".as_ptr() as *const c_char), dest);
+        assert_eq!(&buf[..4], b"ab\0\xff");
+        assert_eq!(__strcat_chk(dest, b"cd\0".as_ptr() as *const c_char, 12), dest);
+        assert_eq!(&buf[..6], b"abcd\0\xff");
+        assert_eq!(__strcpy_chk(dest, b"xyz\0".as_ptr() as *const c_char, 4), dest);
+        assert_eq!(&buf[..4], b"xyz\0");
+
+        // `strncpy` pads with zeros, and does not terminate the string if it is too long.
+        let src = [b'q'; 3];
+        assert_eq!(libc::strncpy(dest, b"hi\0".as_ptr() as *const c_char, 5), dest);
+        assert_eq!(&buf[..6], b"hi\0\0\0\xff");
+        assert_eq!(__strncpy_chk(dest, src.as_ptr() as *const c_char, 3, 12), dest);
+        assert_eq!(&buf[..4], b"qqq\0");
+    }
+}
+
+fn memory() {
+    let src = [1u8, 2, 3, 4];
+    let mut dest = [0u8; 4];
+    unsafe {
+        __memcpy_chk(dest.as_mut_ptr() as *mut c_void, src.as_ptr() as *const c_void, 4, 4);
+    }
+    assert_eq!(dest, src);
+
+    let mut copy = [0u8; 4];
+    unsafe {
+        let ptr = copy.as_mut_ptr() as *mut c_void;
+        assert_eq!(libc::memcpy(ptr, src.as_ptr() as *const c_void, 4), ptr);
+        // Copying nothing is fine even for overlapping pointers.
+        libc::memcpy(ptr, ptr, 0);
+    }
+    assert_eq!(copy, src);
+
+    let mut buf = [1u8, 2, 3, 4, 5, 6];
+    let ptr = buf.as_mut_ptr();
+    unsafe {
+        // Overlapping copies are fine for `memmove`.
+        assert_eq!(libc::memmove(ptr.add(1) as *mut c_void, ptr as *const c_void, 4), ptr.add(1) as *mut c_void);
+        assert_eq!(buf, [1, 1, 2, 3, 4, 6]);
+        __memmove_chk(ptr as *mut c_void, ptr.add(2) as *const c_void, 4, 6);
+        assert_eq!(buf, [2, 3, 4, 6, 4, 6]);
+
+        let end = __mempcpy_chk(ptr as *mut c_void, src.as_ptr() as *const c_void, 2, 6);
+        assert_eq!(end, ptr.add(2) as *mut c_void);
+        assert_eq!(buf, [1, 2, 4, 6, 4, 6]);
+
+        assert_eq!(libc::memset(ptr as *mut c_void, 0x7, 3), ptr as *mut c_void);
+        assert_eq!(buf, [7, 7, 7, 6, 4, 6]);
+        __memset_chk(ptr.add(3) as *mut c_void, 0x100, 3, 3);
+        assert_eq!(buf, [7, 7, 7, 0, 0, 0]);
+    }
+}
+
+fn main() {
+    strings();
+    memory();
+}
@@ -0,0 +1,32 @@
+// ignore-windows: Uses POSIX APIs
+// compile-flags: -Zmiri-seed=0000000000000000
+
+#![feature(rustc_private)]
+extern crate libc;
+
+use libc::{c_int, c_uint, c_void, size_t, ssize_t};
+
+extern "C" {
+    #[cfg(target_os = "linux")]
+    fn getrandom(buf: *mut c_void, buflen: size_t, flags: c_uint) -> ssize_t;
+    fn getentropy(buf: *mut c_void, buflen: size_t) -> c_int;
+}
+
+fn main() {
+    unsafe {
+        #[cfg(target_os = "linux")]
+        {
+            let mut buf = [0u8; 32];
+            assert_eq!(getrandom(buf.as_mut_ptr() as *mut c_void, buf.len(), 0), 32);
+            assert!(buf.iter().any(|&b| b != 0));
+        }
+
+        let mut buf = [0u8; 257];
+        assert_eq!(getentropy(buf.as_mut_ptr() as *mut c_void, 256), 0);
+        assert!(buf[..256].iter().any(|&b| b != 0));
+        assert_eq!(buf[256], 0);
+
+        assert_eq!(getentropy(buf.as_mut_ptr() as *mut c_void, 257), -1);
+        assert_eq!(std::io::Error::last_os_error().raw_os_error(), Some(libc::EIO));
+    }
+}
@@ -0,0 +1,36 @@
+//ignore-windows: Uses POSIX APIs
+// ignore-macos: Uses glibc-only functions
+
+#![feature(rustc_private)]
+#![allow(unused_extern_crates)] // rustc bug https://github.com/rust-lang/rust/issues/56098
+
+extern crate libc;
+
+use std::ffi::CStr;
+use libc::{c_char, c_int, size_t};
+
+const _CS_GNU_LIBC_VERSION: c_int = 2;
+
+extern {
+    fn gnu_get_libc_version() -> *const c_char;
+    fn confstr(name: c_int, buf: *mut c_char, len: size_t) -> size_t;
+}
+
+fn main() {
+    unsafe {
+        let version = CStr::from_ptr(gnu_get_libc_version());
+        assert_eq!(version.to_bytes(), b"2.31");
+        // The string is static.
+        assert_eq!(gnu_get_libc_version(), version.as_ptr());
+
+        // Without a buffer, we only get the size.
+        let len = confstr(_CS_GNU_LIBC_VERSION, std::ptr::null_mut(), 0);
+        assert_eq!(len, 11);
+        let mut buf = [1 as c_char; 16];
+        assert_eq!(confstr(_CS_GNU_LIBC_VERSION, buf.as_mut_ptr(), buf.len()), 11);
+        assert_eq!(CStr::from_ptr(buf.as_ptr()).to_bytes(), b"glibc 2.31");
+        // The value is truncated to fit.
+        assert_eq!(confstr(_CS_GNU_LIBC_VERSION, buf.as_mut_ptr(), 6), 11);
+        assert_eq!(CStr::from_ptr(buf.as_ptr()).to_bytes(), b"glibc");
+    }
+}
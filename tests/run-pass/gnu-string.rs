@@ -0,0 +1,55 @@
+//ignore-windows: Uses POSIX APIs
+// ignore-macos: Uses GNU extensions
+
+#![feature(rustc_private)]
+#![allow(unused_extern_crates)] // rustc bug https://github.com/rust-lang/rust/issues/56098
+
+extern crate libc;
+
+use libc::{c_char, c_int, c_void, size_t};
+
+extern {
+    fn memmem(haystack: *const c_void, haystack_len: size_t, needle: *const c_void, needle_len: size_t) -> *mut c_void;
+    fn mempcpy(dest: *mut c_void, src: *const c_void, n: size_t) -> *mut c_void;
+    fn rawmemchr(s: *const c_void, c: c_int) -> *mut c_void;
+    fn stpcpy(dest: *mut c_char, src: *const c_char) -> *mut c_char;
+    fn stpncpy(dest: *mut c_char, src: *const c_char, n: size_t) -> *mut c_char;
+}
+
+fn search() {
+    let haystack = b"abcabcd";
+    let ptr = haystack.as_ptr() as *const c_void;
+    unsafe {
+        assert_eq!(memmem(ptr, 7, b"abcd".as_ptr() as *const c_void, 4), ptr.add(3) as *mut c_void);
+        assert_eq!(memmem(ptr, 7, b"x".as_ptr() as *const c_void, 1), std::ptr::null_mut());
+        assert_eq!(memmem(ptr, 2, b"abc".as_ptr() as *const c_void, 3), std::ptr::null_mut());
+        assert_eq!(memmem(ptr, 7, std::ptr::null(), 0), ptr as *mut c_void);
+
+        assert_eq!(rawmemchr(ptr, b'd' as c_int), ptr.add(6) as *mut c_void);
+    }
+}
+
+fn copy() {
+    let mut buf = [0xffu8; 8];
+    let dest = buf.as_mut_ptr();
+    unsafe {
+        let end = mempcpy(dest as *mut c_void, b"ab".as_ptr() as *const c_void, 2);
+        assert_eq!(end, dest.add(2) as *mut c_void);
+
+        let end = stpcpy(dest.add(2) as *mut c_char, b"cd\0".as_ptr() as *const c_char);
+        assert_eq!(end, dest.add(4) as *mut c_char);
+        assert_eq!(&buf[..6], b"abcd\0\xff");
+
+        let end = stpncpy(dest as *mut c_char, b"x\0".as_ptr() as *const c_char, 3);
+        assert_eq!(end, dest.add(1) as *mut c_char);
+        assert_eq!(&buf[..4], b"x\0\0d");
+        let end = stpncpy(dest as *mut c_char, b"yz\0".as_ptr() as *const c_char, 2);
+        assert_eq!(end, dest.add(2) as *mut c_char);
+        assert_eq!(&buf[..4], b"yz\0d");
+    }
+}
+
+fn main() {
+    search();
+    copy();
+}
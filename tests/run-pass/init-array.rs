@@ -0,0 +1,31 @@
+// ignore-windows: `.init_array` is an ELF section
+// ignore-macos: `.init_array` is an ELF section
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+extern "C" fn first() {
+    assert_eq!(COUNTER.fetch_add(1, Ordering::Relaxed), 0);
+}
+
+extern "C" fn second() {
+    assert_eq!(COUNTER.fetch_add(1, Ordering::Relaxed), 1);
+}
+
+extern "C" fn third() {
+    assert_eq!(COUNTER.fetch_add(1, Ordering::Relaxed), 2);
+}
+
+#[used]
+#[link_section = ".init_array"]
+static CONSTRUCTORS: [extern "C" fn(); 2] = [first, second];
+
+#[used]
+#[link_section = ".init_array"]
+static CONSTRUCTOR: extern "C" fn() = third;
+
+fn main() {
+    // All constructors ran before `main`.
+    assert_eq!(COUNTER.load(Ordering::Relaxed), 3);
+}
@@ -0,0 +1,67 @@
+//ignore-windows: Uses POSIX APIs
+// ignore-macos: Uses glibc's `__libc_start_main`
+
+#![feature(rustc_private)]
+#![allow(unused_extern_crates)] // rustc bug https://github.com/rust-lang/rust/issues/56098
+
+extern crate libc;
+
+use std::ffi::CStr;
+use std::ptr;
+use libc::{c_char, c_int, c_void};
+
+type MainFn = extern "C" fn(c_int, *const *const c_char, *const *const c_char) -> c_int;
+type InitFn = extern "C" fn(c_int, *const *const c_char, *const *const c_char);
+
+extern {
+    fn __libc_start_main(
+        main: MainFn,
+        argc: c_int,
+        argv: *const *const c_char,
+        init: Option<InitFn>,
+        fini: Option<extern "C" fn()>,
+        rtld_fini: Option<extern "C" fn()>,
+        stack_end: *mut c_void,
+    ) -> c_int;
+}
+
+static mut CALLS: Vec<&'static str> = Vec::new();
+
+extern "C" fn init(argc: c_int, _argv: *const *const c_char, _envp: *const *const c_char) {
+    assert_eq!(argc, 1);
+    unsafe { CALLS.push("init") };
+}
+
+extern "C" fn main_fn(argc: c_int, argv: *const *const c_char, envp: *const *const c_char) -> c_int {
+    assert_eq!(argc, 1);
+    unsafe {
+        assert_eq!(CStr::from_ptr(*argv).to_bytes(), b"prog");
+        assert!((*envp).is_null());
+        CALLS.push("main");
+    }
+    42
+}
+
+extern "C" fn fini() {
+    unsafe { CALLS.push("fini") };
+}
+
+extern "C" fn rtld_fini() {
+    unsafe { CALLS.push("rtld_fini") };
+}
+
+fn main() {
+    // `argv` is followed by the (empty) environment.
+    let argv = [b"prog\0".as_ptr() as *const c_char, ptr::null(), ptr::null()];
+    unsafe {
+        let code = __libc_start_main(main_fn, 1, argv.as_ptr(), Some(init), Some(fini), Some(rtld_fini), ptr::null_mut());
+        assert_eq!(code, 42);
+        assert_eq!(CALLS, ["init", "main", "fini", "rtld_fini"]);
+
+        // Only `main` is required.
+        let code = __libc_start_main(main_fn, 1, argv.as_ptr(), None, None, None, ptr::null_mut());
+        assert_eq!(code, 42);
+        assert_eq!(CALLS.len(), 5);
+        CALLS = Vec::new();
+    }
+}
@@ -0,0 +1,39 @@
+//ignore-windows: Uses POSIX APIs
+
+#![feature(rustc_private)]
+#![allow(unused_extern_crates)] // rustc bug https://github.com/rust-lang/rust/issues/56098
+
+extern crate libc;
+
+use libc::c_char;
+
+fn c_str(s: &[u8]) -> *const c_char {
+    s.as_ptr() as *const c_char
+}
+
+fn spans() {
+    let s = b"abc123\0";
+    unsafe {
+        assert_eq!(libc::strspn(c_str(s), c_str(b"cba\0")), 3);
+        assert_eq!(libc::strspn(c_str(s), c_str(b"\0")), 0);
+        assert_eq!(libc::strcspn(c_str(s), c_str(b"321\0")), 3);
+        assert_eq!(libc::strcspn(c_str(s), c_str(b"xyz\0")), 6);
+
+        assert_eq!(libc::strpbrk(c_str(s), c_str(b"2c\0")), c_str(s).add(2) as *mut c_char);
+        assert!(libc::strpbrk(c_str(s), c_str(b"xyz\0")).is_null());
+    }
+}
+
+fn search() {
+    let s = b"hello world\0";
+    unsafe {
+        assert_eq!(libc::strstr(c_str(s), c_str(b"o w\0")), c_str(s).add(4) as *mut c_char);
+        assert_eq!(libc::strstr(c_str(s), c_str(b"\0")), c_str(s) as *mut c_char);
+        assert!(libc::strstr(c_str(s), c_str(b"worlds\0")).is_null());
+    }
+}
+
+fn main() {
+    spans();
+    search();
+}
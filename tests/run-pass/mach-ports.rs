@@ -0,0 +1,20 @@
+#[cfg(target_os = "macos")]
+fn main() {
+    extern {
+        static mach_task_self_: u32;
+        fn mach_thread_self() -> u32;
+        fn mach_port_deallocate(task: u32, name: u32) -> i32;
+    }
+
+    unsafe {
+        let task = mach_task_self_;
+        let thread = mach_thread_self();
+        assert_ne!(task, 0);
+        assert_ne!(thread, 0);
+        assert_ne!(task, thread);
+        assert_eq!(mach_port_deallocate(task, thread), 0);
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+fn main() {}
@@ -0,0 +1,26 @@
+extern "Rust" {
+    fn __miri_get_alloc_id(ptr: *const u8) -> u64;
+}
+
+fn alloc_id<T>(ptr: *const T) -> u64 {
+    unsafe { __miri_get_alloc_id(ptr as *const u8) }
+}
+
+fn main() {
+    let a = [0u32; 4];
+    let b = Box::new(0u32);
+
+    // Pointers into the same allocation have the same ID, no matter the offset.
+    assert_eq!(alloc_id(&a[0]), alloc_id(&a[3]));
+    assert_ne!(alloc_id(&a[0]), alloc_id(&*b));
+
+    // A fresh allocation gets a fresh ID.
+    let old = alloc_id(&*b);
+    drop(b);
+    let c = Box::new(0u32);
+    assert_ne!(alloc_id(&*c), old);
+
+    // Pointers that went through an integer still know their allocation.
+    let addr = &a[2] as *const u32 as usize;
+    assert_eq!(alloc_id(addr as *const u32), alloc_id(&a[0]));
+}
@@ -0,0 +1,21 @@
+// Only keep the frames of this file, and drop their (host-specific) locations.
+// normalize-stderr-test "\n *= note: inside call to (`(std|core|alloc|<)|closure)[^\n]*" -> ""
+// normalize-stderr-test " at [^\n]*" -> ""
+// normalize-stderr-test "\.rs:[0-9]+:[0-9]+" -> ".rs:LL:CC"
+// normalize-stderr-test "\n[0-9]+ \|" -> "\nLL |"
+
+extern "Rust" {
+    fn __miri_print_backtrace();
+}
+
+#[inline(never)]
+fn inner(x: u32) -> u32 {
+    unsafe { __miri_print_backtrace() };
+    x + 1
+}
+
+fn main() {
+    // Printing the backtrace does not affect the program.
+    let x = inner(41);
+    assert_eq!(x, 42);
+}
@@ -0,0 +1,33 @@
+// ignore-macos: Uses Linux-only APIs
+// ignore-windows: Uses Linux-only APIs
+// compile-flags: -Zmiri-seed=0
+
+#![feature(rustc_private)]
+extern crate libc;
+
+extern "Rust" {
+    fn __miri_set_rng_seed(seed: u64);
+}
+
+fn random_bytes() -> [u8; 16] {
+    let mut buf = [0u8; 16];
+    unsafe {
+        libc::syscall(libc::SYS_getrandom, buf.as_mut_ptr() as *mut libc::c_void, buf.len(), 0 as libc::c_uint);
+    }
+    buf
+}
+
+fn main() {
+    unsafe { __miri_set_rng_seed(42) };
+    let first = random_bytes();
+    let second = random_bytes();
+    assert_ne!(first, second);
+
+    // Resetting the seed repeats the same sequence.
+    unsafe { __miri_set_rng_seed(42) };
+    assert_eq!(random_bytes(), first);
+    assert_eq!(random_bytes(), second);
+
+    unsafe { __miri_set_rng_seed(43) };
+    assert_ne!(random_bytes(), first);
+}
@@ -0,0 +1,25 @@
+// normalize-stderr-test "\.rs:[0-9]+:[0-9]+" -> ".rs:LL:CC"
+// normalize-stderr-test "\n[0-9]+ \|" -> "\nLL |"
+
+#[miri::skip]
+#[inline(never)]
+fn checksum(data: &[u8]) -> (u64, [u8; 4]) {
+    let sum = data.iter().map(|&x| x as u64).sum();
+    (sum, [1, 2, 3, 4])
+}
+
+#[miri::skip]
+#[inline(never)]
+fn set(x: &mut u32) {
+    *x = 1;
+}
+
+fn main() {
+    // The result is all zeros, not what the body would compute.
+    let result = checksum(b"hello");
+    assert_eq!(result, (0, [0; 4]));
+    // The body does not run at all, so it has no side effects either.
+    let mut x = 0;
+    set(&mut x);
+    assert_eq!(x, 0);
+}
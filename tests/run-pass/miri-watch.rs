@@ -0,0 +1,29 @@
+// Only keep the frames of this file, and drop their (host-specific) locations.
+// normalize-stderr-test "\n *= note: inside call to (`(std|core|alloc|<)|closure)[^\n]*" -> ""
+// normalize-stderr-test " at [^\n]*" -> ""
+// normalize-stderr-test "\.rs:[0-9]+:[0-9]+" -> ".rs:LL:CC"
+// normalize-stderr-test "\n[0-9]+ \|" -> "\nLL |"
+
+extern "Rust" {
+    fn __miri_watch(ptr: *const u8, size: usize, mode: u32);
+}
+
+const WATCH_WRITE: u32 = 2;
+
+#[inline(never)]
+fn observe(data: &[u8]) -> u32 {
+    data.iter().map(|&x| x as u32).sum()
+}
+
+fn main() {
+    let mut data = [0u8; 8];
+    unsafe { __miri_watch(data[2..].as_ptr(), 2, WATCH_WRITE) };
+    // Writes outside the watched range and reads are not reported.
+    data[0] = 1;
+    assert_eq!(observe(&data), 1);
+    // The write is reported before the next terminator, here the call of `observe`.
+    let p = &mut data[3] as *mut u8;
+    unsafe { *p = 2 };
+    let sum = observe(&data);
+    assert_eq!(sum, 3);
+}
@@ -0,0 +1,48 @@
+//ignore-windows: Uses POSIX APIs
+// ignore-macos: Uses Linux-only `MAP_POPULATE`
+
+#![feature(rustc_private)]
+#![allow(unused_extern_crates)] // rustc bug https://github.com/rust-lang/rust/issues/56098
+
+extern crate libc;
+
+use std::ptr;
+
+fn errno() -> i32 {
+    unsafe { *libc::__errno_location() }
+}
+
+fn main() {
+    unsafe {
+        let flags = libc::MAP_PRIVATE | libc::MAP_ANONYMOUS | libc::MAP_POPULATE;
+        let ptr = libc::mmap(ptr::null_mut(), 100, libc::PROT_READ | libc::PROT_WRITE, flags, -1, 0);
+        assert_ne!(ptr, libc::MAP_FAILED);
+        let bytes = ptr as *mut u8;
+        // Anonymous mappings are zeroed, and cover whole pages.
+        assert_eq!(*bytes.add(4095), 0);
+        *bytes = 42;
+
+        // Replacing the pages of an existing mapping zeroes them again.
+        let fixed = libc::mmap(ptr, 4096, libc::PROT_READ | libc::PROT_WRITE, flags | libc::MAP_FIXED, -1, 0);
+        assert_eq!(fixed, ptr);
+        assert_eq!(*bytes, 0);
+        assert_eq!(libc::munmap(ptr, 100), 0);
+
+        // Reserving memory and making it accessible later.
+        let reserved = libc::mmap(ptr::null_mut(), 4096, libc::PROT_NONE, flags, -1, 0);
+        assert_ne!(reserved, libc::MAP_FAILED);
+        assert_eq!(libc::mprotect(reserved, 4096, libc::PROT_READ | libc::PROT_WRITE), 0);
+        *(reserved as *mut u8) = 1;
+        assert_eq!(libc::munmap(reserved, 4096), 0);
+
+        // There are no files to map.
+        let file = libc::mmap(ptr::null_mut(), 4096, libc::PROT_READ, libc::MAP_SHARED, 3, 0);
+        assert_eq!(file, libc::MAP_FAILED);
+        assert_eq!(errno(), libc::ENODEV);
+
+        // Mappings must be either private or shared.
+        let neither = libc::mmap(ptr::null_mut(), 4096, libc::PROT_READ, libc::MAP_ANONYMOUS, -1, 0);
+        assert_eq!(neither, libc::MAP_FAILED);
+        assert_eq!(errno(), libc::EINVAL);
+    }
+}
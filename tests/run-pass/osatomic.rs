@@ -0,0 +1,35 @@
+extern {
+    fn OSAtomicAdd32Barrier(amount: i32, value: *mut i32) -> i32;
+    fn OSAtomicAdd64(amount: i64, value: *mut i64) -> i64;
+    fn OSAtomicAnd32Barrier(mask: u32, value: *mut u32) -> i32;
+    fn OSAtomicOr32Barrier(mask: u32, value: *mut u32) -> i32;
+    fn OSAtomicXor32Barrier(mask: u32, value: *mut u32) -> i32;
+    fn OSAtomicIncrement32Barrier(value: *mut i32) -> i32;
+    fn OSAtomicDecrement32Barrier(value: *mut i32) -> i32;
+    fn OSAtomicCompareAndSwap32Barrier(old: i32, new: i32, value: *mut i32) -> bool;
+}
+
+fn main() {
+    unsafe {
+        let mut x = 40;
+        assert_eq!(OSAtomicAdd32Barrier(2, &mut x), 42);
+        assert_eq!(OSAtomicIncrement32Barrier(&mut x), 43);
+        assert_eq!(OSAtomicDecrement32Barrier(&mut x), 42);
+        assert_eq!(OSAtomicAdd32Barrier(-50, &mut x), -8);
+        assert_eq!(x, -8);
+
+        let mut y = i64::max_value();
+        assert_eq!(OSAtomicAdd64(1, &mut y), i64::min_value());
+
+        let mut bits = 0b1100u32;
+        assert_eq!(OSAtomicAnd32Barrier(0b1010, &mut bits), 0b1000);
+        assert_eq!(OSAtomicOr32Barrier(0b0001, &mut bits), 0b1001);
+        assert_eq!(OSAtomicXor32Barrier(0b1111, &mut bits), 0b0110);
+
+        let mut z = 1;
+        assert!(!OSAtomicCompareAndSwap32Barrier(0, 5, &mut z));
+        assert_eq!(z, 1);
+        assert!(OSAtomicCompareAndSwap32Barrier(1, 5, &mut z));
+        assert_eq!(z, 5);
+    }
+}
@@ -0,0 +1,29 @@
+use std::os::raw::{c_int, c_void};
+
+extern {
+    fn __gxx_personality_v0(
+        version: c_int,
+        actions: c_int,
+        exception_class: u64,
+        exception: *mut c_void,
+        context: *mut c_void,
+    ) -> c_int;
+    fn __gcc_personality_v0(
+        version: c_int,
+        actions: c_int,
+        exception_class: u64,
+        exception: *mut c_void,
+        context: *mut c_void,
+    ) -> c_int;
+}
+
+const _URC_CONTINUE_UNWIND: c_int = 8;
+const _UA_SEARCH_PHASE: c_int = 1;
+
+fn main() {
+    let null = std::ptr::null_mut();
+    unsafe {
+        assert_eq!(__gxx_personality_v0(1, _UA_SEARCH_PHASE, 0, null, null), _URC_CONTINUE_UNWIND);
+        assert_eq!(__gcc_personality_v0(1, _UA_SEARCH_PHASE, 0, null, null), _URC_CONTINUE_UNWIND);
+    }
+}
@@ -0,0 +1,82 @@
+//ignore-windows: Uses POSIX APIs
+// ignore-macos: Uses glibc's `__snprintf_chk`
+
+#![feature(rustc_private)]
+#![allow(unused_extern_crates)] // rustc bug https://github.com/rust-lang/rust/issues/56098
+
+extern crate libc;
+
+use libc::{c_char, c_int, size_t};
+
+extern {
+    fn __snprintf_chk(s: *mut c_char, maxlen: size_t, flag: c_int, slen: size_t, format: *const c_char, ...) -> c_int;
+    fn __printf_chk(flag: c_int, format: *const c_char, ...) -> c_int;
+}
+
+macro_rules! assert_snprintf {
+    ($expected:expr, $fmt:expr $(, $arg:expr)*) => ({
+        let mut buf = [0u8; 64];
+        let len = libc::snprintf(
+            buf.as_mut_ptr() as *mut c_char, buf.len(),
+            concat!($fmt, "\0").as_ptr() as *const c_char $(, $arg)*
+        );
+        assert_eq!(&buf[..len as usize], $expected.as_bytes());
+        assert_eq!(buf[len as usize], 0);
+    })
+}
+
+fn integers() {
+    unsafe {
+        assert_snprintf!("42 -7 %", "%d %i %%", 42 as c_int, -7 as c_int);
+        assert_snprintf!("4294967295 ff FF 17", "%u %x %X %o", -1 as c_int, 255 as c_int, 255 as c_int, 15 as c_int);
+        assert_snprintf!("  +5|-5  |00042", "%+4d|%-4d|%05d", 5 as c_int, -5 as c_int, 42 as c_int);
+        assert_snprintf!("0x1f 017 -9223372036854775808", "%#x %#o %ld", 31 as c_int, 15 as c_int, i64::min_value());
+        assert_snprintf!("   007", "%*.*d", 6 as c_int, 3 as c_int, 7 as c_int);
+        assert_snprintf!("-1 255", "%hhd %hhu", 255 as c_int, 255 as c_int);
+    }
+}
+
+fn strings() {
+    unsafe {
+        assert_snprintf!("a hello   |wor", "%c %-8s|%.3s", b'a' as c_int, b"hello\0".as_ptr(), b"world\0".as_ptr());
+        assert_snprintf!("(nil)", "%p", std::ptr::null::<u8>());
+    }
+}
+
+fn floats() {
+    unsafe {
+        assert_snprintf!("1.500000 2.50 -0.1", "%f %.2f %.1f", 1.5f64, 2.5f64, -0.125f64);
+        assert_snprintf!("1.234500e+03 1.2E-05", "%e %.1E", 1234.5f64, 0.0000123f64);
+        assert_snprintf!("100 1e+06 0.0001 1.5", "%g %g %g %g", 100.0f64, 1e6f64, 0.0001f64, 1.5f64);
+        assert_snprintf!("inf -nan NAN", "%f %f %F", std::f64::INFINITY, -std::f64::NAN, std::f64::NAN);
+    }
+}
+
+fn truncation() {
+    unsafe {
+        let mut buf = [0xffu8; 8];
+        let len = libc::snprintf(buf.as_mut_ptr() as *mut c_char, 4, b"%s\0".as_ptr() as *const c_char, b"hello\0".as_ptr());
+        assert_eq!(len, 5);
+        assert_eq!(&buf[..5], b"hel\0\xff");
+        assert_eq!(libc::snprintf(std::ptr::null_mut(), 0, b"%d\0".as_ptr() as *const c_char, 1234 as c_int), 4);
+
+        let len = __snprintf_chk(buf.as_mut_ptr() as *mut c_char, buf.len(), 1, buf.len(), b"%d\0".as_ptr() as *const c_char, 7 as c_int);
+        assert_eq!(len, 1);
+        assert_eq!(&buf[..2], b"7\0");
+
+        let len = libc::sprintf(buf.as_mut_ptr() as *mut c_char, b"%03d\0".as_ptr() as *const c_char, 5 as c_int);
+        assert_eq!(len, 3);
+        assert_eq!(&buf[..4], b"005\0");
+    }
+}
+
+fn main() {
+    integers();
+    strings();
+    floats();
+    truncation();
+    unsafe {
+        assert_eq!(libc::printf(b"%s, %s!\n\0".as_ptr() as *const c_char, b"Hello\0".as_ptr(), b"world\0".as_ptr()), 14);
+        assert_eq!(__printf_chk(1, b"%d\n\0".as_ptr() as *const c_char, 42 as c_int), 3);
+    }
+}
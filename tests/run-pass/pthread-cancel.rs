@@ -0,0 +1,43 @@
+//ignore-windows: Uses POSIX APIs
+// ignore-macos: Uses glibc's cancellation constants
+
+#![feature(rustc_private)]
+#![allow(unused_extern_crates)] // rustc bug https://github.com/rust-lang/rust/issues/56098
+
+extern crate libc;
+
+use libc::c_int;
+
+const PTHREAD_CANCEL_ENABLE: c_int = 0;
+const PTHREAD_CANCEL_DISABLE: c_int = 1;
+const PTHREAD_CANCEL_DEFERRED: c_int = 0;
+const PTHREAD_CANCEL_ASYNCHRONOUS: c_int = 1;
+
+extern {
+    fn pthread_cancel(thread: libc::pthread_t) -> c_int;
+    fn pthread_setcancelstate(state: c_int, oldstate: *mut c_int) -> c_int;
+    fn pthread_setcanceltype(ty: c_int, oldtype: *mut c_int) -> c_int;
+    fn pthread_testcancel();
+}
+
+fn main() {
+    unsafe {
+        let mut old = -1;
+        assert_eq!(pthread_setcanceltype(PTHREAD_CANCEL_ASYNCHRONOUS, &mut old), 0);
+        assert_eq!(old, PTHREAD_CANCEL_DEFERRED);
+        assert_eq!(pthread_setcanceltype(PTHREAD_CANCEL_DEFERRED, std::ptr::null_mut()), 0);
+        assert_eq!(pthread_setcancelstate(42, &mut old), libc::EINVAL);
+
+        assert_eq!(pthread_setcancelstate(PTHREAD_CANCEL_DISABLE, &mut old), 0);
+        assert_eq!(old, PTHREAD_CANCEL_ENABLE);
+        assert_eq!(pthread_cancel(libc::pthread_self()), 0);
+        // Cancellation is disabled, so this returns.
+        pthread_testcancel();
+
+        assert_eq!(pthread_setcancelstate(PTHREAD_CANCEL_ENABLE, &mut old), 0);
+        assert_eq!(old, PTHREAD_CANCEL_DISABLE);
+        // This ends the program.
+        pthread_testcancel();
+        unreachable!();
+    }
+}
@@ -0,0 +1,45 @@
+//ignore-windows: Uses POSIX APIs
+// ignore-macos: Uses glibc's `_pthread_cleanup_push`
+
+#![feature(rustc_private)]
+#![allow(unused_extern_crates)] // rustc bug https://github.com/rust-lang/rust/issues/56098
+
+extern crate libc;
+
+use libc::{c_int, c_void};
+
+/// glibc's `struct _pthread_cleanup_buffer`, which we do not look at.
+#[repr(C)]
+struct CleanupBuffer([usize; 4]);
+
+extern {
+    fn _pthread_cleanup_push(
+        buffer: *mut CleanupBuffer,
+        routine: extern fn(*mut c_void),
+        arg: *mut c_void,
+    );
+    fn _pthread_cleanup_pop(buffer: *mut CleanupBuffer, execute: c_int);
+}
+
+extern fn add_one(arg: *mut c_void) {
+    unsafe { *(arg as *mut u32) += 1 };
+}
+
+extern fn add_ten(arg: *mut c_void) {
+    unsafe { *(arg as *mut u32) += 10 };
+}
+
+fn main() {
+    let mut count = 0u32;
+    let arg = &mut count as *mut u32 as *mut c_void;
+    let mut outer = CleanupBuffer([0; 4]);
+    let mut inner = CleanupBuffer([0; 4]);
+    unsafe {
+        _pthread_cleanup_push(&mut outer, add_one, arg);
+        _pthread_cleanup_push(&mut inner, add_ten, arg);
+        // This pops `add_ten` without running it.
+        _pthread_cleanup_pop(&mut inner, 0);
+        _pthread_cleanup_pop(&mut outer, 1);
+    }
+    assert_eq!(count, 1);
+}
@@ -0,0 +1,39 @@
+//ignore-windows: Uses POSIX APIs
+// ignore-macos: Uses glibc's `_pthread_cleanup_push`
+
+#![feature(rustc_private)]
+#![allow(unused_extern_crates)] // rustc bug https://github.com/rust-lang/rust/issues/56098
+
+extern crate libc;
+
+use libc::c_void;
+
+/// glibc's `struct _pthread_cleanup_buffer`, which we do not look at.
+#[repr(C)]
+struct CleanupBuffer([usize; 4]);
+
+extern {
+    fn _pthread_cleanup_push(
+        buffer: *mut CleanupBuffer,
+        routine: extern fn(*mut c_void),
+        arg: *mut c_void,
+    );
+    fn pthread_exit(retval: *mut c_void) -> !;
+}
+
+extern fn print(arg: *mut c_void) {
+    println!("{}", unsafe { *(arg as *const u32) });
+}
+
+fn main() {
+    let one = 1u32;
+    let two = 2u32;
+    let mut outer = CleanupBuffer([0; 4]);
+    let mut inner = CleanupBuffer([0; 4]);
+    unsafe {
+        _pthread_cleanup_push(&mut outer, print, &one as *const u32 as *mut c_void);
+        _pthread_cleanup_push(&mut inner, print, &two as *const u32 as *mut c_void);
+        // This runs both handlers, the inner one first, and then ends the program.
+        pthread_exit(std::ptr::null_mut());
+    }
+}
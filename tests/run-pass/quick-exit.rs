@@ -0,0 +1,24 @@
+//ignore-windows: Uses POSIX APIs
+
+use std::os::raw::c_int;
+
+extern {
+    fn at_quick_exit(func: extern fn()) -> c_int;
+    fn quick_exit(code: c_int) -> !;
+}
+
+extern fn first() {
+    println!("first handler");
+}
+
+extern fn second() {
+    println!("second handler");
+}
+
+fn main() {
+    unsafe {
+        assert_eq!(at_quick_exit(first), 0);
+        assert_eq!(at_quick_exit(second), 0);
+        quick_exit(0);
+    }
+}
@@ -0,0 +1,16 @@
+// compile-flags: -Zmiri-seed=0000000000000000 -Zmiri-randomize-heap-layout
+
+#[repr(align(64))]
+struct Aligned(u64);
+
+fn main() {
+    let boxes: Vec<Box<Aligned>> = (0..16).map(|i| Box::new(Aligned(i))).collect();
+    let addrs: Vec<usize> = boxes.iter().map(|b| &**b as *const Aligned as usize).collect();
+    for (i, &addr) in addrs.iter().enumerate() {
+        assert_eq!(addr % 64, 0);
+        for &other in &addrs[..i] {
+            assert!(addr >= other + 64 || other >= addr + 64);
+        }
+        assert_eq!(unsafe { (*(addr as *const Aligned)).0 }, i as u64);
+    }
+}
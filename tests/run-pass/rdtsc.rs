@@ -0,0 +1,17 @@
+#[cfg(target_arch = "x86_64")]
+fn main() {
+    use std::arch::x86_64::_rdtsc;
+
+    let start = unsafe { _rdtsc() };
+    let mut sum = 0u64;
+    for i in 0..10 {
+        sum += i;
+    }
+    let end = unsafe { _rdtsc() };
+    assert_eq!(sum, 45);
+    // The counter is monotonic.
+    assert!(end > start);
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+fn main() {}
@@ -0,0 +1,16 @@
+// compile-flags: -Zmiri-seed=0000000000000000 -Zmiri-reproducibility-check
+
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static SUM: AtomicUsize = AtomicUsize::new(0);
+
+fn main() {
+    // The final memory of a deterministic program does not depend on the seed.
+    let mut map = BTreeMap::new();
+    for i in 0..10 {
+        map.insert(i, i * i);
+    }
+    SUM.store(map.values().sum(), Ordering::Relaxed);
+    assert_eq!(SUM.load(Ordering::Relaxed), 285);
+}
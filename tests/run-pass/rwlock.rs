@@ -0,0 +1,45 @@
+//ignore-windows: Uses POSIX APIs
+
+#![feature(rustc_private)]
+
+extern crate libc;
+
+use std::cell::UnsafeCell;
+
+fn main() {
+    unsafe {
+        let lock = UnsafeCell::new(libc::PTHREAD_RWLOCK_INITIALIZER);
+        let lock = lock.get();
+
+        // Any number of readers, but no writer while reading.
+        assert_eq!(libc::pthread_rwlock_rdlock(lock), 0);
+        assert_eq!(libc::pthread_rwlock_tryrdlock(lock), 0);
+        assert_eq!(libc::pthread_rwlock_trywrlock(lock), libc::EBUSY);
+        assert_eq!(libc::pthread_rwlock_wrlock(lock), libc::EDEADLK);
+        assert_eq!(libc::pthread_rwlock_destroy(lock), libc::EBUSY);
+        assert_eq!(libc::pthread_rwlock_unlock(lock), 0);
+        assert_eq!(libc::pthread_rwlock_unlock(lock), 0);
+
+        // One writer, and no readers while writing.
+        assert_eq!(libc::pthread_rwlock_wrlock(lock), 0);
+        assert_eq!(libc::pthread_rwlock_tryrdlock(lock), libc::EBUSY);
+        assert_eq!(libc::pthread_rwlock_trywrlock(lock), libc::EBUSY);
+        assert_eq!(libc::pthread_rwlock_rdlock(lock), libc::EDEADLK);
+        assert_eq!(libc::pthread_rwlock_unlock(lock), 0);
+
+        // Unlocking a lock that is not held fails.
+        assert_eq!(libc::pthread_rwlock_unlock(lock), libc::EPERM);
+        assert_eq!(libc::pthread_rwlock_destroy(lock), 0);
+    }
+
+    // `std`'s `RwLock` works, too.
+    let lock = std::sync::RwLock::new(0);
+    {
+        let a = lock.read().unwrap();
+        let b = lock.read().unwrap();
+        assert!(lock.try_write().is_err());
+        assert_eq!(*a + *b, 0);
+    }
+    *lock.write().unwrap() += 1;
+    assert_eq!(*lock.try_read().unwrap(), 1);
+}
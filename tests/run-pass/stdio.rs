@@ -0,0 +1,123 @@
+//ignore-windows: Uses POSIX APIs
+
+#![feature(rustc_private)]
+#![allow(unused_extern_crates)] // rustc bug https://github.com/rust-lang/rust/issues/56098
+
+extern crate libc;
+
+use libc::{c_int, c_char, FILE};
+
+extern {
+    fn fdopen(fd: c_int, mode: *const c_char) -> *mut FILE;
+    fn fileno(stream: *mut FILE) -> c_int;
+    fn fclose(stream: *mut FILE) -> c_int;
+    fn feof(stream: *mut FILE) -> c_int;
+    fn ferror(stream: *mut FILE) -> c_int;
+    fn clearerr(stream: *mut FILE);
+    fn rewind(stream: *mut FILE);
+    fn fflush(stream: *mut FILE) -> c_int;
+    fn setvbuf(stream: *mut FILE, buf: *mut c_char, mode: c_int, size: libc::size_t) -> c_int;
+    fn setbuf(stream: *mut FILE, buf: *mut c_char);
+    fn setlinebuf(stream: *mut FILE);
+    fn flockfile(stream: *mut FILE);
+    fn ftrylockfile(stream: *mut FILE) -> c_int;
+    fn funlockfile(stream: *mut FILE);
+    fn putc_unlocked(c: c_int, stream: *mut FILE) -> c_int;
+    fn fputs_unlocked(s: *const c_char, stream: *mut FILE) -> c_int;
+    fn fwrite(ptr: *const libc::c_void, size: libc::size_t, nmemb: libc::size_t, stream: *mut FILE) -> libc::size_t;
+    fn getc_unlocked(stream: *mut FILE) -> c_int;
+    fn fgetc(stream: *mut FILE) -> c_int;
+    fn fread(ptr: *mut libc::c_void, size: libc::size_t, nmemb: libc::size_t, stream: *mut FILE) -> libc::size_t;
+    fn fgets(s: *mut c_char, n: c_int, stream: *mut FILE) -> *mut c_char;
+}
+
+fn error_flags() {
+    unsafe {
+        let stream = fdopen(1, b"w\0".as_ptr() as *const c_char);
+        assert!(!stream.is_null());
+        assert_eq!(fileno(stream), 1);
+        assert_eq!(feof(stream), 0);
+        assert_eq!(ferror(stream), 0);
+        clearerr(stream);
+        assert_eq!(feof(stream), 0);
+        assert_eq!(fclose(stream), 0);
+
+        assert!(fdopen(42, b"r\0".as_ptr() as *const c_char).is_null());
+    }
+}
+
+fn flush() {
+    unsafe {
+        let stream = fdopen(2, b"w\0".as_ptr() as *const c_char);
+        assert_eq!(fflush(stream), 0);
+        assert_eq!(fflush(std::ptr::null_mut()), 0);
+        rewind(stream);
+        assert_eq!(ferror(stream), 0);
+        assert_eq!(fclose(stream), 0);
+    }
+}
+
+fn buffering() {
+    unsafe {
+        let stream = fdopen(1, b"w\0".as_ptr() as *const c_char);
+        assert_eq!(setvbuf(stream, std::ptr::null_mut(), libc::_IONBF, 0), 0);
+        assert_eq!(setvbuf(stream, std::ptr::null_mut(), 42, 0), -1);
+        let mut buf = [0 as c_char; 64];
+        setbuf(stream, buf.as_mut_ptr());
+        setlinebuf(stream);
+        assert_eq!(fclose(stream), 0);
+    }
+}
+
+fn locking() {
+    unsafe {
+        let stream = fdopen(1, b"w\0".as_ptr() as *const c_char);
+        flockfile(stream);
+        assert_eq!(ftrylockfile(stream), 0);
+        assert_eq!(putc_unlocked(b'h' as c_int, stream), b'h' as c_int);
+        assert!(fputs_unlocked(b"ello\0".as_ptr() as *const c_char, stream) >= 0);
+        funlockfile(stream);
+        funlockfile(stream);
+        assert_eq!(fwrite(b" world\n".as_ptr() as *const _, 1, 7, stream), 7);
+        assert_eq!(fclose(stream), 0);
+
+        let stream = fdopen(0, b"r\0".as_ptr() as *const c_char);
+        flockfile(stream);
+        assert_eq!(getc_unlocked(stream), libc::EOF);
+        funlockfile(stream);
+        assert_ne!(feof(stream), 0);
+        let mut buf = [0 as c_char; 16];
+        assert!(fgets(buf.as_mut_ptr(), 16, stream).is_null());
+        assert_eq!(fclose(stream), 0);
+    }
+}
+
+// Miri does not forward the host's standard input: every read hits end-of-file.
+fn reading() {
+    unsafe {
+        let stream = fdopen(0, b"r\0".as_ptr() as *const c_char);
+        assert_eq!(fgetc(stream), libc::EOF);
+        assert_ne!(feof(stream), 0);
+        assert_eq!(ferror(stream), 0);
+        clearerr(stream);
+        assert_eq!(feof(stream), 0);
+
+        let mut buf = [7u8; 16];
+        assert_eq!(fread(buf.as_mut_ptr() as *mut _, 1, 16, stream), 0);
+        assert_ne!(feof(stream), 0);
+        clearerr(stream);
+        assert!(fgets(buf.as_mut_ptr() as *mut c_char, 16, stream).is_null());
+        assert_ne!(feof(stream), 0);
+        // Nothing was written to the buffer.
+        assert_eq!(buf, [7u8; 16]);
+        assert_eq!(fclose(stream), 0);
+    }
+}
+
+fn main() {
+    error_flags();
+    flush();
+    buffering();
+    locking();
+    reading();
+}
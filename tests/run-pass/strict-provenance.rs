@@ -0,0 +1,13 @@
+// compile-flags: -Zmiri-seed=0000000000000000 -Zmiri-strict-provenance
+
+fn main() {
+    // Round-tripping through an integer is fine.
+    let x = 42;
+    let addr = &x as *const i32 as usize;
+    assert_eq!(unsafe { *(addr as *const i32) }, 42);
+
+    // So is casting back a pointer into the middle of an allocation.
+    let arr = [1u8, 2, 3];
+    let addr = &arr[1] as *const u8 as usize;
+    assert_eq!(unsafe { *(addr as *const u8) }, 2);
+}
@@ -0,0 +1,30 @@
+// Miri uses the layout of the target, not of the host it runs on.
+
+use std::mem::{align_of, size_of};
+use std::os::raw::c_char;
+
+fn main() {
+    assert_eq!(size_of::<usize>(), size_of::<*const u8>());
+    assert_eq!(size_of::<usize>() * 8, if cfg!(target_pointer_width = "64") { 64 } else { 32 });
+    assert_eq!(u64::max_value() as usize as u64, usize::max_value() as u64);
+
+    // `char` is unsigned on ARM, and signed on x86.
+    let c = 200u8 as c_char;
+    if cfg!(any(target_arch = "aarch64", target_arch = "arm")) {
+        assert!(c as i32 > 0);
+    } else if cfg!(any(target_arch = "x86_64", target_arch = "x86")) {
+        assert!((c as i32) < 0);
+    }
+
+    // `u64` is only 4-aligned on 32-bit x86 (except on Windows).
+    if cfg!(all(target_arch = "x86", not(windows))) {
+        assert_eq!(align_of::<u64>(), 4);
+    } else {
+        assert_eq!(align_of::<u64>(), 8);
+    }
+
+    // Integer arithmetic wraps at the target's pointer width.
+    let big = usize::max_value();
+    assert_eq!(big.wrapping_add(1), 0);
+    assert_eq!(big.count_ones() as usize, size_of::<usize>() * 8);
+}
@@ -0,0 +1,37 @@
+// compile-flags: -Zmiri-tree-borrows
+// Programs that Stacked Borrows rejects, but Tree Borrows accepts.
+
+fn main() {
+    read_parent_before_write();
+    sibling_shared_reference();
+    interior_mutability();
+}
+
+// Reading through the parent does not invalidate a mutable reborrow that was not written to yet.
+fn read_parent_before_write() {
+    let x = &mut 0;
+    let y = &mut *x;
+    let _v = *x;
+    *y = 1;
+    assert_eq!(*x, 1);
+}
+
+// Writing through `y` only disables `r`, which is not used afterwards.
+fn sibling_shared_reference() {
+    let x = &mut 0;
+    let y = &mut *x;
+    let r = &*x;
+    assert_eq!(*r, 0);
+    *y = 2;
+    assert_eq!(*x, 2);
+}
+
+// A mutable reference to a `Cell` allows foreign writes until it is written to.
+fn interior_mutability() {
+    use std::cell::Cell;
+    let c = &mut Cell::new(0);
+    let raw = c as *mut Cell<i32>;
+    let m = unsafe { &mut *raw };
+    c.set(1);
+    assert_eq!(m.get(), 1);
+}
@@ -0,0 +1,41 @@
+//ignore-windows: Uses POSIX APIs
+
+#![feature(rustc_private)]
+#![allow(unused_extern_crates)] // rustc bug https://github.com/rust-lang/rust/issues/56098
+
+extern crate libc;
+
+use libc::c_int;
+
+#[allow(non_camel_case_types)]
+type wint_t = u32;
+
+const WEOF: wint_t = 0xffff_ffff;
+
+extern {
+    fn towupper(wc: wint_t) -> wint_t;
+    fn towlower(wc: wint_t) -> wint_t;
+    fn iswspace(wc: wint_t) -> c_int;
+    fn iswdigit(wc: wint_t) -> c_int;
+    fn iswupper(wc: wint_t) -> c_int;
+    fn iswlower(wc: wint_t) -> c_int;
+}
+
+fn main() {
+    unsafe {
+        assert_eq!(towupper('a' as wint_t), 'A' as wint_t);
+        assert_eq!(towlower('A' as wint_t), 'a' as wint_t);
+        assert_eq!(towupper('é' as wint_t), 'é' as wint_t);
+        assert_eq!(towlower(WEOF), WEOF);
+
+        assert_ne!(iswspace('\t' as wint_t), 0);
+        assert_ne!(iswdigit('9' as wint_t), 0);
+        assert_ne!(iswupper('X' as wint_t), 0);
+        assert_ne!(iswlower('x' as wint_t), 0);
+        assert_eq!(iswlower('X' as wint_t), 0);
+        // Only ASCII characters are classified in the C locale.
+        assert_eq!(iswspace('\u{3000}' as wint_t), 0);
+        assert_eq!(iswlower('é' as wint_t), 0);
+        assert_eq!(iswdigit(WEOF), 0);
+    }
+}
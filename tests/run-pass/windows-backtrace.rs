@@ -0,0 +1,34 @@
+#[cfg(windows)]
+fn main() {
+    use std::ffi::c_void;
+
+    extern "system" {
+        fn RtlCaptureStackBackTrace(
+            frames_to_skip: u32,
+            frames_to_capture: u32,
+            back_trace: *mut *mut c_void,
+            back_trace_hash: *mut u32,
+        ) -> u16;
+    }
+
+    let mut frames = [std::ptr::null_mut(); 64];
+    let mut hash = 0;
+    let n = unsafe { RtlCaptureStackBackTrace(0, 64, frames.as_mut_ptr(), &mut hash) };
+    assert!(n > 0);
+    for frame in &frames[..n as usize] {
+        assert!(!frame.is_null());
+    }
+    // The frames are stable, and so is their hash.
+    let mut again = [std::ptr::null_mut(); 64];
+    let mut hash_again = 0;
+    let m = unsafe { RtlCaptureStackBackTrace(0, 64, again.as_mut_ptr(), &mut hash_again) };
+    assert_eq!((n, hash), (m, hash_again));
+    assert_eq!(&frames[..], &again[..]);
+    // Skipping the current frame drops the first entry.
+    let skipped = unsafe { RtlCaptureStackBackTrace(1, 64, again.as_mut_ptr(), std::ptr::null_mut()) };
+    assert_eq!(skipped, n - 1);
+    assert_eq!(again[0], frames[1]);
+}
+
+#[cfg(not(windows))]
+fn main() {}
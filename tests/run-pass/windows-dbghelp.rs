@@ -0,0 +1,50 @@
+#[cfg(windows)]
+fn main() {
+    use std::ffi::c_void;
+
+    #[repr(C)]
+    struct SymbolInfo {
+        size_of_struct: u32,
+        type_index: u32,
+        reserved: [u64; 2],
+        index: u32,
+        size: u32,
+        mod_base: u64,
+        flags: u32,
+        value: u64,
+        address: u64,
+        register: u32,
+        scope: u32,
+        tag: u32,
+        name_len: u32,
+        max_name_len: u32,
+        name: [u8; 16],
+    }
+
+    extern "system" {
+        fn SymInitialize(process: *mut c_void, search_path: *const u8, invade: i32) -> i32;
+        fn SymFromAddr(process: *mut c_void, address: u64, displacement: *mut u64, symbol: *mut SymbolInfo) -> i32;
+        fn SymGetLineFromAddr64(process: *mut c_void, address: u64, displacement: *mut u32, line: *mut c_void) -> i32;
+    }
+
+    let process = std::ptr::null_mut();
+    unsafe {
+        assert_eq!(SymInitialize(process, std::ptr::null(), 1), 1);
+
+        let mut symbol: SymbolInfo = std::mem::zeroed();
+        symbol.size_of_struct = 88;
+        symbol.max_name_len = 16;
+        let mut displacement = 42;
+        assert_eq!(SymFromAddr(process, 0x1234, &mut displacement, &mut symbol), 1);
+        assert_eq!(displacement, 0);
+        assert_eq!(symbol.address, 0x1234);
+        assert_eq!(symbol.name_len, 2);
+        assert_eq!(&symbol.name[..3], b"??\0");
+
+        let mut line_displacement = 0;
+        assert_eq!(SymGetLineFromAddr64(process, 0x1234, &mut line_displacement, std::ptr::null_mut()), 0);
+    }
+}
+
+#[cfg(not(windows))]
+fn main() {}
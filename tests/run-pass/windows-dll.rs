@@ -0,0 +1,22 @@
+#[cfg(windows)]
+fn main() {
+    extern "system" {
+        fn LoadLibraryW(name: *const u16) -> isize;
+        fn GetProcAddress(module: isize, name: *const u8) -> isize;
+        fn FreeLibrary(module: isize) -> i32;
+        fn GetLastError() -> u32;
+    }
+
+    let name: Vec<u16> = "plugin.dll".encode_utf16().chain(Some(0)).collect();
+    unsafe {
+        assert_eq!(LoadLibraryW(name.as_ptr()), 0);
+        assert_eq!(GetLastError(), 126); // ERROR_MOD_NOT_FOUND
+        assert_eq!(GetProcAddress(0, b"plugin_init\0".as_ptr()), 0);
+        assert_eq!(GetLastError(), 127); // ERROR_PROC_NOT_FOUND
+        assert_eq!(FreeLibrary(0), 1);
+        assert_eq!(FreeLibrary(42), 0);
+    }
+}
+
+#[cfg(not(windows))]
+fn main() {}
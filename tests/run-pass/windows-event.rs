@@ -0,0 +1,44 @@
+#[cfg(windows)]
+fn main() {
+    use std::ptr;
+
+    extern "system" {
+        fn CreateEventW(attributes: *mut u8, manual_reset: i32, initial_state: i32, name: *const u16) -> isize;
+        fn SetEvent(event: isize) -> i32;
+        fn ResetEvent(event: isize) -> i32;
+        fn PulseEvent(event: isize) -> i32;
+        fn WaitForSingleObject(handle: isize, timeout: u32) -> u32;
+        fn CloseHandle(handle: isize) -> i32;
+        fn GetLastError() -> u32;
+    }
+
+    const WAIT_TIMEOUT: u32 = 0x102;
+
+    unsafe {
+        // An auto-reset event is reset by a successful wait.
+        let auto = CreateEventW(ptr::null_mut(), 0, 1, ptr::null());
+        assert_eq!(WaitForSingleObject(auto, 10), 0);
+        assert_eq!(WaitForSingleObject(auto, 10), WAIT_TIMEOUT);
+        assert_eq!(SetEvent(auto), 1);
+        assert_eq!(WaitForSingleObject(auto, 10), 0);
+
+        // A manual-reset event stays signaled until it is reset.
+        let manual = CreateEventW(ptr::null_mut(), 1, 0, ptr::null());
+        assert_eq!(WaitForSingleObject(manual, 0), WAIT_TIMEOUT);
+        assert_eq!(SetEvent(manual), 1);
+        assert_eq!(WaitForSingleObject(manual, 0), 0);
+        assert_eq!(WaitForSingleObject(manual, 0), 0);
+        assert_eq!(ResetEvent(manual), 1);
+        assert_eq!(WaitForSingleObject(manual, 0), WAIT_TIMEOUT);
+        assert_eq!(PulseEvent(manual), 1);
+        assert_eq!(WaitForSingleObject(manual, 0), WAIT_TIMEOUT);
+
+        assert_eq!(SetEvent(1234), 0);
+        assert_eq!(GetLastError(), 6); // ERROR_INVALID_HANDLE
+        assert_eq!(CloseHandle(auto), 1);
+        assert_eq!(CloseHandle(manual), 1);
+    }
+}
+
+#[cfg(not(windows))]
+fn main() {}
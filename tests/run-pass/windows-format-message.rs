@@ -0,0 +1,41 @@
+#[cfg(windows)]
+fn main() {
+    use std::ffi::c_void;
+
+    extern "system" {
+        fn FormatMessageW(
+            flags: u32,
+            source: *const c_void,
+            message_id: u32,
+            language_id: u32,
+            buffer: *mut u16,
+            size: u32,
+            arguments: *mut c_void,
+        ) -> u32;
+        fn GetLastError() -> u32;
+    }
+
+    const FORMAT_MESSAGE_FROM_SYSTEM: u32 = 0x1000;
+    const FORMAT_MESSAGE_IGNORE_INSERTS: u32 = 0x200;
+    let flags = FORMAT_MESSAGE_FROM_SYSTEM | FORMAT_MESSAGE_IGNORE_INSERTS;
+
+    let mut buf = [0u16; 256];
+    let format = |code, buf: &mut [u16]| unsafe {
+        FormatMessageW(flags, std::ptr::null(), code, 0, buf.as_mut_ptr(), buf.len() as u32, std::ptr::null_mut())
+    };
+    let len = format(5, &mut buf);
+    assert_eq!(String::from_utf16(&buf[..len as usize]).unwrap(), "Access is denied.\r\n");
+    assert_eq!(buf[len as usize], 0);
+
+    assert_eq!(format(5, &mut buf[..4]), 0);
+    assert_eq!(unsafe { GetLastError() }, 122); // ERROR_INSUFFICIENT_BUFFER
+    assert_eq!(format(0xdead, &mut buf), 0);
+    assert_eq!(unsafe { GetLastError() }, 317); // ERROR_MR_MID_NOT_FOUND
+
+    // This is how the standard library uses it.
+    let err = std::io::Error::from_raw_os_error(2);
+    assert!(err.to_string().starts_with("The system cannot find the file specified."));
+}
+
+#[cfg(not(windows))]
+fn main() {}
@@ -0,0 +1,40 @@
+#[cfg(windows)]
+fn main() {
+    extern "system" {
+        fn GetStdHandle(which: u32) -> isize;
+        fn FlushFileBuffers(handle: isize) -> i32;
+        fn SetEndOfFile(handle: isize) -> i32;
+        fn SetFilePointerEx(handle: isize, distance: i64, new_pointer: *mut i64, method: u32) -> i32;
+        fn GetFileSize(handle: isize, size_high: *mut u32) -> u32;
+        fn GetFileSizeEx(handle: isize, size: *mut i64) -> i32;
+        fn GetFileType(handle: isize) -> u32;
+        fn GetLastError() -> u32;
+    }
+
+    const STD_OUTPUT_HANDLE: u32 = -11i32 as u32;
+
+    unsafe {
+        let stdout = GetStdHandle(STD_OUTPUT_HANDLE);
+        assert_eq!(FlushFileBuffers(stdout), 1);
+        assert_eq!(FlushFileBuffers(1234), 0);
+        assert_eq!(GetLastError(), 6); // ERROR_INVALID_HANDLE
+
+        // The console cannot be seeked or truncated.
+        assert_eq!(SetEndOfFile(stdout), 0);
+        let mut pos = 0;
+        assert_eq!(SetFilePointerEx(stdout, 0, &mut pos, 0), 0);
+        assert_eq!(GetLastError(), 1); // ERROR_INVALID_FUNCTION
+        assert_eq!(SetFilePointerEx(1234, 0, &mut pos, 0), 0);
+        assert_eq!(GetLastError(), 6);
+
+        // The console is a character device without a size.
+        assert_eq!(GetFileType(stdout), 2);
+        assert_eq!(GetFileType(1234), 0);
+        assert_eq!(GetFileSize(stdout, std::ptr::null_mut()), 0xFFFF_FFFF);
+        let mut size = 0;
+        assert_eq!(GetFileSizeEx(stdout, &mut size), 0);
+    }
+}
+
+#[cfg(not(windows))]
+fn main() {}
@@ -0,0 +1,55 @@
+#[cfg(windows)]
+fn main() {
+    use std::ptr;
+
+    extern "system" {
+        fn CreateMutexW(attributes: *mut u8, initial_owner: i32, name: *const u16) -> isize;
+        fn ReleaseMutex(mutex: isize) -> i32;
+        fn WaitForSingleObject(handle: isize, timeout: u32) -> u32;
+        fn WaitForMultipleObjects(count: u32, handles: *const isize, wait_all: i32, timeout: u32) -> u32;
+        fn CloseHandle(handle: isize) -> i32;
+        fn GetLastError() -> u32;
+    }
+
+    const INFINITE: u32 = 0xFFFF_FFFF;
+
+    unsafe {
+        // An anonymous mutex can be acquired recursively by its owner.
+        let mutex = CreateMutexW(ptr::null_mut(), 1, ptr::null());
+        assert_ne!(mutex, 0);
+        assert_eq!(WaitForSingleObject(mutex, INFINITE), 0);
+        assert_eq!(ReleaseMutex(mutex), 1);
+        assert_eq!(ReleaseMutex(mutex), 1);
+        assert_eq!(ReleaseMutex(mutex), 0);
+        assert_eq!(GetLastError(), 288); // ERROR_NOT_OWNER
+
+        // Named mutexes are shared.
+        let name: Vec<u16> = "miri-mutex".encode_utf16().chain(Some(0)).collect();
+        let named = CreateMutexW(ptr::null_mut(), 0, name.as_ptr());
+        assert_eq!(GetLastError(), 0);
+        let again = CreateMutexW(ptr::null_mut(), 1, name.as_ptr());
+        assert_eq!(GetLastError(), 183); // ERROR_ALREADY_EXISTS
+        assert_ne!(again, named);
+        // `initial_owner` is ignored when the mutex already exists.
+        assert_eq!(ReleaseMutex(again), 0);
+        assert_eq!(WaitForSingleObject(named, 0), 0);
+        assert_eq!(ReleaseMutex(again), 1);
+
+        let handles = [mutex, named];
+        assert_eq!(WaitForMultipleObjects(2, handles.as_ptr(), 0, INFINITE), 0);
+        assert_eq!(WaitForMultipleObjects(2, handles.as_ptr(), 1, INFINITE), 0);
+        assert_eq!(ReleaseMutex(mutex), 1);
+        assert_eq!(ReleaseMutex(mutex), 1);
+        assert_eq!(ReleaseMutex(named), 1);
+
+        assert_eq!(CloseHandle(mutex), 1);
+        assert_eq!(CloseHandle(mutex), 0);
+        assert_eq!(GetLastError(), 6); // ERROR_INVALID_HANDLE
+        assert_eq!(WaitForSingleObject(mutex, 0), 0xFFFF_FFFF);
+        assert_eq!(CloseHandle(named), 1);
+        assert_eq!(CloseHandle(again), 1);
+    }
+}
+
+#[cfg(not(windows))]
+fn main() {}
@@ -0,0 +1,38 @@
+#[cfg(windows)]
+fn main() {
+    extern "system" {
+        fn GetFullPathNameW(name: *const u16, buf_len: u32, buf: *mut u16, file_part: *mut *mut u16) -> u32;
+        fn GetFileAttributesW(name: *const u16) -> u32;
+        fn CreateDirectoryW(name: *const u16, security_attributes: *mut u8) -> i32;
+        fn GetLastError() -> u32;
+    }
+
+    fn wide(s: &str) -> Vec<u16> {
+        s.encode_utf16().chain(Some(0)).collect()
+    }
+
+    unsafe {
+        let name = wide("foo\\bar.txt");
+        let mut buf = [0u16; 64];
+        let mut file_part = std::ptr::null_mut();
+        let len = GetFullPathNameW(name.as_ptr(), 64, buf.as_mut_ptr(), &mut file_part);
+        assert_eq!(String::from_utf16(&buf[..len as usize]).unwrap(), "C:\\miri\\foo\\bar.txt");
+        assert_eq!(file_part, buf.as_mut_ptr().add(len as usize - 7));
+        // A buffer that is too small makes us return the required size.
+        assert_eq!(GetFullPathNameW(name.as_ptr(), 4, buf.as_mut_ptr(), std::ptr::null_mut()), len + 1);
+        let absolute = wide("D:\\data");
+        let len = GetFullPathNameW(absolute.as_ptr(), 64, buf.as_mut_ptr(), std::ptr::null_mut());
+        assert_eq!(String::from_utf16(&buf[..len as usize]).unwrap(), "D:\\data");
+
+        let dir = wide("new_dir");
+        assert_eq!(GetFileAttributesW(dir.as_ptr()), 0xFFFF_FFFF);
+        assert_eq!(GetLastError(), 2); // ERROR_FILE_NOT_FOUND
+        assert_eq!(CreateDirectoryW(dir.as_ptr(), std::ptr::null_mut()), 1);
+        assert_eq!(GetFileAttributesW(wide("C:\\MIRI\\New_Dir\\").as_ptr()), 0x10);
+        assert_eq!(CreateDirectoryW(dir.as_ptr(), std::ptr::null_mut()), 0);
+        assert_eq!(GetLastError(), 183); // ERROR_ALREADY_EXISTS
+    }
+}
+
+#[cfg(not(windows))]
+fn main() {}
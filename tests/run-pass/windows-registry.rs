@@ -0,0 +1,34 @@
+#[cfg(windows)]
+fn main() {
+    use std::ffi::c_void;
+
+    extern "system" {
+        fn RegOpenKeyExW(key: isize, sub_key: *const u16, options: u32, sam: u32, result: *mut isize) -> i32;
+        fn RegQueryValueExW(
+            key: isize,
+            value_name: *const u16,
+            reserved: *mut u32,
+            value_type: *mut u32,
+            data: *mut c_void,
+            data_len: *mut u32,
+        ) -> i32;
+        fn RegCloseKey(key: isize) -> i32;
+    }
+
+    const HKEY_LOCAL_MACHINE: isize = 0x8000_0002u32 as i32 as isize;
+    const KEY_READ: u32 = 0x20019;
+    const ERROR_FILE_NOT_FOUND: i32 = 2;
+
+    let sub_key: Vec<u16> = "SOFTWARE\\Miri".encode_utf16().chain(Some(0)).collect();
+    let value: Vec<u16> = "Setting".encode_utf16().chain(Some(0)).collect();
+    let mut key = 0;
+    let null = std::ptr::null_mut();
+    unsafe {
+        assert_eq!(RegOpenKeyExW(HKEY_LOCAL_MACHINE, sub_key.as_ptr(), 0, KEY_READ, &mut key), ERROR_FILE_NOT_FOUND);
+        assert_eq!(RegQueryValueExW(HKEY_LOCAL_MACHINE, value.as_ptr(), null, null, null as *mut c_void, null), ERROR_FILE_NOT_FOUND);
+        assert_eq!(RegCloseKey(HKEY_LOCAL_MACHINE), 0);
+    }
+}
+
+#[cfg(not(windows))]
+fn main() {}
@@ -0,0 +1,55 @@
+#[cfg(windows)]
+fn main() {
+    use std::ptr;
+
+    extern "system" {
+        fn CreateSemaphoreW(attributes: *mut u8, initial: i32, maximum: i32, name: *const u16) -> isize;
+        fn OpenSemaphoreW(access: u32, inherit: i32, name: *const u16) -> isize;
+        fn ReleaseSemaphore(semaphore: isize, release: i32, previous: *mut i32) -> i32;
+        fn CreateEventW(attributes: *mut u8, manual_reset: i32, initial_state: i32, name: *const u16) -> isize;
+        fn WaitForSingleObject(handle: isize, timeout: u32) -> u32;
+        fn CloseHandle(handle: isize) -> i32;
+        fn GetLastError() -> u32;
+    }
+
+    const SEMAPHORE_ALL_ACCESS: u32 = 0x1F0003;
+    const WAIT_TIMEOUT: u32 = 0x102;
+
+    unsafe {
+        assert_eq!(CreateSemaphoreW(ptr::null_mut(), 3, 2, ptr::null()), 0);
+        assert_eq!(GetLastError(), 87); // ERROR_INVALID_PARAMETER
+
+        let name: Vec<u16> = "miri-semaphore".encode_utf16().chain(Some(0)).collect();
+        let semaphore = CreateSemaphoreW(ptr::null_mut(), 1, 2, name.as_ptr());
+        assert_eq!(WaitForSingleObject(semaphore, 0), 0);
+        assert_eq!(WaitForSingleObject(semaphore, 0), WAIT_TIMEOUT);
+
+        let opened = OpenSemaphoreW(SEMAPHORE_ALL_ACCESS, 0, name.as_ptr());
+        assert_ne!(opened, 0);
+        let mut previous = -1;
+        assert_eq!(ReleaseSemaphore(opened, 2, &mut previous), 1);
+        assert_eq!(previous, 0);
+        assert_eq!(ReleaseSemaphore(semaphore, 1, ptr::null_mut()), 0);
+        assert_eq!(GetLastError(), 298); // ERROR_TOO_MANY_POSTS
+        assert_eq!(WaitForSingleObject(semaphore, 0), 0);
+        assert_eq!(WaitForSingleObject(opened, 0), 0);
+        assert_eq!(WaitForSingleObject(opened, 0), WAIT_TIMEOUT);
+
+        let missing: Vec<u16> = "missing".encode_utf16().chain(Some(0)).collect();
+        assert_eq!(OpenSemaphoreW(SEMAPHORE_ALL_ACCESS, 0, missing.as_ptr()), 0);
+        assert_eq!(GetLastError(), 2); // ERROR_FILE_NOT_FOUND
+
+        // Kernel objects share one namespace.
+        let event_name: Vec<u16> = "miri-event".encode_utf16().chain(Some(0)).collect();
+        let event = CreateEventW(ptr::null_mut(), 0, 0, event_name.as_ptr());
+        assert_eq!(OpenSemaphoreW(SEMAPHORE_ALL_ACCESS, 0, event_name.as_ptr()), 0);
+        assert_eq!(GetLastError(), 6); // ERROR_INVALID_HANDLE
+
+        assert_eq!(CloseHandle(event), 1);
+        assert_eq!(CloseHandle(opened), 1);
+        assert_eq!(CloseHandle(semaphore), 1);
+    }
+}
+
+#[cfg(not(windows))]
+fn main() {}
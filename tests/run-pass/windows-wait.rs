@@ -0,0 +1,25 @@
+#[cfg(windows)]
+fn main() {
+    extern "system" {
+        fn WaitForSingleObject(handle: isize, timeout: u32) -> u32;
+        fn WaitForMultipleObjects(count: u32, handles: *const isize, wait_all: i32, timeout: u32) -> u32;
+        fn GetLastError() -> u32;
+    }
+
+    const INFINITE: u32 = 0xFFFF_FFFF;
+    const WAIT_FAILED: u32 = 0xFFFF_FFFF;
+
+    unsafe {
+        assert_eq!(WaitForSingleObject(1234, INFINITE), WAIT_FAILED);
+        assert_eq!(GetLastError(), 6); // ERROR_INVALID_HANDLE
+
+        let handles = [1234, 5678];
+        assert_eq!(WaitForMultipleObjects(2, handles.as_ptr(), 1, 0), WAIT_FAILED);
+        assert_eq!(GetLastError(), 6);
+        assert_eq!(WaitForMultipleObjects(0, handles.as_ptr(), 1, 0), WAIT_FAILED);
+        assert_eq!(GetLastError(), 87); // ERROR_INVALID_PARAMETER
+    }
+}
+
+#[cfg(not(windows))]
+fn main() {}
@@ -21,6 +21,7 @@ function run_tests {
     # "miri test" has built the sysroot for us, now this should pass without
     # any interactive questions.
     test-cargo-miri/run-test.py
+    tests/output-flags/run-test.py
 }
 
 echo "Test host architecture"
@@ -30,3 +31,15 @@ echo
 echo "Test foreign architecture ($FOREIGN_TARGET)"
 MIRI_TEST_TARGET="$FOREIGN_TARGET" run_tests
 echo
+
+if [ "$TRAVIS_OS_NAME" == linux ]; then
+  # A 64-bit target of a different architecture, to make sure we do not rely on the host's
+  # layout anywhere.
+  echo "Test foreign architecture (aarch64-unknown-linux-gnu)"
+  MIRI_TEST_TARGET=aarch64-unknown-linux-gnu run_tests
+  echo
+  # A big-endian target, to make sure we do not rely on the host's byte order either.
+  echo "Test foreign architecture (mips64-unknown-linux-gnuabi64)"
+  MIRI_TEST_TARGET=mips64-unknown-linux-gnuabi64 run_tests
+  echo
+fi